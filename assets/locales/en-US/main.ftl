@@ -0,0 +1,15 @@
+# Interface text. Lessons are translated with `lesson-<id>-title`,
+# `lesson-<id>-description` and `lesson-<id>-element-<n>` messages.
+
+app-title = Learn Liberty - Educational RPG
+achievement-unlocked = Achievement unlocked: { $name }
+lesson-complete = Lesson complete: { $title }
+lessons-completed = { $count ->
+    [0] No lessons completed yet
+    [one] One lesson completed
+   *[other] { $count } lessons completed
+}
+items-collected = { $count ->
+    [one] You collected { $count } { $item }
+   *[other] You collected { $count } × { $item }
+}
@@ -0,0 +1,12 @@
+app-title = Learn Liberty - RPG educativo
+achievement-unlocked = Logro desbloqueado: { $name }
+lesson-complete = Lección completada: { $title }
+lessons-completed = { $count ->
+    [0] Todavía no has completado ninguna lección
+    [one] Una lección completada
+   *[other] { $count } lecciones completadas
+}
+items-collected = { $count ->
+    [one] Has conseguido { $count } { $item }
+   *[other] Has conseguido { $count } × { $item }
+}
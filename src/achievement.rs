@@ -0,0 +1,410 @@
+//! Achievement module for Learn Liberty
+//!
+//! This module loads achievement definitions from a data file and unlocks
+//! them as game and learning events arrive. Each learner keeps counters of
+//! what they have done so far, so every event only updates those counters
+//! and re-checks the achievements it can affect. Unlocks are queued as
+//! notifications for the game to show briefly on screen.
+
+use crate::quest::QuestTrigger;
+use crate::quiz::QuizResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::Path;
+
+/// Seconds an unlock notification stays on screen
+pub const TOAST_SECONDS: f64 = 4.0;
+
+fn default_count() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    LessonsCompleted {
+        count: u32,
+    },
+    /// Score 100% on `count` different quizzes
+    PerfectQuiz {
+        #[serde(default = "default_count")]
+        count: u32,
+    },
+    VisitRegions {
+        regions: Vec<String>,
+    },
+    CollectItem {
+        item_id: String,
+        quantity: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AchievementDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Icon image, relative to the assets directory
+    #[serde(default)]
+    pub icon: Option<String>,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Deserialize)]
+struct AchievementFile {
+    #[serde(default)]
+    achievement: Vec<AchievementDefinition>,
+}
+
+/// All achievements known to the game, in file order
+#[derive(Debug, Default)]
+pub struct AchievementDatabase {
+    achievements: Vec<AchievementDefinition>,
+}
+
+impl AchievementDatabase {
+    pub fn from_toml_str(source: &str) -> anyhow::Result<Self> {
+        let file: AchievementFile = toml::from_str(source)?;
+        let mut ids = BTreeSet::new();
+
+        for definition in &file.achievement {
+            if !ids.insert(definition.id.as_str()) {
+                anyhow::bail!("Duplicate achievement id: {}", definition.id);
+            }
+            let empty = match &definition.condition {
+                Condition::LessonsCompleted { count } | Condition::PerfectQuiz { count } => {
+                    *count == 0
+                }
+                Condition::VisitRegions { regions } => regions.is_empty(),
+                Condition::CollectItem { quantity, .. } => *quantity == 0,
+            };
+            if empty {
+                anyhow::bail!(
+                    "Achievement {} can never be earned or is free",
+                    definition.id
+                );
+            }
+        }
+
+        Ok(Self {
+            achievements: file.achievement,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&source)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&AchievementDefinition> {
+        self.achievements.iter().find(|a| a.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.achievements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.achievements.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AchievementDefinition> {
+        self.achievements.iter()
+    }
+}
+
+/// Something that happened which achievements can count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AchievementEvent {
+    LessonCompleted {
+        lesson_id: String,
+    },
+    QuizScored {
+        quiz_id: String,
+        score: f32,
+    },
+    RegionEntered {
+        region_id: String,
+    },
+    /// The player now holds `total` of the given item
+    ItemCollected {
+        item_id: String,
+        total: u32,
+    },
+}
+
+impl AchievementEvent {
+    /// The achievement event matching a quest trigger, if any
+    pub fn from_trigger(trigger: &QuestTrigger) -> Option<Self> {
+        match trigger {
+            QuestTrigger::TalkedTo(_) => None,
+            QuestTrigger::EnteredZone(zone_id) => Some(AchievementEvent::RegionEntered {
+                region_id: zone_id.clone(),
+            }),
+            QuestTrigger::LessonCompleted { lesson_id, .. } => {
+                Some(AchievementEvent::LessonCompleted {
+                    lesson_id: lesson_id.clone(),
+                })
+            }
+            QuestTrigger::ItemCollected { item_id, total } => {
+                Some(AchievementEvent::ItemCollected {
+                    item_id: item_id.clone(),
+                    total: *total,
+                })
+            }
+        }
+    }
+
+    pub fn from_quiz_result(result: &QuizResult) -> Self {
+        AchievementEvent::QuizScored {
+            quiz_id: result.quiz_id.clone(),
+            score: result.score,
+        }
+    }
+
+    fn affects(&self, condition: &Condition) -> bool {
+        matches!(
+            (self, condition),
+            (
+                AchievementEvent::LessonCompleted { .. },
+                Condition::LessonsCompleted { .. }
+            ) | (
+                AchievementEvent::QuizScored { .. },
+                Condition::PerfectQuiz { .. }
+            ) | (
+                AchievementEvent::RegionEntered { .. },
+                Condition::VisitRegions { .. }
+            ) | (
+                AchievementEvent::ItemCollected { .. },
+                Condition::CollectItem { .. }
+            )
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AchievementUnlocked {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+}
+
+/// One learner's counters and unlocked achievements
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementProgress {
+    pub completed_lessons: BTreeSet<String>,
+    pub perfect_quizzes: BTreeSet<String>,
+    pub regions: BTreeSet<String>,
+    /// Highest amount held of each item
+    pub items: BTreeMap<String, u32>,
+    /// Unlock time of each achievement, in seconds since the Unix epoch
+    pub unlocked: BTreeMap<String, u64>,
+}
+
+impl AchievementProgress {
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains_key(id)
+    }
+
+    /// Fraction of the condition met so far
+    pub fn progress(&self, condition: &Condition) -> f32 {
+        let ratio = |done: usize, needed: usize| (done as f32 / needed.max(1) as f32).min(1.0);
+        match condition {
+            Condition::LessonsCompleted { count } => {
+                ratio(self.completed_lessons.len(), *count as usize)
+            }
+            Condition::PerfectQuiz { count } => ratio(self.perfect_quizzes.len(), *count as usize),
+            Condition::VisitRegions { regions } => ratio(
+                regions.iter().filter(|r| self.regions.contains(*r)).count(),
+                regions.len(),
+            ),
+            Condition::CollectItem { item_id, quantity } => ratio(
+                self.items.get(item_id).copied().unwrap_or(0) as usize,
+                *quantity as usize,
+            ),
+        }
+    }
+
+    /// Count the event and unlock every achievement it completes
+    pub fn handle(
+        &mut self,
+        database: &AchievementDatabase,
+        event: &AchievementEvent,
+        timestamp: u64,
+    ) -> Vec<AchievementUnlocked> {
+        match event {
+            AchievementEvent::LessonCompleted { lesson_id } => {
+                self.completed_lessons.insert(lesson_id.clone());
+            }
+            AchievementEvent::QuizScored { quiz_id, score } => {
+                if *score >= 1.0 {
+                    self.perfect_quizzes.insert(quiz_id.clone());
+                }
+            }
+            AchievementEvent::RegionEntered { region_id } => {
+                self.regions.insert(region_id.clone());
+            }
+            AchievementEvent::ItemCollected { item_id, total } => {
+                let held = self.items.entry(item_id.clone()).or_insert(0);
+                *held = (*held).max(*total);
+            }
+        }
+
+        let mut unlocked = Vec::new();
+        for definition in database.iter() {
+            if self.is_unlocked(&definition.id)
+                || !event.affects(&definition.condition)
+                || self.progress(&definition.condition) < 1.0
+            {
+                continue;
+            }
+            self.unlocked.insert(definition.id.clone(), timestamp);
+            unlocked.push(AchievementUnlocked {
+                id: definition.id.clone(),
+                name: definition.name.clone(),
+                icon: definition.icon.clone(),
+            });
+        }
+        unlocked
+    }
+}
+
+/// Unlock notifications waiting to be shown, one at a time
+#[derive(Debug, Clone, Default)]
+pub struct AchievementToasts {
+    queue: VecDeque<AchievementUnlocked>,
+    shown_for: f64,
+}
+
+impl AchievementToasts {
+    pub fn push(&mut self, unlocked: impl IntoIterator<Item = AchievementUnlocked>) {
+        self.queue.extend(unlocked);
+    }
+
+    /// The notification currently on screen
+    pub fn current(&self) -> Option<&AchievementUnlocked> {
+        self.queue.front()
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        if self.queue.is_empty() {
+            return;
+        }
+        self.shown_for += delta_time;
+        if self.shown_for >= TOAST_SECONDS {
+            self.queue.pop_front();
+            self.shown_for = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> AchievementDatabase {
+        AchievementDatabase::load("assets/data/achievements.toml").unwrap()
+    }
+
+    fn lesson(id: &str) -> AchievementEvent {
+        AchievementEvent::LessonCompleted {
+            lesson_id: id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_load_shipped_achievements() {
+        let database = database();
+        assert_eq!(database.len(), 5);
+        assert!(matches!(
+            database.get("scholar").unwrap().condition,
+            Condition::LessonsCompleted { count: 3 }
+        ));
+
+        let duplicate = "[[achievement]]\nid = \"a\"\nname = \"A\"\ndescription = \"\"\n\
+                         condition = { kind = \"perfect_quiz\" }\n";
+        assert!(AchievementDatabase::from_toml_str(&duplicate.repeat(2)).is_err());
+        let free = "[[achievement]]\nid = \"a\"\nname = \"A\"\ndescription = \"\"\n\
+                    condition = { kind = \"lessons_completed\", count = 0 }\n";
+        assert!(AchievementDatabase::from_toml_str(free).is_err());
+    }
+
+    #[test]
+    fn test_lesson_events_unlock_incrementally() {
+        let database = database();
+        let mut progress = AchievementProgress::default();
+
+        let unlocked = progress.handle(&database, &lesson("01"), 10);
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].id, "first_steps");
+
+        assert!(progress.handle(&database, &lesson("01"), 11).is_empty());
+        assert!(progress.handle(&database, &lesson("02"), 12).is_empty());
+        let unlocked = progress.handle(&database, &lesson("03"), 13);
+        assert_eq!(unlocked[0].id, "scholar");
+        assert_eq!(progress.unlocked.get("scholar"), Some(&13));
+        assert!(progress.handle(&database, &lesson("04"), 14).is_empty());
+    }
+
+    #[test]
+    fn test_regions_items_and_quizzes() {
+        let database = database();
+        let mut progress = AchievementProgress::default();
+
+        for region in ["village", "library", "harbor"] {
+            let trigger = QuestTrigger::EnteredZone(region.to_string());
+            let event = AchievementEvent::from_trigger(&trigger).unwrap();
+            assert!(progress.handle(&database, &event, 0).is_empty());
+        }
+        let explorer = &database.get("explorer").unwrap().condition;
+        assert_eq!(progress.progress(explorer), 0.75);
+        let forge = AchievementEvent::RegionEntered {
+            region_id: "forge".to_string(),
+        };
+        assert_eq!(progress.handle(&database, &forge, 0)[0].id, "explorer");
+
+        let coins = |total| AchievementEvent::ItemCollected {
+            item_id: "crab_coin".to_string(),
+            total,
+        };
+        assert!(progress.handle(&database, &coins(9), 0).is_empty());
+        assert_eq!(
+            progress.handle(&database, &coins(10), 0)[0].id,
+            "crab_collector"
+        );
+
+        let quiz = |score| AchievementEvent::QuizScored {
+            quiz_id: "ownership".to_string(),
+            score,
+        };
+        assert!(progress.handle(&database, &quiz(0.9), 0).is_empty());
+        assert_eq!(
+            progress.handle(&database, &quiz(1.0), 0)[0].id,
+            "perfectionist"
+        );
+    }
+
+    #[test]
+    fn test_toasts_show_one_at_a_time() {
+        let database = database();
+        let mut progress = AchievementProgress::default();
+        let mut toasts = AchievementToasts::default();
+
+        toasts.push(progress.handle(&database, &lesson("01"), 0));
+        toasts.push(progress.handle(
+            &database,
+            &AchievementEvent::QuizScored {
+                quiz_id: "q".to_string(),
+                score: 1.0,
+            },
+            0,
+        ));
+
+        assert_eq!(toasts.current().unwrap().id, "first_steps");
+        toasts.update(TOAST_SECONDS);
+        assert_eq!(toasts.current().unwrap().id, "perfectionist");
+        toasts.update(TOAST_SECONDS);
+        assert!(toasts.current().is_none());
+    }
+}
@@ -0,0 +1,240 @@
+//! Button action module for Learn Liberty
+//!
+//! This module defines the declarative actions a lesson button can carry.
+//! Actions are plain data so they can be authored in lesson files, either
+//! as tagged objects or in the short `verb:argument` form, and are checked
+//! against the lesson they belong to when it is loaded.
+
+use crate::education::{EducationalContent, ElementPayload};
+use crate::inventory::ItemDatabase;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ButtonAction {
+    /// Does nothing, e.g. for legacy buttons whose data names no action
+    #[default]
+    None,
+    NextSection,
+    /// Jump to the lesson section with this title
+    OpenPage {
+        page: String,
+    },
+    StartQuiz {
+        quiz_id: String,
+    },
+    RevealHint {
+        hint: String,
+    },
+    SetFlag {
+        flag: String,
+        #[serde(default = "default_flag_value")]
+        value: bool,
+    },
+    GrantItem {
+        item_id: String,
+        #[serde(default = "default_quantity")]
+        quantity: u32,
+    },
+    CloseLesson,
+}
+
+fn default_flag_value() -> bool {
+    true
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+impl FromStr for ButtonAction {
+    type Err = anyhow::Error;
+
+    /// Parse the short form, e.g. `next_section`, `start_quiz:ownership`
+    /// or `grant_item:crab_coin:3`
+    fn from_str(source: &str) -> anyhow::Result<Self> {
+        let mut parts = source.trim().splitn(2, ':');
+        let verb = parts.next().unwrap_or_default();
+        let argument = parts.next().map(str::trim).unwrap_or_default();
+        let required = |name: &str| {
+            if argument.is_empty() {
+                anyhow::bail!("Action {} needs a {}", verb, name);
+            }
+            Ok(argument.to_string())
+        };
+
+        Ok(match verb {
+            "none" => ButtonAction::None,
+            "next_section" => ButtonAction::NextSection,
+            "open_page" => ButtonAction::OpenPage {
+                page: required("page")?,
+            },
+            "start_quiz" => ButtonAction::StartQuiz {
+                quiz_id: required("quiz id")?,
+            },
+            "reveal_hint" => ButtonAction::RevealHint {
+                hint: required("hint")?,
+            },
+            "set_flag" => ButtonAction::SetFlag {
+                flag: required("flag")?,
+                value: true,
+            },
+            "grant_item" => {
+                let argument = required("item id")?;
+                let (item_id, quantity) = match argument.rsplit_once(':') {
+                    Some((item_id, quantity)) => (item_id.to_string(), quantity.parse()?),
+                    None => (argument, 1),
+                };
+                ButtonAction::GrantItem { item_id, quantity }
+            }
+            "close_lesson" => ButtonAction::CloseLesson,
+            _ => anyhow::bail!("Unknown button action: {}", source),
+        })
+    }
+}
+
+impl ButtonAction {
+    /// Check that the action refers to things that exist. Item grants are
+    /// only checked when an item database is given.
+    pub fn validate(
+        &self,
+        lesson: &EducationalContent,
+        items: Option<&ItemDatabase>,
+    ) -> anyhow::Result<()> {
+        match self {
+            ButtonAction::NextSection if lesson.sections.is_empty() => {
+                anyhow::bail!("Lesson {} has no sections to advance through", lesson.id)
+            }
+            ButtonAction::OpenPage { page }
+                if !lesson.sections.iter().any(|section| &section.title == page) =>
+            {
+                anyhow::bail!("Lesson {} has no page {}", lesson.id, page)
+            }
+            ButtonAction::StartQuiz { quiz_id } if !has_quiz(lesson, quiz_id) => {
+                anyhow::bail!("Lesson {} has no quiz {}", lesson.id, quiz_id)
+            }
+            ButtonAction::RevealHint { hint } if hint.trim().is_empty() => {
+                anyhow::bail!("Hint text is empty")
+            }
+            ButtonAction::SetFlag { flag, .. } if flag.trim().is_empty() => {
+                anyhow::bail!("Flag name is empty")
+            }
+            ButtonAction::GrantItem {
+                item_id,
+                quantity: 0,
+            } => anyhow::bail!("Item grant of {} has a quantity of zero", item_id),
+            ButtonAction::GrantItem { item_id, .. }
+                if items.is_some_and(|items| items.get(item_id).is_none()) =>
+            {
+                anyhow::bail!("Unknown item: {}", item_id)
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn has_quiz(lesson: &EducationalContent, quiz_id: &str) -> bool {
+    lesson
+        .interactive_elements
+        .iter()
+        .any(|element| matches!(&element.payload, ElementPayload::Quiz(quiz) if quiz.id == quiz_id))
+}
+
+/// Validate every button action in a lesson, reporting the first problem
+/// with the index of the element it belongs to
+pub fn validate_lesson_actions(
+    lesson: &EducationalContent,
+    items: Option<&ItemDatabase>,
+) -> anyhow::Result<()> {
+    for (index, element) in lesson.interactive_elements.iter().enumerate() {
+        if let ElementPayload::Button { action, .. } = &element.payload {
+            action
+                .validate(lesson, items)
+                .map_err(|e| anyhow::anyhow!("Lesson {} element {}: {}", lesson.id, index, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::education::{InteractiveElement, LessonSection};
+
+    fn lesson() -> EducationalContent {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Ownership".to_string(),
+        );
+        lesson.sections.push(LessonSection {
+            title: "Borrowing".to_string(),
+            level: 2,
+            body: String::new(),
+        });
+        lesson
+    }
+
+    #[test]
+    fn test_parse_short_form() {
+        assert_eq!(
+            "next_section".parse::<ButtonAction>().unwrap(),
+            ButtonAction::NextSection
+        );
+        assert_eq!(
+            "grant_item:crab_coin:3".parse::<ButtonAction>().unwrap(),
+            ButtonAction::GrantItem {
+                item_id: "crab_coin".to_string(),
+                quantity: 3
+            }
+        );
+        assert!("start_quiz".parse::<ButtonAction>().is_err());
+        assert!("dance".parse::<ButtonAction>().is_err());
+    }
+
+    #[test]
+    fn test_validate_against_lesson() {
+        let lesson = lesson();
+        let items = ItemDatabase::load("assets/data/items.toml").unwrap();
+
+        let open = ButtonAction::OpenPage {
+            page: "Borrowing".to_string(),
+        };
+        assert!(open.validate(&lesson, None).is_ok());
+        let missing_page = ButtonAction::OpenPage {
+            page: "Lifetimes".to_string(),
+        };
+        assert!(missing_page.validate(&lesson, None).is_err());
+
+        let quiz = ButtonAction::StartQuiz {
+            quiz_id: "ownership".to_string(),
+        };
+        assert!(quiz.validate(&lesson, None).is_err());
+
+        let grant = ButtonAction::GrantItem {
+            item_id: "magic_sword".to_string(),
+            quantity: 1,
+        };
+        assert!(grant.validate(&lesson, None).is_ok());
+        assert!(grant.validate(&lesson, Some(&items)).is_err());
+    }
+
+    #[test]
+    fn test_validate_lesson_reports_element() {
+        let mut lesson = lesson();
+        lesson.add_interactive_element(InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Button {
+                label: "Quiz".to_string(),
+                action: ButtonAction::StartQuiz {
+                    quiz_id: "missing".to_string(),
+                },
+            },
+        ));
+
+        let error = validate_lesson_actions(&lesson, None).unwrap_err();
+        assert!(error.to_string().contains("element 0"));
+    }
+}
@@ -0,0 +1,390 @@
+//! Adaptive difficulty module for Learn Liberty
+//!
+//! This module keeps a per-topic mastery estimate using Bayesian knowledge
+//! tracing and picks the questions and hint level that keep a learner near
+//! a target success rate. Quiz results update every topic a question is
+//! tagged with; partial credit counts as a partial observation.
+
+use crate::quiz::{Question, Quiz, QuizResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Deepest hint level the selector will offer
+pub const MAX_HINT_LEVEL: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BktParams {
+    /// Chance the learner already knows a topic they have not been tested on
+    pub initial: f32,
+    /// Chance of learning the topic from one practice opportunity
+    pub transit: f32,
+    /// Chance of answering wrongly despite knowing the topic
+    pub slip: f32,
+    /// Chance of answering correctly without knowing the topic
+    pub guess: f32,
+}
+
+impl BktParams {
+    /// Check the parameters are probabilities that keep the update well
+    /// defined: slip and guess strictly between 0 and 1, so neither
+    /// posterior divides by zero once mastery reaches 0 or 1
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, value) in [("initial", self.initial), ("transit", self.transit)] {
+            if !(0.0..=1.0).contains(&value) {
+                anyhow::bail!("BKT {} must be between 0 and 1, got {}", name, value);
+            }
+        }
+        for (name, value) in [("slip", self.slip), ("guess", self.guess)] {
+            if !(value > 0.0 && value < 1.0) {
+                anyhow::bail!("BKT {} must be above 0 and below 1, got {}", name, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for BktParams {
+    fn default() -> Self {
+        Self {
+            initial: 0.2,
+            transit: 0.15,
+            slip: 0.1,
+            guess: 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MasteryModel {
+    pub params: BktParams,
+    /// Probability that the learner has mastered each topic
+    pub topics: BTreeMap<String, f32>,
+}
+
+impl MasteryModel {
+    pub fn new(params: BktParams) -> anyhow::Result<Self> {
+        params.validate()?;
+        Ok(Self {
+            params,
+            topics: BTreeMap::new(),
+        })
+    }
+
+    pub fn mastery(&self, topic: &str) -> f32 {
+        self.topics
+            .get(topic)
+            .copied()
+            .unwrap_or(self.params.initial)
+    }
+
+    /// Update a topic from one answer earning `credit` between 0.0 and 1.0
+    pub fn observe(&mut self, topic: &str, credit: f32) -> anyhow::Result<()> {
+        self.params.validate()?;
+        if credit.is_nan() {
+            anyhow::bail!("Credit for {} is not a number", topic);
+        }
+        let BktParams {
+            transit,
+            slip,
+            guess,
+            ..
+        } = self.params;
+        let known = self.mastery(topic);
+        let credit = credit.clamp(0.0, 1.0);
+
+        let if_correct = known * (1.0 - slip) / (known * (1.0 - slip) + (1.0 - known) * guess);
+        let if_wrong = known * slip / (known * slip + (1.0 - known) * (1.0 - guess));
+        let posterior = credit * if_correct + (1.0 - credit) * if_wrong;
+        let learned = posterior + (1.0 - posterior) * transit;
+
+        self.topics
+            .insert(topic.to_string(), learned.clamp(0.0, 1.0));
+        Ok(())
+    }
+
+    /// Update every topic of every answered question in a scored quiz
+    pub fn record_result(&mut self, quiz: &Quiz, result: &QuizResult) -> anyhow::Result<()> {
+        self.params.validate()?;
+        let questions: HashMap<&str, &Question> = quiz
+            .questions
+            .iter()
+            .map(|question| (question.id.as_str(), question))
+            .collect();
+        for answered in &result.questions {
+            let Some(question) = questions.get(answered.question_id.as_str()) else {
+                continue;
+            };
+            for topic in &question.topics {
+                self.observe(topic, answered.credit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Chance the learner answers a question correctly, from the mean
+    /// mastery of its topics and its difficulty
+    pub fn predicted_success(&self, question: &Question) -> f32 {
+        let known = if question.topics.is_empty() {
+            self.params.initial
+        } else {
+            question
+                .topics
+                .iter()
+                .map(|topic| self.mastery(topic))
+                .sum::<f32>()
+                / question.topics.len() as f32
+        };
+        let BktParams { slip, guess, .. } = self.params;
+        let success = known * (1.0 - slip) + (1.0 - known) * guess;
+        success * (1.0 - question.difficulty) + guess * question.difficulty
+    }
+}
+
+/// Picks questions and hints that keep the predicted success rate near a
+/// target
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveSelector {
+    pub target_success: f32,
+}
+
+impl Default for AdaptiveSelector {
+    fn default() -> Self {
+        Self {
+            target_success: 0.7,
+        }
+    }
+}
+
+impl AdaptiveSelector {
+    pub fn new(target_success: f32) -> Self {
+        Self {
+            target_success: target_success.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Up to `count` questions from the pool, closest to the target first,
+    /// skipping any whose id is in `exclude`
+    pub fn select<'a>(
+        &self,
+        model: &MasteryModel,
+        pool: &'a [Question],
+        count: usize,
+        exclude: &HashSet<String>,
+    ) -> Vec<&'a Question> {
+        let mut candidates: Vec<(f32, &Question)> = pool
+            .iter()
+            .filter(|question| !exclude.contains(&question.id))
+            .map(|question| {
+                let distance = (model.predicted_success(question) - self.target_success).abs();
+                (distance, question)
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.id.cmp(&b.1.id)));
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(_, question)| question)
+            .collect()
+    }
+
+    /// Build a quiz of the best-matched questions from several quizzes.
+    /// Question ids become `quiz_id/question_id`, so questions that share
+    /// an id in different quizzes stay apart.
+    pub fn build_quiz(
+        &self,
+        model: &MasteryModel,
+        quizzes: &[Quiz],
+        count: usize,
+        id: &str,
+    ) -> Option<Quiz> {
+        let pool: Vec<Question> = quizzes
+            .iter()
+            .flat_map(|quiz| {
+                quiz.questions.iter().map(|question| {
+                    let mut question = question.clone();
+                    question.id = format!("{}/{}", quiz.id, question.id);
+                    question
+                })
+            })
+            .collect();
+        let questions: Vec<Question> = self
+            .select(model, &pool, count, &HashSet::new())
+            .into_iter()
+            .cloned()
+            .collect();
+        if questions.is_empty() {
+            return None;
+        }
+        Some(Quiz {
+            id: id.to_string(),
+            title: "Practice".to_string(),
+            questions,
+        })
+    }
+
+    /// How much help to offer on a question: 0 when the learner is
+    /// expected to reach the target alone, up to `MAX_HINT_LEVEL`
+    pub fn hint_level(&self, model: &MasteryModel, question: &Question) -> u8 {
+        let gap = self.target_success - model.predicted_success(question);
+        if gap <= 0.0 {
+            0
+        } else {
+            ((gap / 0.15).ceil() as u8).min(MAX_HINT_LEVEL)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Answer, QuestionKind};
+    use std::collections::HashMap;
+
+    fn question(id: &str, topic: &str, difficulty: f32) -> Question {
+        Question {
+            id: id.to_string(),
+            prompt: id.to_string(),
+            kind: QuestionKind::TrueFalse { answer: true },
+            weight: 1.0,
+            topics: vec![topic.to_string()],
+            difficulty,
+            hints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_mastery_rises_and_falls() {
+        let mut model = MasteryModel::default();
+        let start = model.mastery("ownership");
+
+        model.observe("ownership", 1.0).unwrap();
+        let after_correct = model.mastery("ownership");
+        assert!(after_correct > start);
+
+        model.observe("ownership", 0.0).unwrap();
+        assert!(model.mastery("ownership") < after_correct);
+
+        for _ in 0..10 {
+            model.observe("ownership", 1.0).unwrap();
+        }
+        assert!(model.mastery("ownership") > 0.95);
+        assert_eq!(model.mastery("lifetimes"), model.params.initial);
+    }
+
+    #[test]
+    fn test_record_result_updates_tagged_topics() {
+        let quiz = Quiz {
+            id: "basics".to_string(),
+            title: "Basics".to_string(),
+            questions: vec![
+                question("moves", "ownership", 0.5),
+                question("refs", "borrowing", 0.5),
+            ],
+        };
+        let mut answers = HashMap::new();
+        answers.insert("moves".to_string(), Answer::Bool(true));
+        answers.insert("refs".to_string(), Answer::Bool(false));
+
+        let mut model = MasteryModel::default();
+        model.record_result(&quiz, &quiz.score(&answers)).unwrap();
+
+        assert!(model.mastery("ownership") > model.mastery("borrowing"));
+    }
+
+    #[test]
+    fn test_selector_tracks_target() {
+        let pool = vec![
+            question("easy", "ownership", 0.0),
+            question("medium", "ownership", 0.5),
+            question("hard", "ownership", 1.0),
+        ];
+        let selector = AdaptiveSelector::new(0.7);
+        let mut model = MasteryModel::default();
+
+        let first = selector.select(&model, &pool, 1, &HashSet::new());
+        assert_eq!(first[0].id, "easy");
+
+        for _ in 0..10 {
+            model.observe("ownership", 1.0).unwrap();
+        }
+        let next = selector.select(&model, &pool, 1, &HashSet::new());
+        assert_eq!(next[0].id, "medium");
+
+        let exclude: HashSet<String> = ["medium".to_string()].into_iter().collect();
+        assert_eq!(selector.select(&model, &pool, 3, &exclude).len(), 2);
+    }
+
+    #[test]
+    fn test_hint_level_follows_mastery() {
+        let selector = AdaptiveSelector::default();
+        let hard = question("hard", "ownership", 0.6);
+        let mut model = MasteryModel::default();
+
+        let novice = selector.hint_level(&model, &hard);
+        assert!(novice > 0 && novice <= MAX_HINT_LEVEL);
+
+        for _ in 0..10 {
+            model.observe("ownership", 1.0).unwrap();
+        }
+        assert_eq!(
+            selector.hint_level(&model, &question("easy", "ownership", 0.0)),
+            0
+        );
+        assert!(selector.hint_level(&model, &hard) < novice);
+    }
+
+    #[test]
+    fn test_build_quiz_keeps_duplicate_ids_apart() {
+        let quiz = |id: &str, topic: &str| Quiz {
+            id: id.to_string(),
+            title: id.to_string(),
+            questions: vec![question("q1", topic, 0.5)],
+        };
+        let quizzes = vec![
+            quiz("ownership", "ownership"),
+            quiz("borrowing", "borrowing"),
+        ];
+        let mut model = MasteryModel::default();
+
+        let practice = AdaptiveSelector::default()
+            .build_quiz(&model, &quizzes, 5, "practice")
+            .unwrap();
+        let ids: HashSet<&str> = practice.questions.iter().map(|q| q.id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["ownership/q1", "borrowing/q1"]));
+
+        let answers = HashMap::from([
+            ("ownership/q1".to_string(), Answer::Bool(true)),
+            ("borrowing/q1".to_string(), Answer::Bool(false)),
+        ]);
+        model
+            .record_result(&practice, &practice.score(&answers))
+            .unwrap();
+        assert!(model.mastery("ownership") > model.mastery("borrowing"));
+    }
+
+    #[test]
+    fn test_rejects_degenerate_params() {
+        let params = BktParams {
+            slip: 0.0,
+            ..BktParams::default()
+        };
+        assert!(MasteryModel::new(params).is_err());
+        assert!(MasteryModel::new(BktParams {
+            guess: f32::NAN,
+            ..BktParams::default()
+        })
+        .is_err());
+
+        let mut model = MasteryModel {
+            params,
+            topics: BTreeMap::from([("ownership".to_string(), 1.0)]),
+        };
+        assert!(model.observe("ownership", 0.0).is_err());
+        assert_eq!(model.mastery("ownership"), 1.0);
+
+        let mut model = MasteryModel::default();
+        assert!(model.observe("ownership", f32::NAN).is_err());
+        assert!(MasteryModel::new(BktParams::default()).is_ok());
+    }
+}
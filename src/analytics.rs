@@ -0,0 +1,573 @@
+//! Learning analytics module for Learn Liberty
+//!
+//! This module records learning activity as actor/verb/object/result
+//! events, appends them to a local JSON Lines log and converts them into
+//! xAPI statements that a learning record store can import. Every event
+//! gets a random UUID when it is created, which becomes the statement id
+//! so an LRS can ignore statements it has already received.
+
+use crate::profile::LearnerProfile;
+use crate::quiz::QuizResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// IRI that activity and account ids are made absolute under unless
+/// another is given
+pub const DEFAULT_BASE_IRI: &str = "https://learnliberty.example";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Actor {
+    pub id: String,
+    pub name: String,
+}
+
+impl Actor {
+    /// The actor used when nobody picked a profile
+    pub fn guest() -> Self {
+        Self {
+            id: "guest".to_string(),
+            name: "Guest".to_string(),
+        }
+    }
+}
+
+impl From<&LearnerProfile> for Actor {
+    fn from(profile: &LearnerProfile) -> Self {
+        Self {
+            id: profile.id.clone(),
+            name: profile.display_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verb {
+    Started,
+    Interacted,
+    Answered,
+    Completed,
+}
+
+impl Verb {
+    pub fn xapi_id(self) -> &'static str {
+        match self {
+            Verb::Started => "http://adlnet.gov/expapi/verbs/attempted",
+            Verb::Interacted => "http://adlnet.gov/expapi/verbs/interacted",
+            Verb::Answered => "http://adlnet.gov/expapi/verbs/answered",
+            Verb::Completed => "http://adlnet.gov/expapi/verbs/completed",
+        }
+    }
+
+    pub fn display(self) -> &'static str {
+        match self {
+            Verb::Started => "attempted",
+            Verb::Interacted => "interacted",
+            Verb::Answered => "answered",
+            Verb::Completed => "completed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Lesson,
+    Element,
+    Question,
+    Hint,
+}
+
+impl ActivityKind {
+    fn xapi_type(self) -> &'static str {
+        match self {
+            ActivityKind::Lesson => "http://adlnet.gov/expapi/activities/lesson",
+            ActivityKind::Element => "http://adlnet.gov/expapi/activities/interaction",
+            ActivityKind::Question => "http://adlnet.gov/expapi/activities/cmi.interaction",
+            ActivityKind::Hint => "http://adlnet.gov/expapi/activities/interaction",
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            ActivityKind::Lesson => "lessons",
+            ActivityKind::Element => "elements",
+            ActivityKind::Question => "questions",
+            ActivityKind::Hint => "hints",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    /// Unique within its kind, e.g. `lesson_1` or `lesson_1/element/3`
+    pub id: String,
+    pub kind: ActivityKind,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearningEvent {
+    /// UUID used as the xAPI statement id. Events logged before ids were
+    /// recorded get a fresh one when read.
+    #[serde(default = "uuid_v4")]
+    pub id: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub actor: Actor,
+    pub verb: Verb,
+    pub object: Activity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<EventResult>,
+}
+
+impl LearningEvent {
+    pub fn lesson_started(actor: &Actor, lesson_id: &str, timestamp: u64) -> Self {
+        Self {
+            id: uuid_v4(),
+            timestamp,
+            actor: actor.clone(),
+            verb: Verb::Started,
+            object: Activity {
+                id: lesson_id.to_string(),
+                kind: ActivityKind::Lesson,
+                name: lesson_id.to_string(),
+            },
+            result: None,
+        }
+    }
+
+    pub fn element_interacted(
+        actor: &Actor,
+        lesson_id: &str,
+        element_index: usize,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            id: uuid_v4(),
+            timestamp,
+            actor: actor.clone(),
+            verb: Verb::Interacted,
+            object: Activity {
+                id: format!("{}/element/{}", lesson_id, element_index),
+                kind: ActivityKind::Element,
+                name: format!("{} element {}", lesson_id, element_index),
+            },
+            result: None,
+        }
+    }
+
+    /// An interaction with a code exercise, carrying whether the run passed
+    pub fn exercise_run(
+        actor: &Actor,
+        lesson_id: &str,
+        element_index: usize,
+        passed: bool,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            result: Some(EventResult {
+                success: Some(passed),
+                ..EventResult::default()
+            }),
+            ..Self::element_interacted(actor, lesson_id, element_index, timestamp)
+        }
+    }
+
+    /// One `answered` event per question in a scored quiz
+    pub fn quiz_answered(actor: &Actor, result: &QuizResult, timestamp: u64) -> Vec<Self> {
+        result
+            .questions
+            .iter()
+            .map(|question| Self {
+                id: uuid_v4(),
+                timestamp,
+                actor: actor.clone(),
+                verb: Verb::Answered,
+                object: Activity {
+                    id: format!("{}/{}", result.quiz_id, question.question_id),
+                    kind: ActivityKind::Question,
+                    name: question.question_id.clone(),
+                },
+                result: Some(EventResult {
+                    score: Some(question.credit),
+                    success: Some(question.credit >= 1.0),
+                    ..EventResult::default()
+                }),
+            })
+            .collect()
+    }
+
+    /// A hint tier revealed for a question, exercise or hint button
+    pub fn hint_revealed(
+        actor: &Actor,
+        lesson_id: &str,
+        target_id: &str,
+        tier: usize,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            id: uuid_v4(),
+            timestamp,
+            actor: actor.clone(),
+            verb: Verb::Interacted,
+            object: Activity {
+                id: format!("{}/{}/{}", lesson_id, target_id, tier),
+                kind: ActivityKind::Hint,
+                name: format!("{} hint {}", target_id, tier),
+            },
+            result: None,
+        }
+    }
+
+    pub fn lesson_completed(
+        actor: &Actor,
+        lesson_id: &str,
+        score: Option<f32>,
+        duration_secs: f64,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            result: Some(EventResult {
+                score,
+                success: Some(true),
+                completion: Some(true),
+                duration_secs: Some(duration_secs),
+            }),
+            verb: Verb::Completed,
+            ..Self::lesson_started(actor, lesson_id, timestamp)
+        }
+    }
+
+    /// Convert to an xAPI statement. Activity and account ids are made
+    /// absolute under `base_iri`, with each segment of the activity id
+    /// percent-encoded.
+    pub fn to_xapi(&self, base_iri: &str) -> Value {
+        let base_iri = base_iri.trim_end_matches('/');
+        let activity_path: Vec<String> = self.object.id.split('/').map(percent_encode).collect();
+        let mut statement = json!({
+            "id": self.id,
+            "actor": {
+                "objectType": "Agent",
+                "name": self.actor.name,
+                "account": {"homePage": base_iri, "name": self.actor.id},
+            },
+            "verb": {
+                "id": self.verb.xapi_id(),
+                "display": {"en-US": self.verb.display()},
+            },
+            "object": {
+                "objectType": "Activity",
+                "id": format!(
+                    "{}/{}/{}",
+                    base_iri,
+                    self.object.kind.path(),
+                    activity_path.join("/")
+                ),
+                "definition": {
+                    "name": {"en-US": self.object.name},
+                    "type": self.object.kind.xapi_type(),
+                },
+            },
+            "timestamp": rfc3339(self.timestamp),
+        });
+
+        if let Some(result) = &self.result {
+            let mut xapi_result = serde_json::Map::new();
+            if let Some(score) = result.score {
+                xapi_result.insert("score".to_string(), json!({"scaled": score}));
+            }
+            if let Some(success) = result.success {
+                xapi_result.insert("success".to_string(), json!(success));
+            }
+            if let Some(completion) = result.completion {
+                xapi_result.insert("completion".to_string(), json!(completion));
+            }
+            if let Some(duration) = result.duration_secs {
+                xapi_result.insert(
+                    "duration".to_string(),
+                    json!(format!("PT{:.2}S", duration.max(0.0))),
+                );
+            }
+            statement["result"] = Value::Object(xapi_result);
+        }
+        statement
+    }
+}
+
+/// A random (version 4) UUID. The bits come from the standard library's
+/// randomly keyed hasher mixed with the clock and a counter, which is
+/// unique enough for statement ids without pulling in a random number
+/// crate.
+pub fn uuid_v4() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut halves = [0u64; 2];
+    for (index, half) in halves.iter_mut().enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u64(count);
+        hasher.write_usize(index);
+        *half = hasher.finish();
+    }
+
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&halves[0].to_be_bytes());
+    bytes[8..].copy_from_slice(&halves[1].to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Percent-encode everything but unreserved characters, for one IRI path
+/// segment
+fn percent_encode(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Format Unix seconds as an RFC 3339 UTC timestamp
+pub fn rfc3339(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Append-only JSON Lines file of learning events
+pub struct EventLog {
+    path: PathBuf,
+}
+
+impl EventLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, event: &LearningEvent) -> anyhow::Result<()> {
+        self.append_all(std::slice::from_ref(event))
+    }
+
+    pub fn append_all(&self, events: &[LearningEvent]) -> anyhow::Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event)?);
+            lines.push('\n');
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    /// Every event in the log, oldest first
+    pub fn read_all(&self) -> anyhow::Result<Vec<LearningEvent>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let contents = fs::read_to_string(&self.path)?;
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| anyhow::anyhow!("{}:{}: {}", self.path.display(), index + 1, e))
+            })
+            .collect()
+    }
+
+    /// The whole log as an array of xAPI statements
+    pub fn export_xapi(&self, base_iri: &str) -> anyhow::Result<Value> {
+        Ok(Value::Array(
+            self.read_all()?
+                .iter()
+                .map(|event| event.to_xapi(base_iri))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::QuestionResult;
+
+    fn actor() -> Actor {
+        Actor {
+            id: "sam".to_string(),
+            name: "Sam".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "learn_liberty_analytics_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let log = EventLog::new(&path);
+        let result = QuizResult {
+            quiz_id: "ownership".to_string(),
+            questions: vec![QuestionResult {
+                question_id: "q1".to_string(),
+                credit: 1.0,
+                weight: 1.0,
+                hints_used: 0,
+            }],
+            score: 1.0,
+        };
+
+        log.append(&LearningEvent::lesson_started(&actor(), "lesson_1", 10))
+            .unwrap();
+        log.append_all(&LearningEvent::quiz_answered(&actor(), &result, 20))
+            .unwrap();
+        log.append(&LearningEvent::lesson_completed(
+            &actor(),
+            "lesson_1",
+            Some(1.0),
+            95.5,
+            30,
+        ))
+        .unwrap();
+
+        let events = log.read_all().unwrap();
+        let verbs: Vec<Verb> = events.iter().map(|event| event.verb).collect();
+        assert_eq!(verbs, vec![Verb::Started, Verb::Answered, Verb::Completed]);
+        assert_eq!(events[1].object.id, "ownership/q1");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_xapi_statement_shape() {
+        let event = LearningEvent::lesson_completed(&actor(), "lesson_1", Some(0.8), 90.0, 0);
+        let statement = event.to_xapi("https://learnliberty.example/");
+
+        assert_eq!(statement["actor"]["account"]["name"], "sam");
+        assert_eq!(
+            statement["verb"]["id"],
+            "http://adlnet.gov/expapi/verbs/completed"
+        );
+        assert_eq!(
+            statement["object"]["id"],
+            "https://learnliberty.example/lessons/lesson_1"
+        );
+        assert_eq!(statement["result"]["completion"], true);
+        assert_eq!(statement["result"]["duration"], "PT90.00S");
+        assert_eq!(statement["timestamp"], "1970-01-01T00:00:00Z");
+
+        let started = LearningEvent::lesson_started(&actor(), "lesson_1", 0).to_xapi("x");
+        assert!(started.get("result").is_none());
+        assert_eq!(statement["id"], event.id);
+    }
+
+    #[test]
+    fn test_statement_ids_and_encoded_iris() {
+        let first = uuid_v4();
+        let second = uuid_v4();
+        assert_ne!(first, second);
+        assert_eq!(first.len(), 36);
+        assert_eq!(&first[14..15], "4");
+        assert!("89ab".contains(&first[19..20]));
+
+        let event =
+            LearningEvent::hint_revealed(&actor(), "lesson 1", "exercise/Move a String", 1, 0);
+        let statement = event.to_xapi(DEFAULT_BASE_IRI);
+        assert_eq!(
+            statement["object"]["id"],
+            "https://learnliberty.example/hints/lesson%201/exercise/Move%20a%20String/1"
+        );
+
+        let legacy = r#"{"timestamp":0,"actor":{"id":"sam","name":"Sam"},"verb":"started",
+            "object":{"id":"lesson_1","kind":"lesson","name":"lesson_1"}}"#;
+        let legacy: LearningEvent = serde_json::from_str(legacy).unwrap();
+        assert_eq!(legacy.id.len(), 36);
+    }
+}
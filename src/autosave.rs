@@ -0,0 +1,254 @@
+//! Autosave module for Learn Liberty
+//!
+//! This module snapshots learner progress every
+//! `education.progress_save_interval` seconds and whenever a lesson is
+//! completed. Snapshots are handed to a tokio task that serializes and
+//! writes them, so the frame loop never waits on the disk. Only the most
+//! recent few autosaves are kept. Autosaves live in the active learner's
+//! own save directory and the newest one is restored when they are picked.
+
+use crate::config::Settings;
+use crate::profile::ProfileStore;
+use crate::save::{SaveData, SaveManager};
+use crate::state::AppState;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Number of autosaves kept when none is given
+pub const DEFAULT_AUTOSAVE_KEEP: usize = 3;
+
+pub struct Autosave {
+    interval: f64,
+    since_last_save: f64,
+    completed_lessons: usize,
+    sender: mpsc::UnboundedSender<SaveData>,
+    writer: JoinHandle<()>,
+}
+
+impl Autosave {
+    /// Start the background writer. Must be called from within a tokio
+    /// runtime.
+    pub fn spawn(directory: impl Into<PathBuf>, interval_secs: u64, keep: usize) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<SaveData>();
+        let store = SaveManager::new(directory);
+        let keep = keep.max(1);
+
+        let writer = tokio::spawn(async move {
+            let store = std::sync::Arc::new(store);
+            while let Some(save) = receiver.recv().await {
+                let store = std::sync::Arc::clone(&store);
+                let result =
+                    tokio::task::spawn_blocking(move || write_rolling(&store, &save, keep)).await;
+                match result {
+                    Ok(Err(e)) => eprintln!("Autosave error: {}", e),
+                    Err(e) => eprintln!("Autosave task failed: {}", e),
+                    Ok(Ok(())) => {}
+                }
+            }
+        });
+
+        Self {
+            interval: interval_secs as f64,
+            since_last_save: 0.0,
+            completed_lessons: 0,
+            sender,
+            writer,
+        }
+    }
+
+    pub fn from_settings(settings: &Settings, directory: impl Into<PathBuf>) -> Self {
+        Self::spawn(
+            directory,
+            settings.education.progress_save_interval,
+            DEFAULT_AUTOSAVE_KEEP,
+        )
+    }
+
+    /// Advance the timer and queue a snapshot when the interval elapsed or
+    /// a lesson was completed since the last call. Returns whether a
+    /// snapshot was queued.
+    pub fn tick(&mut self, delta_time: f64, state: &AppState, settings: &Settings) -> bool {
+        self.since_last_save += delta_time;
+
+        let lesson_completed = state.completed_lessons.len() > self.completed_lessons;
+        self.completed_lessons = state.completed_lessons.len();
+
+        if lesson_completed || (self.interval > 0.0 && self.since_last_save >= self.interval) {
+            self.save_now(SaveData::capture(state, settings));
+            return true;
+        }
+        false
+    }
+
+    /// Queue a snapshot immediately and restart the interval
+    pub fn save_now(&mut self, save: SaveData) {
+        self.since_last_save = 0.0;
+        if self.sender.send(save).is_err() {
+            eprintln!("Autosave writer has stopped");
+        }
+    }
+
+    /// Stop accepting snapshots and wait for queued ones to be written
+    pub async fn shutdown(self) {
+        drop(self.sender);
+        let _ = self.writer.await;
+    }
+}
+
+/// Write a save into the next autosave slot and drop the oldest ones
+fn write_rolling(store: &SaveManager, save: &SaveData, keep: usize) -> anyhow::Result<()> {
+    let slots = store.slots()?;
+    let next = slots.last().map_or(0, |last| last + 1);
+    store.save(next, save)?;
+
+    let total = slots.len() + 1;
+    for slot in slots.iter().take(total.saturating_sub(keep)) {
+        store.delete(*slot)?;
+    }
+    Ok(())
+}
+
+/// Restore the active learner's newest autosave into the app state and
+/// start autosaving into their save directory. Must be called from within
+/// a tokio runtime.
+pub fn resume_learner(
+    profiles: &ProfileStore,
+    settings: &Settings,
+    state: &mut AppState,
+) -> anyhow::Result<Autosave> {
+    let id = state
+        .learner_id
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("No learner has been selected"))?;
+    let saves = profiles.saves(&id)?;
+    if let Some(save) = load_latest(saves.directory())? {
+        save.apply_to(state);
+    }
+
+    let mut autosave = Autosave::from_settings(settings, saves.directory());
+    autosave.completed_lessons = state.completed_lessons.len();
+    Ok(autosave)
+}
+
+/// Load the newest autosave that passes its checksum
+pub fn load_latest(directory: impl Into<PathBuf>) -> anyhow::Result<Option<SaveData>> {
+    let store = SaveManager::new(directory);
+    for slot in store.slots()?.into_iter().rev() {
+        if let Ok(save) = store.load(slot) {
+            return Ok(Some(save));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "learn_liberty_autosave_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_interval_triggers_save() {
+        let dir = scratch_dir("interval");
+        let settings = Settings::default();
+        let mut autosave = Autosave::from_settings(&settings, &dir);
+        let mut state = AppState::default();
+        state.advance_lesson("lesson_1".to_string(), 0.3);
+
+        assert!(!autosave.tick(10.0, &state, &settings));
+        assert!(!autosave.tick(10.0, &state, &settings));
+        assert!(autosave.tick(10.0, &state, &settings));
+        assert!(!autosave.tick(10.0, &state, &settings));
+        autosave.shutdown().await;
+
+        let latest = load_latest(&dir).unwrap().unwrap();
+        assert_eq!(latest.current_lesson_id, "lesson_1");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_lesson_completion_triggers_save() {
+        let dir = scratch_dir("completion");
+        let settings = Settings::default();
+        let mut autosave = Autosave::from_settings(&settings, &dir);
+        let mut state = AppState::default();
+
+        state.complete_lesson("lesson_1");
+        assert!(autosave.tick(0.016, &state, &settings));
+        assert!(!autosave.tick(0.016, &state, &settings));
+        autosave.shutdown().await;
+
+        let latest = load_latest(&dir).unwrap().unwrap();
+        assert_eq!(latest.completed_lessons, vec!["lesson_1"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_resume_learner_restores_their_own_autosave() {
+        let dir = scratch_dir("resume");
+        let profiles = ProfileStore::new(&dir);
+        let settings = Settings::default();
+        profiles.create("alex", "Alex").unwrap();
+        profiles.create("sam", "Sam").unwrap();
+
+        let mut state = AppState {
+            learner_id: Some("alex".to_string()),
+            ..AppState::default()
+        };
+        let mut autosave = resume_learner(&profiles, &settings, &mut state).unwrap();
+        state.player_position = (64.0, 32.0);
+        state.complete_lesson("lesson_1");
+        assert!(autosave.tick(0.016, &state, &settings));
+        autosave.shutdown().await;
+
+        let mut sam = AppState {
+            learner_id: Some("sam".to_string()),
+            ..AppState::default()
+        };
+        resume_learner(&profiles, &settings, &mut sam)
+            .unwrap()
+            .shutdown()
+            .await;
+        assert!(sam.completed_lessons.is_empty());
+
+        let mut alex = AppState {
+            learner_id: Some("alex".to_string()),
+            ..AppState::default()
+        };
+        let mut autosave = resume_learner(&profiles, &settings, &mut alex).unwrap();
+        assert_eq!(alex.player_position, (64.0, 32.0));
+        assert_eq!(alex.completed_lessons, vec!["lesson_1"]);
+        assert!(!autosave.tick(0.016, &alex, &settings));
+        autosave.shutdown().await;
+
+        assert!(resume_learner(&profiles, &settings, &mut AppState::default()).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_rolling_set() {
+        let dir = scratch_dir("rolling");
+        let mut autosave = Autosave::spawn(&dir, 30, 2);
+        let mut state = AppState::default();
+
+        for progress in [0.1, 0.2, 0.3, 0.4] {
+            state.advance_lesson("lesson_1".to_string(), progress);
+            autosave.save_now(SaveData::capture(&state, &Settings::default()));
+        }
+        autosave.shutdown().await;
+
+        assert_eq!(SaveManager::new(&dir).slots().unwrap(), vec![2, 3]);
+        let latest = load_latest(&dir).unwrap().unwrap();
+        assert_eq!(latest.lesson_progress, 0.4);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,13 @@
+//! Content tools for Learn Liberty authors, without starting the game:
+//! `learn-liberty lint`, `learn-liberty check-locales`,
+//! `learn-liberty export-package`, `learn-liberty report`,
+//! `learn-liberty export-events` and `learn-liberty profile create`.
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !learn_liberty_app::cli::run(&args)? {
+        learn_liberty_app::cli::run(&["help".to_string()])?;
+        std::process::exit(2);
+    }
+    Ok(())
+}
@@ -0,0 +1,390 @@
+//! Command-line module for Learn Liberty
+//!
+//! This module runs the tool subcommands that share the game binary, such
+//! as exporting lessons for an LMS, writing a class report, linting
+//! lesson content, exporting a learner's xAPI statements or creating
+//! learner profiles. With no subcommand the game starts as usual; the
+//! `learn-liberty` tool binary runs the same subcommands without the game.
+
+use crate::analytics::DEFAULT_BASE_IRI;
+use crate::lesson_catalog::LessonCatalog;
+use crate::lint::{plural, Linter};
+use crate::localization::check_locales;
+use crate::package::{write_package, PackageFormat};
+use crate::profile::{unix_timestamp, ProfileStore};
+use crate::report::ClassReport;
+use crate::window::DESIGN_RESOLUTION;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Usage text; `{program}` is replaced with the name the program was
+/// started as
+const USAGE: &str = "Usage:
+  {program}                  Start the game (game binary only)
+  {program} export-package --out FILE [--format scorm12|cc]
+      [--lessons DIR] [--assets DIR] [--title TITLE]
+  {program} report --out DIR [--profiles DIR] [--lessons DIR] [--title TITLE]
+  {program} lint [--lessons DIR] [--assets DIR]
+  {program} check-locales [--locales DIR] [--lessons DIR]
+  {program} export-events --learner ID --out FILE [--profiles DIR]
+      [--base-iri IRI]
+  {program} profile create --id ID --name NAME [--profiles DIR]";
+
+/// Subcommands `run` knows, including the ways of asking for help
+const COMMANDS: [&str; 9] = [
+    "export-package",
+    "report",
+    "lint",
+    "check-locales",
+    "export-events",
+    "profile",
+    "help",
+    "--help",
+    "-h",
+];
+
+/// The usage text for the program as it was started
+fn usage() -> String {
+    let program = std::env::args_os()
+        .next()
+        .and_then(|arg0| {
+            Path::new(&arg0)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "learn-liberty".to_string());
+    USAGE.replace("{program}", &program)
+}
+
+/// Whether `arg` names a subcommand. The game starts for anything else.
+pub fn is_command(arg: &str) -> bool {
+    COMMANDS.contains(&arg)
+}
+
+/// `--name value` pairs following a subcommand
+struct Flags {
+    values: HashMap<String, String>,
+}
+
+impl Flags {
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut values = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| anyhow::anyhow!("Unexpected argument: {}\n{}", arg, usage()))?;
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("--{} needs a value", name))?;
+            values.insert(name.to_string(), value.clone());
+        }
+        Ok(Self { values })
+    }
+
+    fn get(&self, name: &str, default: &str) -> String {
+        self.values
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    fn required(&self, name: &str) -> anyhow::Result<String> {
+        self.values
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("--{} is required\n{}", name, usage()))
+    }
+}
+
+/// Run the subcommand in `args` (without the program name). Returns
+/// `false` when there is none and the game should start.
+pub fn run(args: &[String]) -> anyhow::Result<bool> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(false);
+    };
+
+    match command.as_str() {
+        "export-package" => export_package(&Flags::parse(rest)?)?,
+        "report" => report(&Flags::parse(rest)?)?,
+        "lint" => lint(&Flags::parse(rest)?)?,
+        "check-locales" => locales(&Flags::parse(rest)?)?,
+        "export-events" => export_events(&Flags::parse(rest)?)?,
+        "profile" => profile(rest)?,
+        "help" | "--help" | "-h" => println!("{}", usage()),
+        _ => anyhow::bail!("Unknown command: {}\n{}", command, usage()),
+    }
+    Ok(true)
+}
+
+fn export_package(flags: &Flags) -> anyhow::Result<()> {
+    let format: PackageFormat = flags.get("format", "scorm12").parse()?;
+    let catalog = LessonCatalog::load(flags.get("lessons", "lessons"))?;
+    let output = flags.required("out")?;
+
+    write_package(
+        &catalog,
+        Path::new(&flags.get("assets", "assets")),
+        format,
+        &flags.get("title", "Learn Liberty"),
+        Path::new(&output),
+    )?;
+    println!("Exported {} lessons to {}", catalog.len(), output);
+    Ok(())
+}
+
+fn report(flags: &Flags) -> anyhow::Result<()> {
+    let catalog = LessonCatalog::load(flags.get("lessons", "lessons"))?;
+    let profiles = ProfileStore::new(flags.get("profiles", "profiles")).list()?;
+    let output = flags.required("out")?;
+
+    let report = ClassReport::new(
+        &flags.get("title", "Class report"),
+        &catalog,
+        &profiles,
+        unix_timestamp(),
+    );
+    report.write(Path::new(&output))?;
+    println!(
+        "Reported on {} learners in {}",
+        report.learners.len(),
+        output
+    );
+    Ok(())
+}
+
+/// Write one learner's event log as a JSON array of xAPI statements
+fn export_events(flags: &Flags) -> anyhow::Result<()> {
+    let learner = flags.required("learner")?;
+    let output = flags.required("out")?;
+    let log = ProfileStore::new(flags.get("profiles", "profiles")).events(&learner)?;
+
+    let statements = log.export_xapi(&flags.get("base-iri", DEFAULT_BASE_IRI))?;
+    std::fs::write(&output, serde_json::to_string_pretty(&statements)?)?;
+    println!(
+        "Exported {} statements for {} to {}",
+        statements.as_array().map_or(0, Vec::len),
+        learner,
+        output
+    );
+    Ok(())
+}
+
+/// Print every problem found and fail when there are any
+fn lint(flags: &Flags) -> anyhow::Result<()> {
+    let lessons = flags.get("lessons", "lessons");
+    let linter = Linter::new(
+        flags.get("assets", "assets"),
+        (DESIGN_RESOLUTION.0 as f32, DESIGN_RESOLUTION.1 as f32),
+    );
+
+    let diagnostics = linter.lint_directory(&lessons)?;
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if !diagnostics.is_empty() {
+        anyhow::bail!(
+            "{} found in {}",
+            plural(diagnostics.len(), "problem"),
+            lessons
+        );
+    }
+    println!("No problems found in {}", lessons);
+    Ok(())
+}
+
+/// Manage learner profiles; `create` is the only action so far
+fn profile(args: &[String]) -> anyhow::Result<()> {
+    match args.split_first() {
+        Some((action, rest)) if action == "create" => {
+            let flags = Flags::parse(rest)?;
+            let store = ProfileStore::new(flags.get("profiles", "profiles"));
+            let profile = store.create(&flags.required("id")?, &flags.required("name")?)?;
+            println!("Created profile {} ({})", profile.id, profile.display_name);
+            Ok(())
+        }
+        _ => anyhow::bail!("Unknown profile action\n{}", usage()),
+    }
+}
+
+/// Report missing and unused message keys for every locale
+fn locales(flags: &Flags) -> anyhow::Result<()> {
+    let lessons = crate::lesson_loader::load_lessons(flags.get("lessons", "lessons"))?;
+    let reports = check_locales(flags.get("locales", "assets/locales"), &lessons)?;
+
+    let mut count = 0;
+    for report in &reports {
+        for key in &report.missing {
+            eprintln!("{}: missing {}", report.locale, key);
+        }
+        for key in &report.unused {
+            eprintln!("{}: unused {}", report.locale, key);
+        }
+        count += report.missing.len() + report.unused.len();
+    }
+    let locales = plural(reports.len(), "locale");
+    if count > 0 {
+        anyhow::bail!("{} found in {}", plural(count, "problem"), locales);
+    }
+    println!("{} complete", locales);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_command_starts_game() {
+        assert!(!run(&[]).unwrap());
+        assert!(run(&args(&["dance"])).is_err());
+        assert!(is_command("lint") && is_command("--help"));
+        assert!(!is_command("--fullscreen"));
+    }
+
+    #[test]
+    fn test_usage_and_summaries() {
+        assert!(!usage().contains("{program}"));
+        assert!(usage().contains(" export-events --learner ID"));
+        assert_eq!(plural(1, "problem"), "1 problem");
+        assert_eq!(plural(3, "locale"), "3 locales");
+    }
+
+    #[test]
+    fn test_export_package_command() {
+        let output =
+            std::env::temp_dir().join(format!("learn_liberty_cli_{}.zip", std::process::id()));
+        let output = output.to_str().unwrap();
+
+        assert!(run(&args(&["export-package"])).is_err());
+        assert!(run(&args(&[
+            "export-package",
+            "--out",
+            output,
+            "--format",
+            "cc"
+        ]))
+        .unwrap());
+
+        let entries = crate::package::read_zip(&std::fs::read(output).unwrap()).unwrap();
+        // The manifest, one page per lesson and each media file once
+        let catalog = LessonCatalog::load("lessons").unwrap();
+        let media: std::collections::BTreeSet<&str> = catalog
+            .ordered()
+            .flat_map(|lesson| lesson.asset_references())
+            .collect();
+        assert_eq!(entries.len(), 1 + catalog.len() + media.len());
+        let _ = std::fs::remove_file(output);
+    }
+
+    #[test]
+    fn test_lint_command() {
+        assert!(run(&args(&["lint"])).unwrap());
+
+        let root =
+            std::env::temp_dir().join(format!("learn_liberty_cli_lint_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("a.md"),
+            "+++\nprerequisites = [\"nowhere\"]\n+++\n# A\n",
+        )
+        .unwrap();
+
+        let error = run(&args(&["lint", "--lessons", root.to_str().unwrap()])).unwrap_err();
+        assert!(error.to_string().starts_with("1 problem found"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_profile_create_command() {
+        let root =
+            std::env::temp_dir().join(format!("learn_liberty_cli_profile_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let profiles = root.to_str().unwrap();
+        let create = |id: &str| {
+            run(&args(&[
+                "profile",
+                "create",
+                "--id",
+                id,
+                "--name",
+                "Sam Lee",
+                "--profiles",
+                profiles,
+            ]))
+        };
+
+        assert!(create("sam").unwrap());
+        assert!(create("sam").is_err());
+        assert!(create("../sam").is_err());
+        assert!(run(&args(&["profile", "rename"])).is_err());
+        let store = ProfileStore::new(&root);
+        assert_eq!(store.load("sam").unwrap().display_name, "Sam Lee");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_export_events_command() {
+        use crate::analytics::{Actor, LearningEvent};
+
+        let root =
+            std::env::temp_dir().join(format!("learn_liberty_cli_events_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let store = ProfileStore::new(root.join("profiles"));
+        let sam = store.create("sam", "Sam").unwrap();
+        let event = LearningEvent::lesson_started(&Actor::from(&sam), "lesson_1", 0);
+        store.events("sam").unwrap().append(&event).unwrap();
+        let output = root.join("statements.json");
+
+        assert!(run(&args(&["export-events", "--learner", "sam"])).is_err());
+        assert!(run(&args(&[
+            "export-events",
+            "--learner",
+            "sam",
+            "--profiles",
+            root.join("profiles").to_str().unwrap(),
+            "--out",
+            output.to_str().unwrap(),
+        ]))
+        .unwrap());
+
+        let statements: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(statements[0]["id"], event.id);
+        assert_eq!(
+            statements[0]["object"]["id"],
+            "https://learnliberty.example/lessons/lesson_1"
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_report_command() {
+        let root =
+            std::env::temp_dir().join(format!("learn_liberty_cli_report_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let profiles = root.join("profiles");
+        ProfileStore::new(&profiles).create("sam", "Sam").unwrap();
+        let output = root.join("out");
+
+        assert!(run(&args(&[
+            "report",
+            "--profiles",
+            profiles.to_str().unwrap(),
+            "--out",
+            output.to_str().unwrap(),
+        ]))
+        .unwrap());
+
+        let csv = std::fs::read_to_string(output.join("report.csv")).unwrap();
+        assert_eq!(csv.lines().count(), 1 + 10);
+        assert!(output.join("report.html").is_file());
+        assert!(output.join("report.json").is_file());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,180 @@
+//! Configuration module for Learn Liberty
+//!
+//! This module mirrors `config/settings.toml` so the game can read its
+//! settings and store a snapshot of them alongside learner progress.
+
+use crate::localization::DEFAULT_LOCALE;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowSettings {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub resizable: bool,
+    pub fullscreen: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphicsSettings {
+    pub vsync: bool,
+    pub antialiasing: bool,
+    pub render_scale: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    pub fps_limit: u32,
+    pub debug_mode: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EducationSettings {
+    pub current_lesson: String,
+    /// Seconds between progress saves
+    pub progress_save_interval: u64,
+}
+
+/// Where to push xAPI statements. Only used when the `[lrs]` table is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LrsSettings {
+    /// Base URL of the xAPI endpoint, e.g. `http://localhost:8080/xapi`
+    pub endpoint: String,
+    pub username: String,
+    pub password: String,
+    #[serde(default = "default_lrs_timeout")]
+    pub timeout_secs: u64,
+    /// IRI that statement activity and account ids are made absolute under
+    #[serde(default = "default_base_iri")]
+    pub base_iri: String,
+}
+
+fn default_lrs_timeout() -> u64 {
+    10
+}
+
+fn default_base_iri() -> String {
+    crate::analytics::DEFAULT_BASE_IRI.to_string()
+}
+
+/// Which locale interface text and lessons are shown in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalizationSettings {
+    pub locale: String,
+    /// Locales tried after `locale` and its language, before the default
+    pub fallback: Vec<String>,
+    /// Holds one directory of `.ftl` message files per locale
+    pub directory: String,
+}
+
+impl Default for LocalizationSettings {
+    fn default() -> Self {
+        Self {
+            locale: DEFAULT_LOCALE.to_string(),
+            fallback: Vec::new(),
+            directory: "assets/locales".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub graphics: GraphicsSettings,
+    pub game: GameSettings,
+    pub education: EducationSettings,
+    #[serde(default)]
+    pub lrs: Option<LrsSettings>,
+    #[serde(default)]
+    pub localization: LocalizationSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: WindowSettings {
+                title: "Learn Liberty - Educational RPG".to_string(),
+                width: 1024,
+                height: 768,
+                resizable: true,
+                fullscreen: false,
+            },
+            graphics: GraphicsSettings {
+                vsync: true,
+                antialiasing: true,
+                render_scale: 1.0,
+            },
+            game: GameSettings {
+                fps_limit: 60,
+                debug_mode: false,
+            },
+            education: EducationSettings {
+                current_lesson: "01-rust-basics-and-project-structure".to_string(),
+                progress_save_interval: 30,
+            },
+            lrs: None,
+            localization: LocalizationSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_toml_str(source: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_settings_match_defaults() {
+        let settings = Settings::load("config/settings.toml").unwrap();
+
+        assert_eq!(settings, Settings::default());
+        assert_eq!(settings.education.progress_save_interval, 30);
+    }
+
+    #[test]
+    fn test_lrs_section_is_optional() {
+        let mut source = std::fs::read_to_string("config/settings.toml").unwrap();
+        source.push_str("\n[lrs]\nendpoint = \"http://localhost:8080/xapi\"\nusername = \"key\"\npassword = \"secret\"\n");
+
+        let lrs = Settings::from_toml_str(&source).unwrap().lrs.unwrap();
+        assert_eq!(lrs.endpoint, "http://localhost:8080/xapi");
+        assert_eq!(lrs.timeout_secs, 10);
+        assert_eq!(lrs.base_iri, "https://learnliberty.example");
+    }
+
+    #[test]
+    fn test_localization_section() {
+        let source = std::fs::read_to_string("config/settings.toml").unwrap();
+        let without = source.replace("[localization]", "[unused]");
+        assert_eq!(
+            Settings::from_toml_str(&without).unwrap().localization,
+            LocalizationSettings::default()
+        );
+
+        let spanish = source.replace("locale = \"en-US\"", "locale = \"es-ES\"");
+        assert_eq!(
+            Settings::from_toml_str(&spanish)
+                .unwrap()
+                .localization
+                .locale,
+            "es-ES"
+        );
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        assert!(Settings::from_toml_str("[window]\ntitle = 3").is_err());
+    }
+}
@@ -1,6 +1,12 @@
+use crate::action::ButtonAction;
+use crate::exercise::CodeExercise;
+use crate::hint::Hint;
+use crate::layout::Position;
+use crate::quiz::Quiz;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EducationalContent {
     pub id: String,
     pub title: String,
@@ -8,25 +14,255 @@ pub struct EducationalContent {
     pub media: Vec<String>, // References to assets
     pub interactive_elements: Vec<InteractiveElement>,
     pub completion_criteria: CompletionCriteria,
+    #[serde(default)]
+    pub prerequisites: Vec<String>, // Lesson ids that must be completed first
+    #[serde(default)]
+    pub sections: Vec<LessonSection>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LessonSection {
+    pub title: String,
+    pub level: u8,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ElementRepr")]
 pub struct InteractiveElement {
-    pub element_type: ElementType,
-    pub position: (f32, f32),
-    pub data: String,
+    /// Where the element sits, relative to the design resolution
+    pub position: Position,
+    pub payload: ElementPayload,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ElementType {
     Text,
     Image,
     Button,
     Quiz,
     Video,
+    Code,
+    Exercise,
+}
+
+/// The content carried by each kind of interactive element
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ElementPayload {
+    Text(RichText),
+    Image {
+        asset: String,
+        #[serde(default)]
+        alt_text: String,
+    },
+    Button {
+        label: String,
+        action: ButtonAction,
+    },
+    Quiz(Quiz),
+    Video {
+        asset: String,
+        #[serde(default)]
+        captions: Option<String>, // Asset path of a caption track
+    },
+    Code {
+        #[serde(default)]
+        language: String,
+        code: String,
+    },
+    Exercise {
+        title: String,
+        instructions: RichText,
+        #[serde(default)]
+        hints: Vec<Hint>,
+        /// Starter code and check for exercises learners can run in-game
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<CodeExercise>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RichText {
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub text: String,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub code: bool,
+}
+
+impl RichText {
+    pub fn plain(text: &str) -> Self {
+        Self {
+            spans: vec![TextSpan {
+                text: text.to_string(),
+                ..TextSpan::default()
+            }],
+        }
+    }
+
+    /// Parse inline Markdown emphasis: `**bold**`, `*italic*` and `` `code` ``
+    pub fn from_markdown(text: &str) -> Self {
+        let mut spans: Vec<TextSpan> = Vec::new();
+        let mut current = TextSpan::default();
+        let mut chars = text.chars().peekable();
+
+        let flush = |span: &mut TextSpan, spans: &mut Vec<TextSpan>| {
+            if !span.text.is_empty() {
+                spans.push(span.clone());
+                span.text.clear();
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '`' => {
+                    flush(&mut current, &mut spans);
+                    current.code = !current.code;
+                }
+                '*' if !current.code && chars.peek() == Some(&'*') => {
+                    chars.next();
+                    flush(&mut current, &mut spans);
+                    current.bold = !current.bold;
+                }
+                '*' if !current.code => {
+                    flush(&mut current, &mut spans);
+                    current.italic = !current.italic;
+                }
+                _ => current.text.push(c),
+            }
+        }
+        flush(&mut current, &mut spans);
+
+        Self { spans }
+    }
+
+    /// The text without any formatting
+    pub fn to_plain(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+impl ElementPayload {
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            ElementPayload::Text(_) => ElementType::Text,
+            ElementPayload::Image { .. } => ElementType::Image,
+            ElementPayload::Button { .. } => ElementType::Button,
+            ElementPayload::Quiz(_) => ElementType::Quiz,
+            ElementPayload::Video { .. } => ElementType::Video,
+            ElementPayload::Code { .. } => ElementType::Code,
+            ElementPayload::Exercise { .. } => ElementType::Exercise,
+        }
+    }
+
+    /// Whether using the element counts as an interaction towards the
+    /// completion criteria: clicking a button or passing a runnable
+    /// exercise. Reading text and taking quizzes are tracked separately.
+    pub fn records_interactions(&self) -> bool {
+        matches!(
+            self,
+            ElementPayload::Button { .. } | ElementPayload::Exercise { code: Some(_), .. }
+        )
+    }
+
+    /// Asset paths this payload refers to
+    pub fn asset_references(&self) -> Vec<&str> {
+        match self {
+            ElementPayload::Image { asset, .. } => vec![asset.as_str()],
+            ElementPayload::Video { asset, captions } => std::iter::once(asset.as_str())
+                .chain(captions.as_deref())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl InteractiveElement {
+    pub fn new(position: impl Into<Position>, payload: ElementPayload) -> Self {
+        Self {
+            position: position.into(),
+            payload,
+        }
+    }
+
+    pub fn element_type(&self) -> ElementType {
+        self.payload.element_type()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Accepts both the typed format and the older
+/// `element_type` + `data` string format. Legacy data that does not parse
+/// is kept as text: a button keeps it as its label and does nothing, and
+/// a quiz that is not JSON is shown as plain text.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ElementRepr {
+    Typed {
+        position: Position,
+        payload: ElementPayload,
+    },
+    Legacy {
+        element_type: ElementType,
+        position: Position,
+        data: String,
+    },
+}
+
+impl From<ElementRepr> for InteractiveElement {
+    fn from(repr: ElementRepr) -> Self {
+        let (position, payload) = match repr {
+            ElementRepr::Typed { position, payload } => (position, payload),
+            ElementRepr::Legacy {
+                element_type,
+                position,
+                data,
+            } => {
+                let payload = match element_type {
+                    ElementType::Text => ElementPayload::Text(RichText::plain(&data)),
+                    ElementType::Image => ElementPayload::Image {
+                        asset: data,
+                        alt_text: String::new(),
+                    },
+                    ElementType::Button => ElementPayload::Button {
+                        action: data.parse().unwrap_or_default(),
+                        label: data,
+                    },
+                    ElementType::Quiz => match serde_json::from_str(&data) {
+                        Ok(quiz) => ElementPayload::Quiz(quiz),
+                        Err(_) => ElementPayload::Text(RichText::plain(&data)),
+                    },
+                    ElementType::Video => ElementPayload::Video {
+                        asset: data,
+                        captions: None,
+                    },
+                    ElementType::Code => ElementPayload::Code {
+                        language: String::new(),
+                        code: data,
+                    },
+                    ElementType::Exercise => ElementPayload::Exercise {
+                        title: String::new(),
+                        instructions: RichText::plain(&data),
+                        hints: Vec::new(),
+                        code: None,
+                    },
+                };
+                (position, payload)
+            }
+        };
+        Self { position, payload }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CompletionCriteria {
     pub required_interactions: u32,
     pub time_spent_minimum: f32, // in seconds
@@ -47,6 +283,8 @@ impl EducationalContent {
                 time_spent_minimum: 0.0,
                 quiz_score_threshold: None,
             },
+            prerequisites: Vec::new(),
+            sections: Vec::new(),
         }
     }
 
@@ -57,6 +295,41 @@ impl EducationalContent {
     pub fn add_interactive_element(&mut self, element: InteractiveElement) {
         self.interactive_elements.push(element);
     }
+
+    /// Every asset path referenced by the lesson media and its elements
+    pub fn asset_references(&self) -> Vec<&str> {
+        self.media
+            .iter()
+            .map(|path| path.as_str())
+            .chain(
+                self.interactive_elements
+                    .iter()
+                    .flat_map(|element| element.payload.asset_references()),
+            )
+            .collect()
+    }
+
+    /// Asset references that do not exist below `asset_root`
+    pub fn missing_assets(&self, asset_root: &Path) -> Vec<String> {
+        self.asset_references()
+            .into_iter()
+            .filter(|path| !asset_root.join(path).is_file())
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    /// Fail when any referenced asset does not exist below `asset_root`
+    pub fn validate_assets(&self, asset_root: &Path) -> anyhow::Result<()> {
+        let missing = self.missing_assets(asset_root);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Lesson {} references missing assets: {}",
+                self.id,
+                missing.join(", ")
+            );
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -105,17 +378,118 @@ mod tests {
             "A test lesson".to_string(),
         );
 
-        let element = InteractiveElement {
-            element_type: ElementType::Button,
-            position: (100.0, 200.0),
-            data: "click_me".to_string(),
-        };
+        let element = InteractiveElement::new(
+            (100.0, 200.0),
+            ElementPayload::Button {
+                label: "Next".to_string(),
+                action: ButtonAction::NextSection,
+            },
+        );
 
         content.add_interactive_element(element);
 
         assert_eq!(content.interactive_elements.len(), 1);
-        assert_eq!(content.interactive_elements[0].position, (100.0, 200.0));
-        assert_eq!(content.interactive_elements[0].data, "click_me");
+        assert_eq!(
+            content.interactive_elements[0].position,
+            Position::from((100.0, 200.0))
+        );
+        assert_eq!(
+            content.interactive_elements[0].element_type(),
+            ElementType::Button
+        );
+    }
+
+    #[test]
+    fn test_legacy_element_json() {
+        let json = r#"[
+            {"element_type": "Button", "position": [1.0, 2.0], "data": "next_section"},
+            {"element_type": "Video", "position": [0.0, 0.0], "data": "intro.mp4"}
+        ]"#;
+
+        let elements: Vec<InteractiveElement> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            elements[0].payload,
+            ElementPayload::Button {
+                label: "next_section".to_string(),
+                action: ButtonAction::NextSection,
+            }
+        );
+        assert_eq!(elements[1].payload.asset_references(), vec!["intro.mp4"]);
+
+        let typed = serde_json::to_string(&elements).unwrap();
+        let reread: Vec<InteractiveElement> = serde_json::from_str(&typed).unwrap();
+        assert_eq!(reread, elements);
+    }
+
+    #[test]
+    fn test_baseline_lesson_json_loads() {
+        // A lesson as the original `element_type` + `data` format wrote it
+        let json = r#"{
+            "id": "test_lesson",
+            "title": "Test Lesson",
+            "description": "A test lesson",
+            "media": ["image1.png", "video1.mp4"],
+            "interactive_elements": [
+                {"element_type": "Button", "position": [100.0, 200.0], "data": "click_me"},
+                {"element_type": "Quiz", "position": [0.0, 0.0], "data": "What is a crate?"}
+            ],
+            "completion_criteria": {
+                "required_interactions": 5,
+                "time_spent_minimum": 120.0,
+                "quiz_score_threshold": 0.8
+            }
+        }"#;
+
+        let content: EducationalContent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            content.interactive_elements[0].payload,
+            ElementPayload::Button {
+                label: "click_me".to_string(),
+                action: ButtonAction::None,
+            }
+        );
+        assert_eq!(
+            content.interactive_elements[0].position,
+            Position::from((100.0, 200.0))
+        );
+        assert_eq!(
+            content.interactive_elements[1].payload,
+            ElementPayload::Text(RichText::plain("What is a crate?"))
+        );
+        assert_eq!(content.completion_criteria.quiz_score_threshold, Some(0.8));
+    }
+
+    #[test]
+    fn test_rich_text_from_markdown() {
+        let text = RichText::from_markdown("Use **`Vec`** or *maybe* a slice");
+
+        assert_eq!(text.to_plain(), "Use Vec or maybe a slice");
+        assert!(text.spans[1].bold && text.spans[1].code);
+        assert!(text.spans[3].italic);
+    }
+
+    #[test]
+    fn test_missing_assets() {
+        let mut content = EducationalContent::new(
+            "test_lesson".to_string(),
+            "Test Lesson".to_string(),
+            "A test lesson".to_string(),
+        );
+        content.add_media("images/Learn-Liberty-Banner.jpg".to_string());
+        content.add_interactive_element(InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Image {
+                asset: "images/missing.png".to_string(),
+                alt_text: "Nothing here".to_string(),
+            },
+        ));
+
+        let missing = content.missing_assets(Path::new("assets"));
+
+        assert_eq!(missing, vec!["images/missing.png"]);
+        assert!(content.validate_assets(Path::new("assets")).is_err());
     }
 
     #[test]
@@ -0,0 +1,578 @@
+//! Code exercise module for Learn Liberty
+//!
+//! This module lets learners edit and run the code of a Rust exercise
+//! without leaving the game. The code is compiled with the locally
+//! installed `rustc` in a fresh temporary directory that is removed
+//! afterwards. An exercise passes when its program prints the expected
+//! output, when its attached tests pass, or, with no check, when it
+//! compiles and exits successfully.
+//!
+//! The limits keep a mistake from hanging or flooding the game; they are
+//! not a security boundary. Compiling and running each have a time limit
+//! and keep only the start of their output, and the program gets no input
+//! and an empty environment. On Unix both steps run in their own process
+//! group, which is killed as a whole so children they start cannot
+//! outlive the limit, and their address space is capped with `ulimit -v`.
+//! Elsewhere there is no memory limit and only the direct child is
+//! killed. The code runs as the learner's own user with full access to
+//! their files and the network.
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Environment passed to `rustc` so the toolchain and linker are found;
+/// the learner's program gets none
+const COMPILER_ENVIRONMENT: &[&str] = &[
+    "PATH",
+    "HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+    "CARGO_HOME",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "SystemRoot",
+];
+
+static NEXT_SANDBOX: AtomicU64 = AtomicU64::new(0);
+
+/// How a run of the learner's code is judged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExerciseCheck {
+    /// Compiles and exits successfully
+    #[default]
+    Runs,
+    /// Prints this to stdout, ignoring trailing whitespace
+    Output { expected: String },
+    /// Test functions appended to the code as a `#[cfg(test)]` module
+    Tests { code: String },
+}
+
+/// The runnable part of an exercise element
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CodeExercise {
+    pub starter_code: String,
+    #[serde(default)]
+    pub check: ExerciseCheck,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunLimits {
+    pub compile_timeout: Duration,
+    pub run_timeout: Duration,
+    /// Address space limit of `rustc`; only applied on Unix
+    pub compile_memory_bytes: u64,
+    /// Address space limit of the running program; only applied on Unix
+    pub memory_bytes: u64,
+    /// Output kept from each of stdout and stderr
+    pub output_bytes: usize,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self {
+            compile_timeout: Duration::from_secs(30),
+            run_timeout: Duration::from_secs(5),
+            compile_memory_bytes: 2 * 1024 * 1024 * 1024,
+            memory_bytes: 512 * 1024 * 1024,
+            output_bytes: 64 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunStatus {
+    Passed,
+    CompileError,
+    /// Ran but crashed, failed a test or printed the wrong output
+    Failed,
+    TimedOut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOutcome {
+    pub status: RunStatus,
+    pub stdout: String,
+    /// Compiler messages on a compile error, otherwise the program's stderr
+    pub stderr: String,
+}
+
+impl RunOutcome {
+    pub fn passed(&self) -> bool {
+        self.status == RunStatus::Passed
+    }
+}
+
+/// Removes the temporary directory when the run ends
+struct Sandbox {
+    path: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> anyhow::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "learn_liberty_exercise_{}_{}",
+            std::process::id(),
+            NEXT_SANDBOX.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// What a process left behind once it exited or was killed
+struct Finished {
+    status: Option<ExitStatus>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Compiles and runs exercise code with `rustc`
+#[derive(Debug, Clone)]
+pub struct ExerciseRunner {
+    pub rustc: PathBuf,
+    pub limits: RunLimits,
+}
+
+impl Default for ExerciseRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExerciseRunner {
+    /// Use `rustc` from `$RUSTC` or the `PATH`
+    pub fn new() -> Self {
+        Self {
+            rustc: std::env::var_os("RUSTC")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("rustc")),
+            limits: RunLimits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Compile and run `code` and judge it by the exercise's check. Errors
+    /// are reserved for problems with the runner itself, such as `rustc`
+    /// missing; mistakes in the code are reported in the outcome.
+    pub fn run(&self, exercise: &CodeExercise, code: &str) -> anyhow::Result<RunOutcome> {
+        let sandbox = Sandbox::new()?;
+        let source = sandbox.path.join("main.rs");
+        let program = sandbox
+            .path
+            .join(format!("exercise{}", std::env::consts::EXE_SUFFIX));
+
+        let mut compile = limited_command(&self.rustc, self.limits.compile_memory_bytes);
+        compile
+            .args(["--edition", "2021", "-o"])
+            .arg(&program)
+            .arg(&source);
+        match &exercise.check {
+            ExerciseCheck::Tests { code: tests } => {
+                std::fs::write(
+                    &source,
+                    format!(
+                        "{}\n\n#[cfg(test)]\nmod exercise_tests {{\n    #[allow(unused_imports)]\n    use super::*;\n\n{}\n}}\n",
+                        code, tests
+                    ),
+                )?;
+                compile.arg("--test");
+            }
+            _ => std::fs::write(&source, code)?,
+        }
+        compile.current_dir(&sandbox.path).env_clear();
+        for name in COMPILER_ENVIRONMENT {
+            if let Some(value) = std::env::var_os(name) {
+                compile.env(name, value);
+            }
+        }
+
+        let compiled = self.wait(
+            spawn_piped(&mut compile)
+                .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", self.rustc.display(), e))?,
+            self.limits.compile_timeout,
+        )?;
+        match compiled.status {
+            None => return Ok(outcome(RunStatus::TimedOut, compiled)),
+            // The shell reports a compiler it could not start this way
+            Some(status) if cfg!(unix) && status.code() == Some(127) => anyhow::bail!(
+                "Failed to start {}: {}",
+                self.rustc.display(),
+                compiled.stderr.trim()
+            ),
+            Some(status) if !status.success() => {
+                return Ok(outcome(RunStatus::CompileError, compiled))
+            }
+            Some(_) => {}
+        }
+
+        let mut run = limited_command(&program, self.limits.memory_bytes);
+        run.current_dir(&sandbox.path).env_clear();
+        let ran = self.wait(spawn_piped(&mut run)?, self.limits.run_timeout)?;
+        let status = match (&ran.status, &exercise.check) {
+            (None, _) => RunStatus::TimedOut,
+            (Some(status), _) if !status.success() => RunStatus::Failed,
+            (Some(_), ExerciseCheck::Output { expected })
+                if normalize(&ran.stdout) != normalize(expected) =>
+            {
+                RunStatus::Failed
+            }
+            (Some(_), _) => RunStatus::Passed,
+        };
+        Ok(outcome(status, ran))
+    }
+
+    /// Wait for a child, killing its process group once `timeout` has
+    /// passed. Anything the child started that still holds its output open
+    /// after it exits is killed at the same deadline.
+    fn wait(&self, mut child: Child, timeout: Duration) -> anyhow::Result<Finished> {
+        let _ = child.stdin.take();
+        let stdout = self.capture(child.stdout.take());
+        let stderr = self.capture(child.stderr.take());
+        let deadline = Instant::now() + timeout;
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break Some(status);
+            }
+            if Instant::now() >= deadline {
+                kill_group(&mut child);
+                let _ = child.wait();
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        while !(stdout.is_finished() && stderr.is_finished()) {
+            if Instant::now() >= deadline {
+                // The group outlives its leader while any member is left, so
+                // its id cannot have been reused yet
+                kill_group(&mut child);
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        Ok(Finished {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+
+    /// Read a pipe to its end on another thread, keeping only the first
+    /// `output_bytes` so a chatty program cannot block or exhaust memory
+    fn capture(&self, pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+        let limit = self.limits.output_bytes;
+        std::thread::spawn(move || {
+            let Some(mut pipe) = pipe else {
+                return String::new();
+            };
+            let mut kept = Vec::new();
+            let mut buffer = [0u8; 4096];
+            while let Ok(read) = pipe.read(&mut buffer) {
+                if read == 0 {
+                    break;
+                }
+                let room = limit.saturating_sub(kept.len());
+                kept.extend_from_slice(&buffer[..read.min(room)]);
+            }
+            String::from_utf8_lossy(&kept).into_owned()
+        })
+    }
+}
+
+/// A command for `program` in its own process group whose address space is
+/// capped at `memory_bytes`. Arguments added later are passed to `program`.
+#[cfg(unix)]
+fn limited_command(program: &Path, memory_bytes: u64) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut command = Command::new("/bin/sh");
+    command
+        .arg("-c")
+        .arg("ulimit -v \"$1\" && shift && exec \"$@\"")
+        .arg("sh")
+        .arg((memory_bytes / 1024).to_string())
+        .arg(program)
+        .process_group(0);
+    command
+}
+
+#[cfg(not(unix))]
+fn limited_command(program: &Path, _memory_bytes: u64) -> Command {
+    Command::new(program)
+}
+
+/// Kill every process in the child's group, which shares the child's id
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    let _ = Command::new("kill")
+        .args(["-KILL", "--"])
+        .arg(format!("-{}", child.id()))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Start a command with no input and both outputs captured
+fn spawn_piped(command: &mut Command) -> std::io::Result<Child> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+fn outcome(status: RunStatus, finished: Finished) -> RunOutcome {
+    RunOutcome {
+        status,
+        stdout: finished.stdout,
+        stderr: finished.stderr,
+    }
+}
+
+/// Output compared line by line without trailing whitespace
+fn normalize(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_end().to_string()
+}
+
+/// Expected and actual output compared the way an output check compares
+/// them, one line per entry: lines both share are kept as they are, and
+/// differing ones appear as `- expected` followed by `+ actual`
+pub fn output_diff(expected: &str, actual: &str) -> Vec<String> {
+    let (expected, actual) = (normalize(expected), normalize(actual));
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut diff = Vec::new();
+    for index in 0..expected.len().max(actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(wanted), Some(got)) if wanted == got => diff.push(wanted.to_string()),
+            (wanted, got) => {
+                diff.extend(wanted.map(|line| format!("- {}", line)));
+                diff.extend(got.map(|line| format!("+ {}", line)));
+            }
+        }
+    }
+    diff
+}
+
+/// The learner's copy of an exercise's code, edited with key presses
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodeEditor {
+    text: String,
+    /// Byte offset of the cursor, always on a character boundary
+    cursor: usize,
+}
+
+impl CodeEditor {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.len(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    /// Apply a key from `InputEvent::KeyPress`. Returns `false` for keys
+    /// the editor does not use.
+    pub fn handle_key(&mut self, key: &str) -> bool {
+        match key {
+            "Enter" => self.insert("\n"),
+            "Tab" => self.insert("    "),
+            "Space" => self.insert(" "),
+            "Backspace" => {
+                if let Some(previous) = self.previous_boundary() {
+                    self.text.replace_range(previous..self.cursor, "");
+                    self.cursor = previous;
+                }
+            }
+            "Delete" => {
+                if let Some(next) = self.next_boundary() {
+                    self.text.replace_range(self.cursor..next, "");
+                }
+            }
+            "ArrowLeft" => self.cursor = self.previous_boundary().unwrap_or(0),
+            "ArrowRight" => self.cursor = self.next_boundary().unwrap_or(self.text.len()),
+            "Home" => self.cursor = self.line_start(),
+            "End" => {
+                self.cursor = self.text[self.cursor..]
+                    .find('\n')
+                    .map_or(self.text.len(), |offset| self.cursor + offset)
+            }
+            _ if key.chars().count() == 1 => self.insert(key),
+            _ => return false,
+        }
+        true
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map(|c| self.cursor + c.len_utf8())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(check: ExerciseCheck) -> CodeExercise {
+        CodeExercise {
+            starter_code: String::new(),
+            check,
+        }
+    }
+
+    #[test]
+    fn test_output_check() {
+        let runner = ExerciseRunner::new();
+        let hello = exercise(ExerciseCheck::Output {
+            expected: "Hello, Ferris!\n".to_string(),
+        });
+
+        let passed = runner
+            .run(&hello, "fn main() { println!(\"Hello, Ferris!  \"); }")
+            .unwrap();
+        assert_eq!(passed.status, RunStatus::Passed);
+
+        let wrong = runner
+            .run(&hello, "fn main() { println!(\"Hello\"); }")
+            .unwrap();
+        assert_eq!(wrong.status, RunStatus::Failed);
+        assert_eq!(wrong.stdout, "Hello\n");
+        assert_eq!(
+            output_diff("Hello, Ferris!\n", &wrong.stdout),
+            vec!["- Hello, Ferris!", "+ Hello"]
+        );
+        assert_eq!(
+            output_diff("a\nb\nc", "a  \nx\n"),
+            vec!["a", "- b", "+ x", "- c"]
+        );
+
+        let broken = runner
+            .run(&hello, "fn main() { let x: u8 = \"no\"; }")
+            .unwrap();
+        assert_eq!(broken.status, RunStatus::CompileError);
+        assert!(broken.stderr.contains("mismatched types"));
+
+        let missing = ExerciseRunner {
+            rustc: PathBuf::from("/nonexistent/rustc"),
+            ..ExerciseRunner::new()
+        };
+        assert!(missing.run(&hello, "fn main() {}").is_err());
+    }
+
+    #[test]
+    fn test_attached_tests() {
+        let runner = ExerciseRunner::new();
+        let add = exercise(ExerciseCheck::Tests {
+            code: "    #[test]\n    fn adds() { assert_eq!(add(2, 3), 5); }".to_string(),
+        });
+
+        let code = "fn add(a: i32, b: i32) -> i32 { a + b }\nfn main() {}";
+        assert!(runner.run(&add, code).unwrap().passed());
+        let wrong = "fn add(a: i32, b: i32) -> i32 { a - b }\nfn main() {}";
+        assert_eq!(runner.run(&add, wrong).unwrap().status, RunStatus::Failed);
+    }
+
+    #[test]
+    fn test_limits_stop_runaway_programs() {
+        let runner = ExerciseRunner::new().with_limits(RunLimits {
+            run_timeout: Duration::from_millis(500),
+            memory_bytes: 256 * 1024 * 1024,
+            output_bytes: 16,
+            ..RunLimits::default()
+        });
+        let runs = exercise(ExerciseCheck::Runs);
+
+        let looping = runner.run(&runs, "fn main() { loop {} }").unwrap();
+        assert_eq!(looping.status, RunStatus::TimedOut);
+
+        let chatty = runner
+            .run(
+                &runs,
+                "fn main() { for _ in 0..100_000 { println!(\"spam\"); } }",
+            )
+            .unwrap();
+        assert!(chatty.passed());
+        assert_eq!(chatty.stdout.len(), 16);
+
+        if cfg!(unix) {
+            let started = Instant::now();
+            let detached = "fn main() { std::process::Command::new(\"/bin/sh\").args([\"-c\", \"sleep 30\"]).spawn().unwrap(); }";
+            assert!(runner.run(&runs, detached).unwrap().passed());
+            assert!(started.elapsed() < Duration::from_secs(10));
+
+            let greedy = "fn main() { let v = vec![1u8; 1 << 30]; println!(\"{}\", v[0]); }";
+            assert_eq!(runner.run(&runs, greedy).unwrap().status, RunStatus::Failed);
+        }
+    }
+
+    #[test]
+    fn test_editor_keys() {
+        let mut editor = CodeEditor::new("fn main() {}");
+
+        for key in ["ArrowLeft", "Enter", "Tab", "x", "Backspace", "é"] {
+            assert!(editor.handle_key(key));
+        }
+        assert_eq!(editor.text(), "fn main() {\n    é}");
+        editor.handle_key("Home");
+        editor.handle_key("Delete");
+        assert_eq!(editor.text(), "fn main() {\n   é}");
+        assert!(!editor.handle_key("F5"));
+    }
+}
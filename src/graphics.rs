@@ -1,24 +1,55 @@
 //! Graphics engine module for Learn Liberty
 //!
 //! This module provides a simplified graphics engine interface
-//! for the educational RPG application.
+//! for the educational RPG application. Callers queue draw commands each
+//! frame and `render` submits them, keeping the last frame for inspection.
+
+/// Something to draw, in window pixels with the origin at the top left
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Quad {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: [f32; 4],
+    },
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        text: String,
+        color: [f32; 4],
+    },
+    /// Restrict the following commands to a rectangle, or lift the limit
+    Clip(Option<[f32; 4]>),
+}
 
 #[allow(dead_code)]
 pub struct GraphicsEngine {
     pub width: u32,
     pub height: u32,
     pub frame_count: u32,
+    queued: Vec<DrawCommand>,
+    last_frame: Vec<DrawCommand>,
 }
 
 impl GraphicsEngine {
     pub async fn new(window: &winit::window::Window) -> anyhow::Result<Self> {
         // Get window size
         let size = window.inner_size();
-        Ok(Self {
-            width: size.width,
-            height: size.height,
+        Ok(Self::headless(size.width, size.height))
+    }
+
+    /// Create an engine that is not attached to a window, for tests and tools
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
             frame_count: 0,
-        })
+            queued: Vec::new(),
+            last_frame: Vec::new(),
+        }
     }
 
     #[allow(dead_code)]
@@ -27,8 +58,19 @@ impl GraphicsEngine {
         self.height = height;
     }
 
+    /// Queue a command for the next `render`
+    pub fn draw(&mut self, command: DrawCommand) {
+        self.queued.push(command);
+    }
+
+    /// Commands submitted by the most recent `render`
+    pub fn last_frame(&self) -> &[DrawCommand] {
+        &self.last_frame
+    }
+
     pub fn render(&mut self) -> anyhow::Result<()> {
         self.frame_count += 1;
+        self.last_frame = std::mem::take(&mut self.queued);
         // Simulate rendering work
         std::thread::sleep(std::time::Duration::from_millis(1));
         Ok(())
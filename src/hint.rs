@@ -0,0 +1,142 @@
+//! Hint module for Learn Liberty
+//!
+//! This module lets quiz questions and exercises carry tiers of hints that
+//! are revealed one at a time. Each tier has a score penalty which the quiz
+//! scorer deducts from the question's credit, and every reveal is counted
+//! so the learner's progress can show teachers where help was needed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Credit deducted per revealed hint unless the hint sets its own penalty
+pub const DEFAULT_HINT_PENALTY: f32 = 0.1;
+
+fn default_penalty() -> f32 {
+    DEFAULT_HINT_PENALTY
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hint {
+    pub text: String,
+    /// Fraction of the question's credit lost by revealing this hint
+    #[serde(default = "default_penalty")]
+    pub penalty: f32,
+}
+
+impl Hint {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            penalty: DEFAULT_HINT_PENALTY,
+        }
+    }
+}
+
+/// Combined penalty of the first `revealed` tiers, capped at full credit
+pub fn total_penalty(hints: &[Hint], revealed: usize) -> f32 {
+    hints
+        .iter()
+        .take(revealed)
+        .map(|hint| hint.penalty.max(0.0))
+        .sum::<f32>()
+        .min(1.0)
+}
+
+/// How many hint tiers a learner has revealed for each question or
+/// exercise, keyed by question id or a namespaced id such as
+/// `exercise/<title>`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HintTracker {
+    revealed: BTreeMap<String, usize>,
+}
+
+impl HintTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reveal the next tier for `target_id`, or `None` once every tier is
+    /// showing
+    pub fn reveal<'a>(&mut self, target_id: &str, hints: &'a [Hint]) -> Option<&'a Hint> {
+        let revealed = self.revealed(target_id);
+        let hint = hints.get(revealed)?;
+        self.revealed.insert(target_id.to_string(), revealed + 1);
+        Some(hint)
+    }
+
+    pub fn revealed(&self, target_id: &str) -> usize {
+        self.revealed.get(target_id).copied().unwrap_or(0)
+    }
+
+    /// The tiers currently showing for `target_id`
+    pub fn revealed_hints<'a>(&self, target_id: &str, hints: &'a [Hint]) -> &'a [Hint] {
+        &hints[..self.revealed(target_id).min(hints.len())]
+    }
+
+    /// Every target with at least one revealed hint
+    pub fn usage(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.revealed
+            .iter()
+            .map(|(target_id, &revealed)| (target_id.as_str(), revealed))
+    }
+}
+
+/// A record of the hints a learner used on one question or exercise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HintUsage {
+    pub lesson_id: String,
+    pub target_id: String,
+    pub tiers_revealed: u32,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints() -> Vec<Hint> {
+        vec![
+            Hint::new("Think about who owns the String."),
+            Hint {
+                text: "Use a reference: &s".to_string(),
+                penalty: 0.3,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_reveal_one_tier_at_a_time() {
+        let hints = hints();
+        let mut tracker = HintTracker::new();
+
+        assert!(tracker.revealed_hints("q1", &hints).is_empty());
+        assert_eq!(tracker.reveal("q1", &hints), Some(&hints[0]));
+        assert_eq!(tracker.revealed_hints("q1", &hints).len(), 1);
+        assert_eq!(tracker.reveal("q1", &hints), Some(&hints[1]));
+        assert_eq!(tracker.reveal("q1", &hints), None);
+        assert_eq!(tracker.revealed("q1"), 2);
+        assert_eq!(tracker.revealed("q2"), 0);
+    }
+
+    #[test]
+    fn test_total_penalty_is_capped() {
+        let hints = hints();
+
+        assert_eq!(total_penalty(&hints, 0), 0.0);
+        assert_eq!(total_penalty(&hints, 1), DEFAULT_HINT_PENALTY);
+        assert!((total_penalty(&hints, 5) - 0.4).abs() < 1e-6);
+
+        let harsh = vec![Hint {
+            text: "Answer".to_string(),
+            penalty: 2.0,
+        }];
+        assert_eq!(total_penalty(&harsh, 1), 1.0);
+    }
+
+    #[test]
+    fn test_hint_penalty_defaults_when_missing() {
+        let hint: Hint = serde_json::from_str(r#"{"text": "Look at line 3"}"#).unwrap();
+        assert_eq!(hint.penalty, DEFAULT_HINT_PENALTY);
+    }
+}
@@ -0,0 +1,338 @@
+//! Inventory module for Learn Liberty
+//!
+//! This module loads item definitions from a data file and provides
+//! the per-player inventory, including slot stacking and world pickups.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Number of inventory slots given to a fresh player
+pub const DEFAULT_INVENTORY_SLOTS: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemDefinition {
+    pub id: String,
+    pub name: String,
+    /// Icon image, relative to the assets directory
+    #[serde(default)]
+    pub icon: Option<String>,
+    pub stack_size: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Lesson that becomes available once the player holds this item
+    #[serde(default)]
+    pub unlocks_lesson: Option<String>,
+}
+
+impl ItemDefinition {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemFile {
+    #[serde(default)]
+    item: Vec<ItemDefinition>,
+}
+
+/// All item definitions known to the game, keyed by id
+#[derive(Debug, Default)]
+pub struct ItemDatabase {
+    items: HashMap<String, ItemDefinition>,
+}
+
+impl ItemDatabase {
+    pub fn from_toml_str(source: &str) -> anyhow::Result<Self> {
+        let file: ItemFile = toml::from_str(source)?;
+        let mut items = HashMap::new();
+
+        for definition in file.item {
+            if definition.stack_size == 0 {
+                anyhow::bail!("Item {} has a stack size of zero", definition.id);
+            }
+            if items.contains_key(&definition.id) {
+                anyhow::bail!("Duplicate item id: {}", definition.id);
+            }
+            items.insert(definition.id.clone(), definition);
+        }
+
+        Ok(Self { items })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&source)
+    }
+
+    pub fn get(&self, item_id: &str) -> Option<&ItemDefinition> {
+        self.items.get(item_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a ItemDefinition> {
+        self.items.values().filter(move |item| item.has_tag(tag))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+/// An item lying in the world, waiting to be collected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldPickup {
+    pub item_id: String,
+    pub quantity: u32,
+    pub position: (f32, f32),
+}
+
+impl WorldPickup {
+    pub fn new(item_id: &str, quantity: u32, position: (f32, f32)) -> Self {
+        Self {
+            item_id: item_id.to_string(),
+            quantity,
+            position,
+        }
+    }
+
+    /// The items lying around town at the start of a new game
+    pub fn town() -> Vec<WorldPickup> {
+        vec![
+            WorldPickup::new("scroll_of_ownership", 1, (-96.0, 64.0)),
+            WorldPickup::new("crab_coin", 3, (-128.0, 384.0)),
+            WorldPickup::new("crab_coin", 5, (448.0, 416.0)),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: vec![None; capacity],
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Add items, filling existing stacks before empty slots.
+    /// Returns the quantity that did not fit.
+    pub fn add(
+        &mut self,
+        items: &ItemDatabase,
+        item_id: &str,
+        quantity: u32,
+    ) -> anyhow::Result<u32> {
+        let definition = items
+            .get(item_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown item: {}", item_id))?;
+        let mut remaining = quantity;
+
+        for stack in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.item_id == item_id && stack.quantity < definition.stack_size {
+                let moved = remaining.min(definition.stack_size - stack.quantity);
+                stack.quantity += moved;
+                remaining -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let moved = remaining.min(definition.stack_size);
+            *slot = Some(ItemStack {
+                item_id: item_id.to_string(),
+                quantity: moved,
+            });
+            remaining -= moved;
+        }
+
+        Ok(remaining)
+    }
+
+    /// How many more of an item fit, in partly filled stacks and empty
+    /// slots
+    pub fn room_for(&self, items: &ItemDatabase, item_id: &str) -> anyhow::Result<u32> {
+        let definition = items
+            .get(item_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown item: {}", item_id))?;
+        Ok(self
+            .slots
+            .iter()
+            .map(|slot| match slot {
+                None => definition.stack_size,
+                Some(stack) if stack.item_id == item_id => {
+                    definition.stack_size.saturating_sub(stack.quantity)
+                }
+                Some(_) => 0,
+            })
+            .sum())
+    }
+
+    /// Remove items, failing without changes if not enough are held
+    pub fn remove(&mut self, item_id: &str, quantity: u32) -> anyhow::Result<()> {
+        let held = self.count(item_id);
+        if held < quantity {
+            anyhow::bail!(
+                "Cannot remove {} x {}: only {} held",
+                quantity,
+                item_id,
+                held
+            );
+        }
+
+        let mut remaining = quantity;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(stack) = slot.as_mut().filter(|stack| stack.item_id == item_id) {
+                let taken = remaining.min(stack.quantity);
+                stack.quantity -= taken;
+                remaining -= taken;
+                if stack.quantity == 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn count(&self, item_id: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item_id == item_id)
+            .map(|stack| stack.quantity)
+            .sum()
+    }
+
+    pub fn contains(&self, item_id: &str, quantity: u32) -> bool {
+        self.count(item_id) >= quantity
+    }
+
+    /// Collect a world pickup. The pickup keeps whatever did not fit.
+    pub fn pick_up(
+        &mut self,
+        items: &ItemDatabase,
+        pickup: &mut WorldPickup,
+    ) -> anyhow::Result<u32> {
+        let leftover = self.add(items, &pickup.item_id, pickup.quantity)?;
+        let collected = pickup.quantity - leftover;
+        pickup.quantity = leftover;
+        Ok(collected)
+    }
+
+    /// Lessons unlocked by the items currently held
+    pub fn unlocked_lessons(&self, items: &ItemDatabase) -> Vec<String> {
+        let mut lessons: Vec<String> = self
+            .slots
+            .iter()
+            .flatten()
+            .filter_map(|stack| items.get(&stack.item_id))
+            .filter_map(|definition| definition.unlocks_lesson.clone())
+            .collect();
+        lessons.sort();
+        lessons.dedup();
+        lessons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: &str = r#"
+        [[item]]
+        id = "key"
+        name = "Key"
+        icon = "key.png"
+        stack_size = 1
+        tags = ["quest"]
+        unlocks_lesson = "lesson_2"
+
+        [[item]]
+        id = "coin"
+        name = "Coin"
+        icon = "coin.png"
+        stack_size = 10
+    "#;
+
+    #[test]
+    fn test_load_item_definitions() {
+        let items = ItemDatabase::from_toml_str(ITEMS).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items.get("coin").unwrap().stack_size, 10);
+        assert_eq!(items.with_tag("quest").count(), 1);
+
+        let shipped = ItemDatabase::load("assets/data/items.toml").unwrap();
+        assert!(!shipped.is_empty());
+    }
+
+    #[test]
+    fn test_stacking_and_overflow() {
+        let items = ItemDatabase::from_toml_str(ITEMS).unwrap();
+        let mut inventory = Inventory::new(2);
+
+        assert_eq!(inventory.add(&items, "coin", 15).unwrap(), 0);
+        assert_eq!(inventory.count("coin"), 15);
+        assert_eq!(inventory.add(&items, "coin", 10).unwrap(), 5);
+        assert_eq!(inventory.add(&items, "key", 1).unwrap(), 1);
+        assert!(inventory.add(&items, "sword", 1).is_err());
+
+        inventory.remove("coin", 12).unwrap();
+        assert_eq!(inventory.count("coin"), 8);
+        assert!(inventory.remove("coin", 9).is_err());
+    }
+
+    #[test]
+    fn test_pickup_unlocks_lesson() {
+        let items = ItemDatabase::from_toml_str(ITEMS).unwrap();
+        let mut inventory = Inventory::new(4);
+        let mut pickup = WorldPickup {
+            item_id: "key".to_string(),
+            quantity: 1,
+            position: (32.0, 64.0),
+        };
+
+        assert_eq!(inventory.pick_up(&items, &mut pickup).unwrap(), 1);
+        assert_eq!(pickup.quantity, 0);
+        assert_eq!(inventory.unlocked_lessons(&items), vec!["lesson_2"]);
+    }
+
+    #[test]
+    fn test_inventory_serde_round_trip() {
+        let items = ItemDatabase::from_toml_str(ITEMS).unwrap();
+        let mut inventory = Inventory::new(3);
+        inventory.add(&items, "coin", 12).unwrap();
+
+        let json = serde_json::to_string(&inventory).unwrap();
+        let restored: Inventory = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, inventory);
+        assert_eq!(restored.count("coin"), 12);
+    }
+}
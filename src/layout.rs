@@ -0,0 +1,406 @@
+//! Layout module for Learn Liberty
+//!
+//! This module places interactive elements independently of the window
+//! size. Lessons give each position as offsets from an anchor (a corner,
+//! an edge midpoint or the center) of the screen's safe area. Offsets are
+//! either design pixels, authored against the design resolution and
+//! scaled uniformly to the window, or percentages of the safe area. A
+//! plain `[x, y]` pair is read as design pixels from the top left, which
+//! is how lessons were written before anchors existed. `ElementLayout`
+//! is the layout pass that turns a lesson's positions into window pixels
+//! and redoes it whenever the window size changes.
+
+use crate::education::InteractiveElement;
+use crate::ui::Rect;
+use crate::window::DESIGN_RESOLUTION;
+use serde::{Deserialize, Serialize};
+
+/// The point of the safe area a position is measured from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far across and down the safe area the anchor sits, from 0 to 1
+    pub fn factors(self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// An offset along one axis. Written as a number for design pixels or as
+/// a string like `"25%"` for a share of the safe area.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "LengthRepr", into = "LengthRepr")]
+pub enum Length {
+    Design(f32),
+    Percent(f32),
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::Design(0.0)
+    }
+}
+
+impl Length {
+    /// The offset in window pixels, given the design-to-window scale and
+    /// the length of the safe area along this axis
+    pub fn to_pixels(self, scale: f32, extent: f32) -> f32 {
+        match self {
+            Length::Design(pixels) => pixels * scale,
+            Length::Percent(percent) => percent / 100.0 * extent,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LengthRepr {
+    Number(f32),
+    Text(String),
+}
+
+impl TryFrom<LengthRepr> for Length {
+    type Error = String;
+
+    fn try_from(repr: LengthRepr) -> Result<Self, Self::Error> {
+        match repr {
+            LengthRepr::Number(pixels) => Ok(Length::Design(pixels)),
+            LengthRepr::Text(text) => text
+                .trim()
+                .strip_suffix('%')
+                .and_then(|number| number.trim().parse().ok())
+                .map(Length::Percent)
+                .ok_or_else(|| {
+                    format!(
+                        "invalid length {:?}, expected a number or a percentage",
+                        text
+                    )
+                }),
+        }
+    }
+}
+
+impl From<Length> for LengthRepr {
+    fn from(length: Length) -> Self {
+        match length {
+            Length::Design(pixels) => LengthRepr::Number(pixels),
+            Length::Percent(percent) => LengthRepr::Text(format!("{}%", percent)),
+        }
+    }
+}
+
+/// Where an element's top-left corner goes: `x` to the right of and `y`
+/// below its anchor. Offsets from bottom or right anchors are usually
+/// negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "PositionRepr", into = "PositionRepr")]
+pub struct Position {
+    pub anchor: Anchor,
+    pub x: Length,
+    pub y: Length,
+}
+
+impl Position {
+    pub fn new(anchor: Anchor, x: Length, y: Length) -> Self {
+        Self { anchor, x, y }
+    }
+}
+
+impl From<(f32, f32)> for Position {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self::new(Anchor::TopLeft, Length::Design(x), Length::Design(y))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PositionRepr {
+    Pair(f32, f32),
+    Anchored {
+        #[serde(default)]
+        anchor: Anchor,
+        #[serde(default)]
+        x: Length,
+        #[serde(default)]
+        y: Length,
+    },
+}
+
+impl From<PositionRepr> for Position {
+    fn from(repr: PositionRepr) -> Self {
+        match repr {
+            PositionRepr::Pair(x, y) => Position::from((x, y)),
+            PositionRepr::Anchored { anchor, x, y } => Position::new(anchor, x, y),
+        }
+    }
+}
+
+impl From<Position> for PositionRepr {
+    fn from(position: Position) -> Self {
+        match position {
+            Position {
+                anchor: Anchor::TopLeft,
+                x: Length::Design(x),
+                y: Length::Design(y),
+            } => PositionRepr::Pair(x, y),
+            Position { anchor, x, y } => PositionRepr::Anchored { anchor, x, y },
+        }
+    }
+}
+
+/// Window pixels kept clear of content on each side, for notches, rounded
+/// corners or television overscan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafeArea {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// The reference frame positions are resolved in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub design_resolution: (f32, f32),
+    pub safe_area: SafeArea,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::new((DESIGN_RESOLUTION.0 as f32, DESIGN_RESOLUTION.1 as f32))
+    }
+}
+
+impl Layout {
+    pub fn new(design_resolution: (f32, f32)) -> Self {
+        Self {
+            design_resolution,
+            safe_area: SafeArea::default(),
+        }
+    }
+
+    pub fn with_safe_area(mut self, safe_area: SafeArea) -> Self {
+        self.safe_area = safe_area;
+        self
+    }
+
+    /// The part of a `width` by `height` window content may use
+    pub fn safe_rect(&self, width: f32, height: f32) -> Rect {
+        let area = &self.safe_area;
+        Rect::new(
+            area.left,
+            area.top,
+            (width - area.left - area.right).max(0.0),
+            (height - area.top - area.bottom).max(0.0),
+        )
+    }
+
+    /// Window pixels per design pixel, chosen so the whole design
+    /// resolution fits inside the safe area
+    pub fn scale(&self, width: f32, height: f32) -> f32 {
+        let safe = self.safe_rect(width, height);
+        let (design_width, design_height) = self.design_resolution;
+        if design_width <= 0.0 || design_height <= 0.0 {
+            return 1.0;
+        }
+        (safe.width / design_width).min(safe.height / design_height)
+    }
+
+    /// Where a position lands in a `width` by `height` window
+    pub fn resolve(&self, position: &Position, width: f32, height: f32) -> (f32, f32) {
+        let safe = self.safe_rect(width, height);
+        let scale = self.scale(width, height);
+        let (across, down) = position.anchor.factors();
+        (
+            safe.x + across * safe.width + position.x.to_pixels(scale, safe.width),
+            safe.y + down * safe.height + position.y.to_pixels(scale, safe.height),
+        )
+    }
+}
+
+/// A lesson's element positions, resolved for the current window size
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElementLayout {
+    layout: Layout,
+    positions: Vec<Position>,
+    window_size: Option<(u32, u32)>,
+    resolved: Vec<(f32, f32)>,
+}
+
+impl ElementLayout {
+    pub fn new(layout: Layout, elements: &[InteractiveElement]) -> Self {
+        Self {
+            layout,
+            positions: elements.iter().map(|element| element.position).collect(),
+            window_size: None,
+            resolved: Vec::new(),
+        }
+    }
+
+    /// Lay the elements out for a new window size. Returns false when the
+    /// size is unchanged and the previous pass still holds.
+    pub fn resize(&mut self, width: u32, height: u32) -> bool {
+        if self.window_size == Some((width, height)) {
+            return false;
+        }
+        let (width_f, height_f) = (width as f32, height as f32);
+        self.resolved = self
+            .positions
+            .iter()
+            .map(|position| self.layout.resolve(position, width_f, height_f))
+            .collect();
+        self.window_size = Some((width, height));
+        true
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Swap the reference frame; the next `resize` lays out again
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.window_size = None;
+    }
+
+    pub fn window_size(&self) -> Option<(u32, u32)> {
+        self.window_size
+    }
+
+    /// The window position of an element, once a pass has run
+    pub fn position(&self, element_index: usize) -> Option<(f32, f32)> {
+        self.resolved.get(element_index).copied()
+    }
+
+    pub fn positions(&self) -> &[(f32, f32)] {
+        &self.resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::education::{ElementPayload, RichText};
+
+    #[test]
+    fn test_design_pixels_scale_with_the_window() {
+        let layout = Layout::default();
+        let position = Position::from((100.0, 200.0));
+
+        assert_eq!(layout.resolve(&position, 800.0, 600.0), (100.0, 200.0));
+        assert_eq!(layout.resolve(&position, 1600.0, 1200.0), (200.0, 400.0));
+        // A wider window scales by its height so the design still fits
+        assert_eq!(layout.resolve(&position, 1920.0, 1080.0), (180.0, 360.0));
+    }
+
+    #[test]
+    fn test_anchors_and_percentages() {
+        let layout = Layout::default();
+        let center = Position::new(Anchor::Center, Length::Design(-50.0), Length::Design(0.0));
+        let corner = Position::new(
+            Anchor::BottomRight,
+            Length::Design(-100.0),
+            Length::Percent(-10.0),
+        );
+
+        assert_eq!(layout.resolve(&center, 800.0, 600.0), (350.0, 300.0));
+        assert_eq!(
+            layout.resolve(&center, 1920.0, 1080.0),
+            (960.0 - 90.0, 540.0)
+        );
+        assert_eq!(
+            layout.resolve(&corner, 1920.0, 1080.0),
+            (1920.0 - 180.0, 1080.0 - 108.0)
+        );
+    }
+
+    #[test]
+    fn test_safe_area_insets_content() {
+        let layout = Layout::default().with_safe_area(SafeArea {
+            left: 40.0,
+            top: 20.0,
+            right: 40.0,
+            bottom: 20.0,
+        });
+
+        assert_eq!(
+            layout.safe_rect(880.0, 640.0),
+            Rect::new(40.0, 20.0, 800.0, 600.0)
+        );
+        assert_eq!(layout.scale(880.0, 640.0), 1.0);
+        assert_eq!(
+            layout.resolve(&Position::default(), 880.0, 640.0),
+            (40.0, 20.0)
+        );
+        let bottom = Position::new(Anchor::Bottom, Length::Percent(0.0), Length::Design(-30.0));
+        assert_eq!(layout.resolve(&bottom, 880.0, 640.0), (440.0, 590.0));
+    }
+
+    #[test]
+    fn test_position_serde_accepts_pairs_and_anchors() {
+        let pair: Position = serde_json::from_str("[10.0, 20.0]").unwrap();
+        assert_eq!(pair, Position::from((10.0, 20.0)));
+        assert_eq!(serde_json::to_string(&pair).unwrap(), "[10.0,20.0]");
+
+        let anchored: Position =
+            toml::from_str("anchor = \"top_right\"\nx = \"-25%\"\ny = 16").unwrap();
+        assert_eq!(
+            anchored,
+            Position::new(
+                Anchor::TopRight,
+                Length::Percent(-25.0),
+                Length::Design(16.0)
+            )
+        );
+        let json = serde_json::to_string(&anchored).unwrap();
+        assert_eq!(serde_json::from_str::<Position>(&json).unwrap(), anchored);
+
+        assert!(serde_json::from_str::<Position>(r#"{"x": "wide"}"#).is_err());
+    }
+
+    #[test]
+    fn test_element_layout_reruns_on_resize() {
+        let elements = vec![
+            InteractiveElement::new((100.0, 50.0), ElementPayload::Text(RichText::plain("Hi"))),
+            InteractiveElement::new(
+                Position::new(Anchor::Center, Length::Design(0.0), Length::Design(0.0)),
+                ElementPayload::Text(RichText::plain("Middle")),
+            ),
+        ];
+        let mut layout = ElementLayout::new(Layout::default(), &elements);
+        assert_eq!(layout.position(0), None);
+
+        assert!(layout.resize(800, 600));
+        assert_eq!(layout.positions(), &[(100.0, 50.0), (400.0, 300.0)]);
+        assert!(!layout.resize(800, 600));
+
+        assert!(layout.resize(1920, 1080));
+        assert_eq!(layout.position(0), Some((180.0, 90.0)));
+        assert_eq!(layout.position(1), Some((960.0, 540.0)));
+        assert_eq!(layout.window_size(), Some((1920, 1080)));
+    }
+}
@@ -0,0 +1,257 @@
+//! Lesson catalog module for Learn Liberty
+//!
+//! This module stores lessons by id together with the prerequisite edges
+//! between them. A catalog is only built when every prerequisite refers
+//! to a known lesson and the prerequisites form no cycle, so unlock checks
+//! and the recommended lesson order can rely on a well-formed graph.
+
+use crate::education::EducationalContent;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct LessonCatalog {
+    lessons: Vec<EducationalContent>,
+    index: HashMap<String, usize>,
+    order: Vec<usize>,
+}
+
+impl LessonCatalog {
+    pub fn new(lessons: Vec<EducationalContent>) -> anyhow::Result<Self> {
+        let mut index = HashMap::new();
+        for (position, lesson) in lessons.iter().enumerate() {
+            if index.insert(lesson.id.clone(), position).is_some() {
+                anyhow::bail!("Duplicate lesson id: {}", lesson.id);
+            }
+        }
+
+        for lesson in &lessons {
+            for prerequisite in &lesson.prerequisites {
+                if !index.contains_key(prerequisite) {
+                    anyhow::bail!(
+                        "Lesson {} requires unknown lesson {}",
+                        lesson.id,
+                        prerequisite
+                    );
+                }
+            }
+        }
+
+        let order = topological_order(&lessons, &index)?;
+        Ok(Self {
+            lessons,
+            index,
+            order,
+        })
+    }
+
+    /// Load the Markdown lessons in a directory into a catalog
+    pub fn load(directory: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::new(crate::lesson_loader::load_lessons(directory)?)
+    }
+
+    /// Check that the media of every lesson exists below `asset_root`,
+    /// with one error per lesson that is missing some. The lessons stay
+    /// playable without their media, so callers report these rather than
+    /// refusing the catalog.
+    pub fn validate_assets(&self, asset_root: &Path) -> Vec<anyhow::Error> {
+        self.lessons
+            .iter()
+            .filter_map(|lesson| lesson.validate_assets(asset_root).err())
+            .collect()
+    }
+
+    pub fn get(&self, lesson_id: &str) -> Option<&EducationalContent> {
+        self.index.get(lesson_id).map(|&i| &self.lessons[i])
+    }
+
+    pub fn len(&self) -> usize {
+        self.lessons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lessons.is_empty()
+    }
+
+    /// Lessons in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = &EducationalContent> {
+        self.lessons.iter()
+    }
+
+    /// Lessons ordered so that every lesson follows its prerequisites
+    pub fn ordered(&self) -> impl Iterator<Item = &EducationalContent> {
+        self.order.iter().map(|&i| &self.lessons[i])
+    }
+
+    /// A lesson is unlocked once all of its prerequisites are completed, or
+    /// when it is in `granted`, the lessons the learner was given access to
+    /// by an item or a quest reward
+    pub fn is_unlocked(&self, lesson_id: &str, completed: &[String], granted: &[String]) -> bool {
+        self.get(lesson_id).is_some_and(|lesson| {
+            granted.contains(&lesson.id)
+                || lesson
+                    .prerequisites
+                    .iter()
+                    .all(|prerequisite| completed.contains(prerequisite))
+        })
+    }
+
+    /// Unlocked lessons the learner has not completed yet
+    pub fn available_lessons(
+        &self,
+        completed: &[String],
+        granted: &[String],
+    ) -> Vec<&EducationalContent> {
+        self.ordered()
+            .filter(|lesson| !completed.contains(&lesson.id))
+            .filter(|lesson| self.is_unlocked(&lesson.id, completed, granted))
+            .collect()
+    }
+
+    /// The first available lesson in prerequisite order
+    pub fn recommended_next(
+        &self,
+        completed: &[String],
+        granted: &[String],
+    ) -> Option<&EducationalContent> {
+        self.available_lessons(completed, granted)
+            .into_iter()
+            .next()
+    }
+}
+
+fn topological_order(
+    lessons: &[EducationalContent],
+    index: &HashMap<String, usize>,
+) -> anyhow::Result<Vec<usize>> {
+    prerequisite_order(lessons, index).map_err(|cyclic| {
+        let ids: Vec<&str> = cyclic.iter().map(|&i| lessons[i].id.as_str()).collect();
+        anyhow::anyhow!("Lesson prerequisites form a cycle: {}", ids.join(", "))
+    })
+}
+
+/// Kahn's algorithm, preferring catalog order among ready lessons. On a
+/// cycle the error holds every lesson that could not be ordered. Every
+/// prerequisite must be in `index`.
+pub(crate) fn prerequisite_order(
+    lessons: &[EducationalContent],
+    index: &HashMap<String, usize>,
+) -> Result<Vec<usize>, Vec<usize>> {
+    let mut remaining: Vec<usize> = lessons.iter().map(|l| l.prerequisites.len()).collect();
+    let mut dependents = vec![Vec::new(); lessons.len()];
+    for (position, lesson) in lessons.iter().enumerate() {
+        for prerequisite in &lesson.prerequisites {
+            dependents[index[prerequisite]].push(position);
+        }
+    }
+
+    let mut ready: Vec<usize> = (0..lessons.len()).filter(|&i| remaining[i] == 0).collect();
+    let mut order = Vec::with_capacity(lessons.len());
+
+    while !ready.is_empty() {
+        let next = ready.remove(0);
+        order.push(next);
+        for &dependent in &dependents[next] {
+            remaining[dependent] -= 1;
+            if remaining[dependent] == 0 {
+                let at = ready.partition_point(|&i| i < dependent);
+                ready.insert(at, dependent);
+            }
+        }
+    }
+
+    if order.len() != lessons.len() {
+        return Err((0..lessons.len()).filter(|&i| remaining[i] > 0).collect());
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lesson(id: &str, prerequisites: &[&str]) -> EducationalContent {
+        let mut lesson = EducationalContent::new(id.to_string(), id.to_string(), String::new());
+        lesson.prerequisites = prerequisites.iter().map(|p| p.to_string()).collect();
+        lesson
+    }
+
+    fn completed(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unlocks_follow_prerequisites() {
+        let catalog = LessonCatalog::new(vec![
+            lesson("traits", &["basics", "types"]),
+            lesson("basics", &[]),
+            lesson("types", &["basics"]),
+        ])
+        .unwrap();
+
+        assert!(catalog.is_unlocked("basics", &[], &[]));
+        assert!(!catalog.is_unlocked("types", &[], &[]));
+        assert!(!catalog.is_unlocked("traits", &completed(&["basics"]), &[]));
+        assert!(catalog.is_unlocked("traits", &completed(&["basics", "types"]), &[]));
+        assert!(!catalog.is_unlocked("missing", &[], &[]));
+        assert!(catalog.is_unlocked("traits", &[], &completed(&["traits"])));
+        assert!(!catalog.is_unlocked("missing", &[], &completed(&["missing"])));
+    }
+
+    #[test]
+    fn test_recommended_next_lesson() {
+        let catalog = LessonCatalog::new(vec![
+            lesson("traits", &["types"]),
+            lesson("types", &["basics"]),
+            lesson("basics", &[]),
+        ])
+        .unwrap();
+
+        let order: Vec<&str> = catalog.ordered().map(|l| l.id.as_str()).collect();
+        assert_eq!(order, vec!["basics", "types", "traits"]);
+        assert_eq!(catalog.recommended_next(&[], &[]).unwrap().id, "basics");
+        assert_eq!(
+            catalog
+                .recommended_next(&completed(&["basics"]), &[])
+                .unwrap()
+                .id,
+            "types"
+        );
+        assert!(catalog
+            .recommended_next(&completed(&["basics", "types", "traits"]), &[])
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_catalogs_rejected() {
+        let cycle = LessonCatalog::new(vec![lesson("a", &["b"]), lesson("b", &["a"])]);
+        assert!(cycle.unwrap_err().to_string().contains("cycle"));
+
+        let missing = LessonCatalog::new(vec![lesson("a", &["ghost"])]);
+        assert!(missing.unwrap_err().to_string().contains("ghost"));
+
+        let duplicate = LessonCatalog::new(vec![lesson("a", &[]), lesson("a", &[])]);
+        assert!(duplicate.is_err());
+    }
+
+    #[test]
+    fn test_shipped_lessons_form_a_path() {
+        let catalog = LessonCatalog::load("lessons").unwrap();
+
+        assert_eq!(catalog.len(), 10);
+        assert_eq!(
+            catalog.recommended_next(&[], &[]).unwrap().id,
+            "01-rust-basics-and-project-structure"
+        );
+        assert_eq!(catalog.available_lessons(&[], &[]).len(), 1);
+        assert!(catalog.validate_assets(Path::new("assets")).is_empty());
+
+        let mut broken = lesson("broken", &[]);
+        broken.add_media("images/missing.png".to_string());
+        let catalog = LessonCatalog::new(vec![broken, lesson("basics", &[])]).unwrap();
+        let errors = catalog.validate_assets(Path::new("assets"));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("images/missing.png"));
+    }
+}
@@ -0,0 +1,495 @@
+//! Lesson loader module for Learn Liberty
+//!
+//! This module turns the Markdown lessons in `lessons/` into
+//! `EducationalContent`. A lesson may start with TOML front matter
+//! between `+++` lines to set its id, title, prerequisites and completion
+//! criteria; everything else is taken from the Markdown itself. Level two
+//! and three headings become sections, fenced code blocks become `Code`
+//! elements, `### Exercise` headings become `Exercise` elements (with each
+//! `**Hint**:` or `> Hint:` line as a hint tier) and `![alt](path)` images are added as
+//! media. Inside an exercise the first `rust` block is the starter code
+//! learners can run, and an `output` or `rust,test` block is the expected
+//! output or the tests it is checked against.
+
+use crate::education::{
+    CompletionCriteria, EducationalContent, ElementPayload, InteractiveElement, LessonSection,
+    RichText,
+};
+use crate::exercise::{CodeExercise, ExerciseCheck};
+use crate::hint::Hint;
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Vertical distance between generated elements in the lesson panel
+const ELEMENT_SPACING: f32 = 64.0;
+
+/// Left margin of generated elements in the lesson panel
+const ELEMENT_MARGIN: f32 = 32.0;
+
+const FRONT_MATTER_DELIMITER: &str = "+++";
+
+/// Line prefixes that mark a hint tier inside an exercise
+const HINT_PREFIXES: [&str; 3] = ["**Hint**:", "**Hint:**", "> Hint:"];
+
+/// The hint text of an exercise line, if the line is a hint
+fn hint_text(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    HINT_PREFIXES
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .map(str::trim)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FrontMatter {
+    id: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    prerequisites: Vec<String>,
+    completion: Option<CompletionCriteria>,
+}
+
+/// Split optional front matter from the Markdown body
+fn split_front_matter(source: &str) -> anyhow::Result<(FrontMatter, &str)> {
+    let trimmed = source.trim_start();
+    let Some(rest) = trimmed.strip_prefix(FRONT_MATTER_DELIMITER) else {
+        return Ok((FrontMatter::default(), source));
+    };
+
+    let end = rest
+        .find(&format!("\n{}", FRONT_MATTER_DELIMITER))
+        .ok_or_else(|| anyhow::anyhow!("Front matter is missing its closing +++"))?;
+    let front_matter = toml::from_str(&rest[..end]).context("Invalid lesson front matter")?;
+    let body = &rest[end + 1 + FRONT_MATTER_DELIMITER.len()..];
+
+    Ok((front_matter, body))
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some((level as u8, line[level..].trim()))
+    } else {
+        None
+    }
+}
+
+/// Extract the path of every `![alt](path)` image on a line
+fn image_paths(line: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("![") {
+        rest = &rest[start + 2..];
+        let Some(close) = rest.find("](") else { break };
+        rest = &rest[close + 2..];
+        let Some(end) = rest.find(')') else { break };
+        paths.push(rest[..end].trim().to_string());
+        rest = &rest[end + 1..];
+    }
+    paths
+}
+
+#[derive(Default)]
+struct LessonBuilder {
+    title: Option<String>,
+    description: Option<String>,
+    sections: Vec<LessonSection>,
+    elements: Vec<ElementPayload>,
+    media: Vec<String>,
+    paragraph: Vec<String>,
+    exercise: Option<(String, Vec<String>)>,
+    exercise_code: Option<CodeExercise>,
+}
+
+impl LessonBuilder {
+    fn section_line(&mut self, line: &str) {
+        if let Some(section) = self.sections.last_mut() {
+            section.body.push_str(line);
+            section.body.push('\n');
+        }
+    }
+
+    fn body_line(&mut self, line: &str) {
+        self.section_line(line);
+        if let Some((_, body)) = self.exercise.as_mut() {
+            body.push(line.to_string());
+        }
+    }
+
+    /// Whether a code block with this language belongs to the exercise
+    /// being read rather than being a `Code` element
+    fn is_exercise_code(&self, language: &str) -> bool {
+        self.exercise.is_some()
+            && match language {
+                "rust" => self.exercise_code.is_none(),
+                "output" | "rust,test" => self.exercise_code.is_some(),
+                _ => false,
+            }
+    }
+
+    fn exercise_block(&mut self, language: &str, code: String) {
+        match language {
+            "rust" => {
+                self.exercise_code = Some(CodeExercise {
+                    starter_code: code,
+                    check: ExerciseCheck::Runs,
+                })
+            }
+            "output" => {
+                if let Some(exercise) = self.exercise_code.as_mut() {
+                    exercise.check = ExerciseCheck::Output { expected: code };
+                }
+            }
+            _ => {
+                if let Some(exercise) = self.exercise_code.as_mut() {
+                    exercise.check = ExerciseCheck::Tests { code };
+                }
+            }
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        if self.description.is_none() && !self.paragraph.is_empty() {
+            self.description = Some(self.paragraph.join("\n"));
+        }
+        self.paragraph.clear();
+    }
+
+    fn end_exercise(&mut self) {
+        if let Some((title, body)) = self.exercise.take() {
+            let (hints, instructions): (Vec<&String>, Vec<&String>) =
+                body.iter().partition(|line| hint_text(line).is_some());
+            let instructions: Vec<&str> = instructions.iter().map(|line| line.as_str()).collect();
+            self.elements.push(ElementPayload::Exercise {
+                title,
+                instructions: RichText::from_markdown(instructions.join("\n").trim()),
+                hints: hints
+                    .iter()
+                    .filter_map(|line| hint_text(line))
+                    .map(Hint::new)
+                    .collect(),
+                code: self.exercise_code.take(),
+            });
+        }
+    }
+}
+
+/// Parse one Markdown lesson. `default_id` is used when the front matter
+/// does not set an id, usually the file stem.
+pub fn parse_lesson(source: &str, default_id: &str) -> anyhow::Result<EducationalContent> {
+    let (front_matter, body) = split_front_matter(source)?;
+    let mut builder = LessonBuilder::default();
+    // Language, lines and whether the block belongs to the exercise
+    let mut code_block: Option<(String, Vec<String>, bool)> = None;
+
+    for line in body.lines() {
+        if let Some(fence) = line.trim_start().strip_prefix("```") {
+            match code_block.take() {
+                Some((language, code, true)) => {
+                    builder.exercise_block(&language, code.join("\n"));
+                    builder.section_line(line);
+                }
+                Some((language, code, false)) => {
+                    builder.elements.push(ElementPayload::Code {
+                        language,
+                        code: code.join("\n"),
+                    });
+                    builder.body_line(line);
+                }
+                None => {
+                    builder.end_paragraph();
+                    let language = fence.trim().to_string();
+                    let for_exercise = builder.is_exercise_code(&language);
+                    if for_exercise {
+                        builder.section_line(line);
+                    } else {
+                        builder.body_line(line);
+                    }
+                    code_block = Some((language, Vec::new(), for_exercise));
+                }
+            }
+            continue;
+        }
+
+        if let Some((_, code, for_exercise)) = code_block.as_mut() {
+            code.push(line.to_string());
+            if *for_exercise {
+                builder.section_line(line);
+            } else {
+                builder.body_line(line);
+            }
+            continue;
+        }
+
+        if let Some((level, text)) = heading(line) {
+            builder.end_paragraph();
+            builder.end_exercise();
+            if level == 1 {
+                builder.title.get_or_insert_with(|| text.to_string());
+            } else {
+                builder.sections.push(LessonSection {
+                    title: text.to_string(),
+                    level,
+                    body: String::new(),
+                });
+                if level == 3 && text.to_lowercase().starts_with("exercise") {
+                    builder.exercise = Some((text.to_string(), Vec::new()));
+                }
+            }
+            continue;
+        }
+
+        builder.media.extend(image_paths(line));
+        if line.trim().is_empty() {
+            builder.end_paragraph();
+        } else {
+            builder.paragraph.push(line.trim().to_string());
+        }
+        builder.body_line(line);
+    }
+
+    if code_block.is_some() {
+        anyhow::bail!("Unterminated code block");
+    }
+    builder.end_paragraph();
+    builder.end_exercise();
+
+    let title = front_matter
+        .title
+        .or(builder.title)
+        .ok_or_else(|| anyhow::anyhow!("Lesson has no title heading"))?;
+    let mut content = EducationalContent::new(
+        front_matter.id.unwrap_or_else(|| default_id.to_string()),
+        title,
+        front_matter
+            .description
+            .or(builder.description)
+            .unwrap_or_default(),
+    );
+
+    for path in builder.media {
+        content.add_media(path);
+    }
+    for (index, payload) in builder.elements.into_iter().enumerate() {
+        content.add_interactive_element(InteractiveElement::new(
+            (
+                ELEMENT_MARGIN,
+                ELEMENT_MARGIN + index as f32 * ELEMENT_SPACING,
+            ),
+            payload,
+        ));
+    }
+    content.sections = builder.sections;
+    content.prerequisites = front_matter.prerequisites;
+    if let Some(criteria) = front_matter.completion {
+        content.completion_criteria = criteria;
+    }
+
+    Ok(content)
+}
+
+pub fn load_lesson(path: impl AsRef<Path>) -> anyhow::Result<EducationalContent> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let default_id = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+
+    parse_lesson(&source, default_id).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Paths of the Markdown lessons in a directory, ordered by file name.
+/// `README.md` is skipped.
+pub fn lesson_paths(directory: impl AsRef<Path>) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths: Vec<_> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter(|path| !path.ends_with("README.md"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Load every Markdown lesson in a directory, ordered by file name
+pub fn load_lessons(directory: impl AsRef<Path>) -> anyhow::Result<Vec<EducationalContent>> {
+    lesson_paths(directory)?.iter().map(load_lesson).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::education::ElementType;
+    use crate::layout::Layout;
+
+    const LESSON: &str = r#"+++
+id = "ownership"
+prerequisites = ["intro"]
+
+[completion]
+required_interactions = 2
+quiz_score_threshold = 0.75
++++
+# Lesson 1: Ownership
+
+Learn who owns what.
+
+## Example
+
+![Borrow diagram](images/borrow.png)
+
+```rust
+let a = String::from("hi");
+```
+
+## Exercises
+
+### Exercise 1: Move a String
+Move `a` into a function.
+
+**Hint**: Functions take ownership.
+> Hint: Look at the function signature.
+> Hint: Pass `a` without `&`.
+"#;
+
+    #[test]
+    fn test_parse_front_matter_and_body() {
+        let lesson = parse_lesson(LESSON, "fallback").unwrap();
+
+        assert_eq!(lesson.id, "ownership");
+        assert_eq!(lesson.title, "Lesson 1: Ownership");
+        assert_eq!(lesson.description, "Learn who owns what.");
+        assert_eq!(lesson.prerequisites, vec!["intro"]);
+        assert_eq!(lesson.completion_criteria.required_interactions, 2);
+        assert_eq!(lesson.completion_criteria.quiz_score_threshold, Some(0.75));
+        assert_eq!(lesson.media, vec!["images/borrow.png"]);
+    }
+
+    #[test]
+    fn test_sections_code_and_exercises() {
+        let lesson = parse_lesson(LESSON, "fallback").unwrap();
+
+        let titles: Vec<&str> = lesson.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Example", "Exercises", "Exercise 1: Move a String"]
+        );
+        assert!(lesson.sections[0].body.contains("String::from"));
+
+        assert_eq!(lesson.interactive_elements.len(), 2);
+        let code = &lesson.interactive_elements[0];
+        assert_eq!(
+            code.payload,
+            ElementPayload::Code {
+                language: "rust".to_string(),
+                code: "let a = String::from(\"hi\");".to_string(),
+            }
+        );
+        let exercise = &lesson.interactive_elements[1];
+        let ElementPayload::Exercise {
+            title,
+            instructions,
+            hints,
+            code,
+        } = &exercise.payload
+        else {
+            panic!("expected an exercise");
+        };
+        assert_eq!(title, "Exercise 1: Move a String");
+        assert_eq!(instructions.to_plain(), "Move a into a function.");
+        assert_eq!(hints.len(), 3);
+        assert_eq!(hints[0].text, "Functions take ownership.");
+        assert_eq!(hints[1].text, "Look at the function signature.");
+        assert!(code.is_none());
+        let layout = Layout::default();
+        let below =
+            |element: &InteractiveElement| layout.resolve(&element.position, 800.0, 600.0).1;
+        assert!(below(exercise) > below(&lesson.interactive_elements[0]));
+    }
+
+    #[test]
+    fn test_shipped_lesson_exercise_hints() {
+        let lesson = load_lesson("lessons/04-collections-and-graphics-pipeline.md").unwrap();
+
+        let exercises: Vec<(&String, &RichText, &Vec<Hint>)> = lesson
+            .interactive_elements
+            .iter()
+            .filter_map(|element| match &element.payload {
+                ElementPayload::Exercise {
+                    title,
+                    instructions,
+                    hints,
+                    ..
+                } => Some((title, instructions, hints)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(exercises.len(), 5);
+        for (title, instructions, hints) in &exercises {
+            assert_eq!(hints.len(), 1, "{}", title);
+            assert!(!instructions.to_plain().contains("Hint"), "{}", title);
+        }
+        assert_eq!(exercises[2].2[0].text, "Use `map` and `collect` methods.");
+    }
+
+    #[test]
+    fn test_runnable_exercise_blocks() {
+        let source = "# Runnable\n\n### Exercise: Greet\nPrint a greeting.\n\n\
+                      ```rust\nfn main() {}\n```\n\n```output\nHello\n```\n\n\
+                      ```toml\n[package]\n```\n";
+        let lesson = parse_lesson(source, "runnable").unwrap();
+
+        assert_eq!(lesson.interactive_elements.len(), 2);
+        let ElementPayload::Exercise {
+            instructions, code, ..
+        } = &lesson.interactive_elements[1].payload
+        else {
+            panic!("expected an exercise");
+        };
+        assert!(instructions.to_plain().starts_with("Print a greeting."));
+        assert!(!instructions.to_plain().contains("fn main"));
+        assert_eq!(
+            code.as_ref().unwrap(),
+            &CodeExercise {
+                starter_code: "fn main() {}".to_string(),
+                check: ExerciseCheck::Output {
+                    expected: "Hello".to_string()
+                },
+            }
+        );
+        assert_eq!(
+            lesson.interactive_elements[0].element_type(),
+            ElementType::Code
+        );
+    }
+
+    #[test]
+    fn test_lesson_without_front_matter() {
+        let lesson = parse_lesson("# Plain\n\nJust text.\n", "plain_lesson").unwrap();
+
+        assert_eq!(lesson.id, "plain_lesson");
+        assert!(lesson.prerequisites.is_empty());
+        assert!(parse_lesson("```rust\nfn main() {}\n", "broken").is_err());
+        assert!(parse_lesson("+++\nid = 3\n+++\n# Bad\n", "bad").is_err());
+    }
+
+    #[test]
+    fn test_load_shipped_lessons() {
+        let lessons = load_lessons("lessons").unwrap();
+
+        assert_eq!(lessons.len(), 10);
+        assert_eq!(lessons[0].id, "01-rust-basics-and-project-structure");
+        for lesson in &lessons {
+            let exercises = lesson
+                .interactive_elements
+                .iter()
+                .filter(|e| e.element_type() == ElementType::Exercise)
+                .count();
+            assert!(exercises > 0, "{} has no exercises", lesson.id);
+        }
+    }
+}
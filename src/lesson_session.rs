@@ -0,0 +1,272 @@
+//! Lesson session module for Learn Liberty
+//!
+//! This module tracks a learner working through one lesson: which
+//! interactive elements they used, how long they actively spent on it and
+//! the quiz scores they reached. The session evaluates the lesson's
+//! `CompletionCriteria` and keeps `AppState` progress in sync.
+
+use crate::education::{CompletionCriteria, EducationalContent};
+use crate::quiz::QuizResult;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+
+/// A completion criterion the learner has not met yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UnmetCriterion {
+    Interactions { done: u32, required: u32 },
+    TimeSpent { spent: f32, required: f32 },
+    QuizScore { best: Option<f32>, threshold: f32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonSession {
+    pub lesson_id: String,
+    pub criteria: CompletionCriteria,
+    /// Interaction count for each interactive element, by index
+    pub interactions: Vec<u32>,
+    /// Seconds spent while the window was focused and the lesson unpaused
+    pub active_time: f64,
+    pub quiz_scores: Vec<f32>,
+    focused: bool,
+    paused: bool,
+}
+
+impl LessonSession {
+    pub fn new(lesson: &EducationalContent) -> Self {
+        Self {
+            lesson_id: lesson.id.clone(),
+            criteria: lesson.completion_criteria.clone(),
+            interactions: vec![0; lesson.interactive_elements.len()],
+            active_time: 0.0,
+            quiz_scores: Vec::new(),
+            focused: true,
+            paused: false,
+        }
+    }
+
+    pub fn record_interaction(&mut self, element_index: usize) -> anyhow::Result<()> {
+        let count = self.interactions.get_mut(element_index).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Lesson {} has no interactive element {}",
+                self.lesson_id,
+                element_index
+            )
+        })?;
+        *count += 1;
+        Ok(())
+    }
+
+    /// Number of distinct elements the learner interacted with
+    pub fn elements_interacted(&self) -> u32 {
+        self.interactions.iter().filter(|&&count| count > 0).count() as u32
+    }
+
+    /// Record a quiz score between 0.0 and 1.0
+    pub fn record_quiz_score(&mut self, score: f32) {
+        self.quiz_scores.push(score.clamp(0.0, 1.0));
+    }
+
+    pub fn record_quiz_result(&mut self, result: &QuizResult) {
+        self.record_quiz_score(result.score);
+    }
+
+    pub fn best_quiz_score(&self) -> Option<f32> {
+        self.quiz_scores.iter().copied().reduce(f32::max)
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.focused && !self.paused
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        if self.is_active() {
+            self.active_time += delta_time;
+        }
+    }
+
+    pub fn unmet_criteria(&self) -> Vec<UnmetCriterion> {
+        let mut unmet = Vec::new();
+
+        let done = self.elements_interacted();
+        if done < self.criteria.required_interactions {
+            unmet.push(UnmetCriterion::Interactions {
+                done,
+                required: self.criteria.required_interactions,
+            });
+        }
+
+        let spent = self.active_time as f32;
+        if spent < self.criteria.time_spent_minimum {
+            unmet.push(UnmetCriterion::TimeSpent {
+                spent,
+                required: self.criteria.time_spent_minimum,
+            });
+        }
+
+        if let Some(threshold) = self.criteria.quiz_score_threshold {
+            let best = self.best_quiz_score();
+            if best.is_none_or(|score| score < threshold) {
+                unmet.push(UnmetCriterion::QuizScore { best, threshold });
+            }
+        }
+
+        unmet
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.unmet_criteria().is_empty()
+    }
+
+    /// Average progress over the criteria the lesson requires
+    pub fn progress(&self) -> f32 {
+        let mut parts = Vec::new();
+
+        if self.criteria.required_interactions > 0 {
+            parts.push(
+                self.elements_interacted() as f32 / self.criteria.required_interactions as f32,
+            );
+        }
+        if self.criteria.time_spent_minimum > 0.0 {
+            parts.push(self.active_time as f32 / self.criteria.time_spent_minimum);
+        }
+        if let Some(threshold) = self.criteria.quiz_score_threshold {
+            let best = self.best_quiz_score().unwrap_or(0.0);
+            parts.push(if threshold > 0.0 {
+                best / threshold
+            } else {
+                1.0
+            });
+        }
+
+        if parts.is_empty() {
+            return 1.0;
+        }
+        let total: f32 = parts.iter().map(|part| part.min(1.0)).sum();
+        total / parts.len() as f32
+    }
+
+    /// Write the session progress into the app state, marking the lesson
+    /// completed once every criterion is met
+    pub fn sync(&self, state: &mut AppState) {
+        state.advance_lesson(self.lesson_id.clone(), self.progress());
+        if self.is_complete() {
+            state.complete_lesson_with_score(&self.lesson_id, self.best_quiz_score());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::ButtonAction;
+    use crate::education::{ElementPayload, InteractiveElement};
+
+    fn lesson() -> EducationalContent {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Ownership".to_string(),
+        );
+        for data in ["intro", "example", "quiz"] {
+            lesson.add_interactive_element(InteractiveElement::new(
+                (0.0, 0.0),
+                ElementPayload::Button {
+                    label: data.to_string(),
+                    action: ButtonAction::RevealHint {
+                        hint: data.to_string(),
+                    },
+                },
+            ));
+        }
+        lesson.completion_criteria = CompletionCriteria {
+            required_interactions: 2,
+            time_spent_minimum: 60.0,
+            quiz_score_threshold: Some(0.8),
+        };
+        lesson
+    }
+
+    #[test]
+    fn test_interactions_count_distinct_elements() {
+        let mut session = LessonSession::new(&lesson());
+
+        session.record_interaction(0).unwrap();
+        session.record_interaction(0).unwrap();
+        assert_eq!(session.elements_interacted(), 1);
+        assert!(session.record_interaction(7).is_err());
+
+        session.record_interaction(2).unwrap();
+        assert_eq!(session.elements_interacted(), 2);
+        assert!(!session
+            .unmet_criteria()
+            .iter()
+            .any(|c| matches!(c, UnmetCriterion::Interactions { .. })));
+    }
+
+    #[test]
+    fn test_only_active_time_counts() {
+        let mut session = LessonSession::new(&lesson());
+
+        session.update(20.0);
+        session.set_paused(true);
+        session.update(100.0);
+        session.set_paused(false);
+        session.set_focused(false);
+        session.update(100.0);
+        session.set_focused(true);
+        session.update(20.0);
+
+        assert_eq!(session.active_time, 40.0);
+        assert!(session
+            .unmet_criteria()
+            .contains(&UnmetCriterion::TimeSpent {
+                spent: 40.0,
+                required: 60.0
+            }));
+    }
+
+    #[test]
+    fn test_completion_and_state_sync() {
+        let mut session = LessonSession::new(&lesson());
+        let mut state = AppState::default();
+
+        session.record_interaction(0).unwrap();
+        session.update(30.0);
+        session.record_quiz_score(0.6);
+        session.sync(&mut state);
+        assert_eq!(state.current_lesson_id, "lesson_1");
+        assert!(state.lesson_progress > 0.0 && state.lesson_progress < 1.0);
+        assert!(!state.is_lesson_completed("lesson_1"));
+
+        session.record_interaction(1).unwrap();
+        session.update(30.0);
+        session.record_quiz_score(0.9);
+        assert!(session.is_complete());
+        session.sync(&mut state);
+        assert_eq!(state.lesson_progress, 1.0);
+        assert!(state.is_lesson_completed("lesson_1"));
+    }
+
+    #[test]
+    fn test_fresh_shipped_lessons_are_not_complete() {
+        let catalog = crate::lesson_catalog::LessonCatalog::load("lessons").unwrap();
+
+        for lesson in catalog.iter() {
+            let mut session = LessonSession::new(lesson);
+            session.update(0.016);
+            assert!(!session.is_complete(), "{} completes on open", lesson.id);
+            assert!(session.progress() < 0.01);
+
+            session.update(lesson.completion_criteria.time_spent_minimum as f64);
+            assert!(session.is_complete(), "{} cannot be completed", lesson.id);
+        }
+    }
+}
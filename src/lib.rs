@@ -4,15 +4,72 @@
 //! by Casey Moriarty. This is a simple 2D four-way scroller RPG designed for
 //! educational content delivery.
 
+pub mod achievement;
+pub mod action;
+pub mod adaptive;
+pub mod analytics;
+pub mod autosave;
+pub mod cli;
+pub mod config;
 pub mod education;
+pub mod exercise;
 pub mod graphics;
+pub mod hint;
+pub mod inventory;
+pub mod layout;
+pub mod lesson_catalog;
+pub mod lesson_loader;
+pub mod lesson_runtime;
+pub mod lesson_session;
+pub mod lint;
+pub mod localization;
+pub mod lrs;
+pub mod package;
+pub mod profile;
+pub mod quest;
+pub mod quiz;
+pub mod report;
+pub mod review;
+pub mod save;
+pub mod scene;
 pub mod state;
+pub mod ui;
 pub mod window;
 
 // Re-export main types for easier access
-pub use education::{CompletionCriteria, EducationalContent, ElementType, InteractiveElement};
+pub use achievement::{AchievementDatabase, AchievementEvent, AchievementProgress};
+pub use action::ButtonAction;
+pub use adaptive::{AdaptiveSelector, BktParams, MasteryModel};
+pub use analytics::{Actor, EventLog, LearningEvent, Verb};
+pub use config::Settings;
+pub use education::{
+    CompletionCriteria, EducationalContent, ElementPayload, ElementType, InteractiveElement,
+    LessonSection, RichText, TextSpan,
+};
+pub use exercise::{CodeEditor, CodeExercise, ExerciseCheck, ExerciseRunner, RunOutcome};
 pub use graphics::GraphicsEngine;
+pub use hint::{Hint, HintTracker, HintUsage};
+pub use inventory::{Inventory, ItemDatabase, ItemDefinition, ItemStack, WorldPickup};
+pub use layout::{Anchor, ElementLayout, Layout, Length, Position, SafeArea};
+pub use lesson_catalog::LessonCatalog;
+pub use lesson_runtime::LessonRuntime;
+pub use lesson_session::{LessonSession, UnmetCriterion};
+pub use lint::{Diagnostic, Linter};
+pub use localization::{Bundle, Localizer};
+pub use lrs::{LrsClient, LrsSync, StatementQueue};
+pub use package::PackageFormat;
+pub use profile::{LearnerPreferences, LearnerProfile, LearnerProgress, ProfileStore};
+pub use quest::{
+    Objective, ObjectiveKind, ObjectiveOrder, Quest, QuestEvent, QuestLog, QuestState,
+    QuestTrigger, Reward,
+};
+pub use quiz::{Answer, Question, QuestionKind, Quiz, QuizResult};
+pub use report::ClassReport;
+pub use review::{ReviewCard, ReviewScheduler};
+pub use save::{SaveData, SaveManager};
+pub use scene::{Scene, SceneStack, SceneTransition};
 pub use state::AppState;
+pub use ui::{LayoutTree, UiState};
 pub use window::WindowManager;
 
 pub mod tests {
@@ -42,6 +99,25 @@ pub mod tests {
         MouseClick { x: f32, y: f32 },
     }
 
+    impl MockEvent {
+        /// The window input this event stands for; `Close` has none
+        pub fn to_input(&self) -> Option<window::InputEvent> {
+            match self {
+                MockEvent::Close => None,
+                MockEvent::Resize { width, height } => Some(window::InputEvent::Resize {
+                    width: *width,
+                    height: *height,
+                }),
+                MockEvent::KeyPress { key } => {
+                    Some(window::InputEvent::KeyPress { key: key.clone() })
+                }
+                MockEvent::MouseClick { x, y } => {
+                    Some(window::InputEvent::MouseClick { x: *x, y: *y })
+                }
+            }
+        }
+    }
+
     impl MockWindow {
         pub fn new(title: &str, width: u32, height: u32) -> (Self, mpsc::Sender<MockEvent>) {
             let (tx, rx) = mpsc::channel();
@@ -0,0 +1,325 @@
+//! Content lint module for Learn Liberty
+//!
+//! This module checks lesson content for authoring mistakes without
+//! playing through it: missing media, malformed quizzes, elements placed
+//! outside the design resolution, duplicate ids, unknown or cyclic
+//! prerequisites and completion criteria no learner could meet. Every
+//! problem is collected with the file it came from instead of stopping at
+//! the first one.
+
+use crate::education::{EducationalContent, ElementPayload};
+use crate::layout::Layout;
+use crate::lesson_catalog::prerequisite_order;
+use crate::lesson_loader::{lesson_paths, load_lesson};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// One authoring problem and where it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    /// Index of the interactive element, when the problem is in one
+    pub element: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.element {
+            Some(index) => write!(
+                f,
+                "{}: element {}: {}",
+                self.file.display(),
+                index,
+                self.message
+            ),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Checks lessons against an asset directory and a design resolution
+pub struct Linter {
+    pub asset_root: PathBuf,
+    pub design_resolution: (f32, f32),
+}
+
+impl Linter {
+    pub fn new(asset_root: impl Into<PathBuf>, design_resolution: (f32, f32)) -> Self {
+        Self {
+            asset_root: asset_root.into(),
+            design_resolution,
+        }
+    }
+
+    /// Lint every lesson in a directory
+    pub fn lint_directory(&self, directory: impl AsRef<Path>) -> anyhow::Result<Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let mut files = Vec::new();
+        let mut lessons = Vec::new();
+
+        for path in lesson_paths(directory)? {
+            match load_lesson(&path) {
+                Ok(lesson) => {
+                    files.push(path);
+                    lessons.push(lesson);
+                }
+                Err(e) => diagnostics.push(Diagnostic {
+                    file: path,
+                    element: None,
+                    message: format!("{:#}", e),
+                }),
+            }
+        }
+
+        diagnostics.extend(self.lint_lessons(&files, &lessons));
+        Ok(diagnostics)
+    }
+
+    /// Lint loaded lessons; `files[i]` is where `lessons[i]` came from
+    pub fn lint_lessons(
+        &self,
+        files: &[PathBuf],
+        lessons: &[EducationalContent],
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (path, lesson) in files.iter().zip(lessons) {
+            diagnostics.extend(self.lint_lesson(path, lesson));
+        }
+        diagnostics.extend(lint_graph(files, lessons));
+        diagnostics
+    }
+
+    /// Checks that only need the lesson itself
+    pub fn lint_lesson(&self, path: &Path, lesson: &EducationalContent) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut report = |element: Option<usize>, message: String| {
+            diagnostics.push(Diagnostic {
+                file: path.to_path_buf(),
+                element,
+                message,
+            })
+        };
+
+        if lesson.id.trim().is_empty() {
+            report(None, "lesson has no id".to_string());
+        }
+        for asset in lesson.missing_assets(&self.asset_root) {
+            report(None, format!("missing asset {}", asset));
+        }
+
+        let (width, height) = self.design_resolution;
+        let layout = Layout::new(self.design_resolution);
+        let mut quizzes = 0;
+        for (index, element) in lesson.interactive_elements.iter().enumerate() {
+            let (x, y) = layout.resolve(&element.position, width, height);
+            // The lesson panel scrolls vertically, so only the top edge
+            // bounds the vertical position
+            if !(0.0..=width).contains(&x) || y.is_nan() || y < 0.0 {
+                report(
+                    Some(index),
+                    format!(
+                        "position ({}, {}) is outside the {}x{} design resolution",
+                        x, y, width, height
+                    ),
+                );
+            }
+            if let ElementPayload::Quiz(quiz) = &element.payload {
+                quizzes += 1;
+                if quiz.questions.is_empty() {
+                    report(Some(index), format!("quiz {} has no questions", quiz.id));
+                }
+                if let Err(e) = quiz.validate() {
+                    report(Some(index), e.to_string());
+                }
+            }
+        }
+
+        let criteria = &lesson.completion_criteria;
+        let elements = lesson
+            .interactive_elements
+            .iter()
+            .filter(|element| element.payload.records_interactions())
+            .count();
+        if criteria.required_interactions as usize > elements {
+            report(
+                None,
+                format!(
+                    "requires {} but has only {}",
+                    plural(criteria.required_interactions as usize, "interaction"),
+                    plural(elements, "interactive element")
+                ),
+            );
+        }
+        if !criteria.time_spent_minimum.is_finite() || criteria.time_spent_minimum < 0.0 {
+            report(
+                None,
+                format!("invalid minimum time spent {}", criteria.time_spent_minimum),
+            );
+        }
+        if let Some(threshold) = criteria.quiz_score_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                report(
+                    None,
+                    format!("quiz score threshold {} is not between 0 and 1", threshold),
+                );
+            } else if quizzes == 0 {
+                report(None, "requires a quiz score but has no quiz".to_string());
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// `count` followed by `noun`, made plural unless the count is one
+pub(crate) fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+/// Checks across lessons: unique ids and a well-formed prerequisite graph
+fn lint_graph(files: &[PathBuf], lessons: &[EducationalContent]) -> Vec<Diagnostic> {
+    let diagnostic = |position: usize, message: String| Diagnostic {
+        file: files[position].clone(),
+        element: None,
+        message,
+    };
+    let mut diagnostics = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (position, lesson) in lessons.iter().enumerate() {
+        if let Some(&first) = index.get(&lesson.id) {
+            diagnostics.push(diagnostic(
+                position,
+                format!(
+                    "duplicate lesson id {} (also in {})",
+                    lesson.id,
+                    files[first].display()
+                ),
+            ));
+        } else {
+            index.insert(lesson.id.clone(), position);
+        }
+    }
+
+    for (position, lesson) in lessons.iter().enumerate() {
+        for prerequisite in &lesson.prerequisites {
+            if !index.contains_key(prerequisite) {
+                diagnostics.push(diagnostic(
+                    position,
+                    format!("unknown prerequisite {}", prerequisite),
+                ));
+            }
+        }
+    }
+
+    // Cycles are only meaningful once ids are unique and every edge resolves
+    if diagnostics.is_empty() {
+        if let Err(blocked) = prerequisite_order(lessons, &index) {
+            for position in blocked {
+                diagnostics.push(diagnostic(
+                    position,
+                    format!(
+                        "lesson {} is part of or blocked by a prerequisite cycle",
+                        lessons[position].id
+                    ),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::ButtonAction;
+    use crate::education::{CompletionCriteria, InteractiveElement};
+    use crate::quiz::Quiz;
+
+    fn linter() -> Linter {
+        Linter::new("assets", (800.0, 600.0))
+    }
+
+    fn lesson(id: &str, prerequisites: &[&str]) -> EducationalContent {
+        let mut lesson = EducationalContent::new(id.to_string(), id.to_string(), String::new());
+        lesson.prerequisites = prerequisites.iter().map(|p| p.to_string()).collect();
+        lesson
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn test_shipped_lessons_are_clean() {
+        let diagnostics = linter().lint_directory("lessons").unwrap();
+        assert!(diagnostics.is_empty(), "{:#?}", messages(&diagnostics));
+    }
+
+    #[test]
+    fn test_lesson_problems_are_all_reported() {
+        let mut broken = lesson("broken", &[]);
+        broken.add_media("images/missing.png".to_string());
+        broken.add_interactive_element(InteractiveElement::new(
+            (900.0, 10.0),
+            ElementPayload::Quiz(Quiz {
+                id: "empty".to_string(),
+                title: String::new(),
+                questions: Vec::new(),
+            }),
+        ));
+        broken.add_interactive_element(InteractiveElement::new(
+            (10.0, 10.0),
+            ElementPayload::Button {
+                label: "Continue".to_string(),
+                action: ButtonAction::NextSection,
+            },
+        ));
+        broken.completion_criteria = CompletionCriteria {
+            required_interactions: 2,
+            time_spent_minimum: 0.0,
+            quiz_score_threshold: Some(1.5),
+        };
+
+        let diagnostics = linter().lint_lesson(Path::new("lessons/broken.md"), &broken);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                "lessons/broken.md: missing asset images/missing.png",
+                "lessons/broken.md: element 0: position (900, 10) is outside the 800x600 design resolution",
+                "lessons/broken.md: element 0: quiz empty has no questions",
+                "lessons/broken.md: requires 2 interactions but has only 1 interactive element",
+                "lessons/broken.md: quiz score threshold 1.5 is not between 0 and 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_problems() {
+        let files: Vec<PathBuf> = ["a.md", "b.md", "c.md"].iter().map(PathBuf::from).collect();
+
+        let duplicate = [
+            lesson("a", &[]),
+            lesson("a", &["missing"]),
+            lesson("c", &[]),
+        ];
+        assert_eq!(
+            messages(&linter().lint_lessons(&files, &duplicate)),
+            vec![
+                "b.md: duplicate lesson id a (also in a.md)",
+                "b.md: unknown prerequisite missing",
+            ]
+        );
+
+        let cyclic = [lesson("a", &["b"]), lesson("b", &["a"]), lesson("c", &[])];
+        let diagnostics = linter().lint_lessons(&files, &cyclic);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].message.contains("prerequisite cycle"));
+    }
+}
@@ -0,0 +1,732 @@
+//! Localization module for Learn Liberty
+//!
+//! This module reads message files in a subset of the Fluent syntax from
+//! `assets/locales/<locale>/*.ftl` and formats messages with arguments,
+//! message references and select expressions, including plural
+//! categories. A `Localizer` looks messages up along a fallback chain such
+//! as `fr-CA`, `fr`, `en-US`, so a missing translation shows the default
+//! language rather than a key. Lesson titles, descriptions and element
+//! text can be translated with `lesson-<id>-...` messages, and the checker
+//! reports keys each locale is missing or does not use.
+
+use crate::education::{EducationalContent, ElementPayload, RichText};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// The locale the game is written in and every chain ends with
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Deepest chain of message references followed before giving up
+const MAX_REFERENCE_DEPTH: usize = 8;
+
+/// A value passed to a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    String(String),
+    Number(f64),
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Argument::String(text) => write!(f, "{}", text),
+            Argument::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Argument::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl From<&str> for Argument {
+    fn from(text: &str) -> Self {
+        Argument::String(text.to_string())
+    }
+}
+
+impl From<String> for Argument {
+    fn from(text: String) -> Self {
+        Argument::String(text)
+    }
+}
+
+impl From<f64> for Argument {
+    fn from(n: f64) -> Self {
+        Argument::Number(n)
+    }
+}
+
+impl From<u32> for Argument {
+    fn from(n: u32) -> Self {
+        Argument::Number(n.into())
+    }
+}
+
+impl From<i64> for Argument {
+    fn from(n: i64) -> Self {
+        Argument::Number(n as f64)
+    }
+}
+
+impl From<usize> for Argument {
+    fn from(n: usize) -> Self {
+        Argument::Number(n as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    Text(String),
+    Variable(String),
+    Reference(String),
+    Select {
+        selector: String,
+        variants: Vec<(String, Vec<Element>)>,
+        default: usize,
+    },
+}
+
+/// Messages of one locale
+#[derive(Debug, Clone, Default)]
+pub struct Bundle {
+    pub locale: String,
+    messages: BTreeMap<String, Vec<Element>>,
+}
+
+impl Bundle {
+    pub fn new(locale: &str) -> Self {
+        Self {
+            locale: locale.to_string(),
+            messages: BTreeMap::new(),
+        }
+    }
+
+    /// Add the messages of one `.ftl` source. `name` is used in errors.
+    pub fn add_source(&mut self, name: &str, source: &str) -> anyhow::Result<()> {
+        let mut lines = source.lines().enumerate().peekable();
+        while let Some((number, line)) = lines.next() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let location = || format!("{}:{}", name, number + 1);
+            let (id, first) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("{}: expected `key = value`", location()))?;
+            let id = id.trim();
+            if !is_identifier(id) {
+                anyhow::bail!("{}: invalid message id {:?}", location(), id);
+            }
+
+            let mut value = first.trim().to_string();
+            while let Some((_, next)) = lines.peek() {
+                let continues = next.starts_with([' ', '\t']) || next.trim_start().starts_with('}');
+                if !continues || next.trim().is_empty() {
+                    break;
+                }
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(next.trim());
+                lines.next();
+            }
+
+            let pattern =
+                parse_pattern(&value).map_err(|e| anyhow::anyhow!("{}: {}", location(), e))?;
+            if self.messages.insert(id.to_string(), pattern).is_some() {
+                anyhow::bail!("{}: duplicate message {}", location(), id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_source(locale: &str, source: &str) -> anyhow::Result<Self> {
+        let mut bundle = Self::new(locale);
+        bundle.add_source(locale, source)?;
+        Ok(bundle)
+    }
+
+    /// Load every `.ftl` file in `<directory>/<locale>`, in name order
+    pub fn load(directory: impl AsRef<Path>, locale: &str) -> anyhow::Result<Self> {
+        let mut paths: Vec<_> = std::fs::read_dir(directory.as_ref().join(locale))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ftl"))
+            .collect();
+        paths.sort();
+
+        let mut bundle = Self::new(locale);
+        for path in paths {
+            let source = std::fs::read_to_string(&path)?;
+            bundle.add_source(&path.display().to_string(), &source)?;
+        }
+        Ok(bundle)
+    }
+
+    pub fn has_message(&self, key: &str) -> bool {
+        self.messages.contains_key(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+}
+
+fn is_identifier(id: &str) -> bool {
+    let mut chars = id.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parse a message value into text and placeables
+fn parse_pattern(source: &str) -> anyhow::Result<Vec<Element>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut position = 0;
+    let pattern = parse_elements(&chars, &mut position, false)?;
+    if position < chars.len() {
+        anyhow::bail!("unexpected {:?}", chars[position]);
+    }
+    Ok(pattern)
+}
+
+/// Read text and placeables until the end, or in a variant until the end
+/// of its line
+fn parse_elements(
+    chars: &[char],
+    position: &mut usize,
+    in_variant: bool,
+) -> anyhow::Result<Vec<Element>> {
+    let mut elements = Vec::new();
+    let mut text = String::new();
+
+    while let Some(&c) = chars.get(*position) {
+        match c {
+            '\n' if in_variant => break,
+            '}' => break,
+            '{' => {
+                *position += 1;
+                if !text.is_empty() {
+                    elements.push(Element::Text(std::mem::take(&mut text)));
+                }
+                elements.push(parse_placeable(chars, position)?);
+            }
+            _ => {
+                text.push(c);
+                *position += 1;
+            }
+        }
+    }
+    if in_variant {
+        let trimmed = text.trim_end().to_string();
+        text = trimmed;
+    }
+    if !text.is_empty() {
+        elements.push(Element::Text(text));
+    }
+    Ok(elements)
+}
+
+fn skip_whitespace(chars: &[char], position: &mut usize) {
+    while chars.get(*position).is_some_and(|c| c.is_whitespace()) {
+        *position += 1;
+    }
+}
+
+fn identifier(chars: &[char], position: &mut usize) -> String {
+    let start = *position;
+    while chars
+        .get(*position)
+        .is_some_and(|&c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        *position += 1;
+    }
+    chars[start..*position].iter().collect()
+}
+
+fn expect(chars: &[char], position: &mut usize, expected: char) -> anyhow::Result<()> {
+    skip_whitespace(chars, position);
+    if chars.get(*position) != Some(&expected) {
+        anyhow::bail!("expected {:?}", expected);
+    }
+    *position += 1;
+    Ok(())
+}
+
+/// Parse what follows a `{`, up to and including the closing `}`
+fn parse_placeable(chars: &[char], position: &mut usize) -> anyhow::Result<Element> {
+    skip_whitespace(chars, position);
+    let element = match chars.get(*position) {
+        Some('$') => {
+            *position += 1;
+            let name = identifier(chars, position);
+            if name.is_empty() {
+                anyhow::bail!("expected a variable name after $");
+            }
+            skip_whitespace(chars, position);
+            if chars.get(*position) == Some(&'-') && chars.get(*position + 1) == Some(&'>') {
+                *position += 2;
+                return parse_select(chars, position, name);
+            }
+            Element::Variable(name)
+        }
+        Some('"') => {
+            *position += 1;
+            let start = *position;
+            while chars.get(*position).is_some_and(|&c| c != '"') {
+                *position += 1;
+            }
+            let literal = chars[start..*position].iter().collect();
+            expect(chars, position, '"')?;
+            Element::Text(literal)
+        }
+        Some(c) if c.is_ascii_alphabetic() => Element::Reference(identifier(chars, position)),
+        _ => anyhow::bail!("expected a variable, string or message reference"),
+    };
+    expect(chars, position, '}')?;
+    Ok(element)
+}
+
+/// Parse the variants of `{ $selector -> ... }`
+fn parse_select(chars: &[char], position: &mut usize, selector: String) -> anyhow::Result<Element> {
+    let mut variants = Vec::new();
+    let mut default = None;
+
+    loop {
+        skip_whitespace(chars, position);
+        match chars.get(*position) {
+            Some('}') => {
+                *position += 1;
+                break;
+            }
+            Some('*') => {
+                if default.is_some() {
+                    anyhow::bail!("select on ${} has two default variants", selector);
+                }
+                default = Some(variants.len());
+                *position += 1;
+            }
+            Some('[') => {}
+            _ => anyhow::bail!("expected a [variant] in select on ${}", selector),
+        }
+        expect(chars, position, '[')?;
+        skip_whitespace(chars, position);
+        let start = *position;
+        while chars.get(*position).is_some_and(|&c| c != ']' && c != '\n') {
+            *position += 1;
+        }
+        let key: String = chars[start..*position].iter().collect();
+        expect(chars, position, ']')?;
+        while chars.get(*position).is_some_and(|&c| c == ' ' || c == '\t') {
+            *position += 1;
+        }
+        let value = parse_elements(chars, position, true)?;
+        variants.push((key.trim().to_string(), value));
+    }
+
+    let default =
+        default.ok_or_else(|| anyhow::anyhow!("select on ${} has no *[default]", selector))?;
+    Ok(Element::Select {
+        selector,
+        variants,
+        default,
+    })
+}
+
+/// CLDR plural category of a number for the most common rule families
+pub fn plural_category(locale: &str, n: f64) -> &'static str {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    let integer = n.fract() == 0.0;
+    let i = n.abs().trunc() as u64;
+    match language {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" => "other",
+        "fr" | "pt" if n.abs() < 2.0 => "one",
+        "ru" | "uk" | "pl" if integer => {
+            let (last, last_two) = (i % 10, i % 100);
+            let one = if language == "pl" {
+                i == 1
+            } else {
+                last == 1 && last_two != 11
+            };
+            if one {
+                "one"
+            } else if (2..=4).contains(&last) && !(12..=14).contains(&last_two) {
+                "few"
+            } else {
+                "many"
+            }
+        }
+        "ru" | "uk" | "pl" => "other",
+        _ if integer && i == 1 => "one",
+        _ => "other",
+    }
+}
+
+/// The locales to try for `locale`, most specific first, ending with the
+/// default locale
+pub fn fallback_chain(locale: &str, extra: &[String]) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    let mut push = |locale: &str| {
+        if !locale.is_empty() && !chain.iter().any(|l| l == locale) {
+            chain.push(locale.to_string());
+        }
+    };
+    push(locale);
+    if let Some((language, _)) = locale.split_once(['-', '_']) {
+        push(language);
+    }
+    for locale in extra {
+        push(locale);
+    }
+    push(DEFAULT_LOCALE);
+    chain
+}
+
+/// Looks messages up along a fallback chain of bundles
+#[derive(Debug, Clone, Default)]
+pub struct Localizer {
+    bundles: Vec<Bundle>,
+}
+
+impl Localizer {
+    pub fn new(bundles: Vec<Bundle>) -> Self {
+        Self { bundles }
+    }
+
+    /// Load the bundles of `locale`'s fallback chain that exist in
+    /// `directory`
+    pub fn load(
+        directory: impl AsRef<Path>,
+        locale: &str,
+        extra: &[String],
+    ) -> anyhow::Result<Self> {
+        let directory = directory.as_ref();
+        let mut bundles = Vec::new();
+        for locale in fallback_chain(locale, extra) {
+            if directory.join(&locale).is_dir() {
+                bundles.push(Bundle::load(directory, &locale)?);
+            }
+        }
+        Ok(Self { bundles })
+    }
+
+    /// The locale messages are preferably taken from
+    pub fn locale(&self) -> &str {
+        self.bundles
+            .first()
+            .map_or(DEFAULT_LOCALE, |bundle| bundle.locale.as_str())
+    }
+
+    /// Format a message from the first bundle that has it
+    pub fn get(&self, key: &str, args: &[(&str, Argument)]) -> Option<String> {
+        self.get_at_depth(key, args, 0)
+    }
+
+    /// Format a message, showing the key when no bundle has it
+    pub fn text(&self, key: &str, args: &[(&str, Argument)]) -> String {
+        self.get(key, args).unwrap_or_else(|| key.to_string())
+    }
+
+    fn format(
+        &self,
+        bundle: &Bundle,
+        pattern: &[Element],
+        args: &[(&str, Argument)],
+        depth: usize,
+        output: &mut String,
+    ) {
+        let argument = |name: &str| args.iter().find(|(n, _)| *n == name).map(|(_, a)| a);
+        for element in pattern {
+            match element {
+                Element::Text(text) => output.push_str(text),
+                Element::Variable(name) => match argument(name) {
+                    Some(value) => output.push_str(&value.to_string()),
+                    None => {
+                        output.push('$');
+                        output.push_str(name);
+                    }
+                },
+                Element::Reference(key) => match self.get_at_depth(key, args, depth + 1) {
+                    Some(text) => output.push_str(&text),
+                    None => output.push_str(key),
+                },
+                Element::Select {
+                    selector,
+                    variants,
+                    default,
+                } => {
+                    let chosen = argument(selector)
+                        .and_then(|value| {
+                            variants
+                                .iter()
+                                .position(|(key, _)| match value {
+                                    Argument::String(text) => key == text,
+                                    Argument::Number(n) => {
+                                        key.parse::<f64>().is_ok_and(|exact| exact == *n)
+                                    }
+                                })
+                                .or_else(|| match value {
+                                    Argument::Number(n) => {
+                                        let category = plural_category(&bundle.locale, *n);
+                                        variants.iter().position(|(key, _)| key == category)
+                                    }
+                                    Argument::String(_) => None,
+                                })
+                        })
+                        .unwrap_or(*default);
+                    self.format(bundle, &variants[chosen].1, args, depth, output);
+                }
+            }
+        }
+    }
+
+    fn get_at_depth(&self, key: &str, args: &[(&str, Argument)], depth: usize) -> Option<String> {
+        if depth > MAX_REFERENCE_DEPTH {
+            return None;
+        }
+        let bundle = self.bundles.iter().find(|bundle| bundle.has_message(key))?;
+        let mut output = String::new();
+        self.format(bundle, &bundle.messages[key], args, depth, &mut output);
+        Some(output)
+    }
+
+    /// Replace a lesson's title, description and element text with any
+    /// translations of them
+    pub fn localize_lesson(&self, lesson: &mut EducationalContent) {
+        let translate = |key: String| self.get(&key, &[]);
+        if let Some(title) = translate(lesson_key(&lesson.id, "title")) {
+            lesson.title = title;
+        }
+        if let Some(description) = translate(lesson_key(&lesson.id, "description")) {
+            lesson.description = description;
+        }
+        for (index, element) in lesson.interactive_elements.iter_mut().enumerate() {
+            let Some(text) = translate(element_key(&lesson.id, index)) else {
+                continue;
+            };
+            match &mut element.payload {
+                ElementPayload::Text(rich) => *rich = RichText::from_markdown(&text),
+                ElementPayload::Image { alt_text, .. } => *alt_text = text,
+                ElementPayload::Button { label, .. } => *label = text,
+                ElementPayload::Quiz(quiz) => quiz.title = text,
+                ElementPayload::Exercise {
+                    title,
+                    instructions,
+                    ..
+                } => {
+                    *title = text;
+                    if let Some(translated) =
+                        translate(format!("{}-instructions", element_key(&lesson.id, index)))
+                    {
+                        *instructions = RichText::from_markdown(&translated);
+                    }
+                }
+                ElementPayload::Video { .. } | ElementPayload::Code { .. } => {}
+            }
+        }
+    }
+}
+
+fn lesson_key(lesson_id: &str, field: &str) -> String {
+    format!("lesson-{}-{}", lesson_id, field)
+}
+
+fn element_key(lesson_id: &str, index: usize) -> String {
+    format!("lesson-{}-element-{}", lesson_id, index)
+}
+
+/// Every message key that can translate part of a lesson
+pub fn lesson_keys(lesson: &EducationalContent) -> Vec<String> {
+    let mut keys = vec![
+        lesson_key(&lesson.id, "title"),
+        lesson_key(&lesson.id, "description"),
+    ];
+    for (index, element) in lesson.interactive_elements.iter().enumerate() {
+        match &element.payload {
+            ElementPayload::Video { .. } | ElementPayload::Code { .. } => {}
+            ElementPayload::Exercise { .. } => {
+                keys.push(element_key(&lesson.id, index));
+                keys.push(format!("{}-instructions", element_key(&lesson.id, index)));
+            }
+            _ => keys.push(element_key(&lesson.id, index)),
+        }
+    }
+    keys
+}
+
+/// Keys a locale lacks or has without any use
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocaleReport {
+    pub locale: String,
+    pub missing: Vec<String>,
+    pub unused: Vec<String>,
+}
+
+impl LocaleReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.unused.is_empty()
+    }
+}
+
+/// Compare every locale in `directory` with the default locale. A locale
+/// must have every message of the default locale; lesson messages are only
+/// required once it translates part of that lesson, since untranslated
+/// lessons fall back to their source text. Keys that are neither are
+/// reported as unused.
+pub fn check_locales(
+    directory: impl AsRef<Path>,
+    lessons: &[EducationalContent],
+) -> anyhow::Result<Vec<LocaleReport>> {
+    let directory = directory.as_ref();
+    let mut locales: Vec<String> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .collect();
+    locales.sort();
+
+    let reference = Bundle::load(directory, DEFAULT_LOCALE)?;
+    let lesson_keys: Vec<BTreeSet<String>> = lessons
+        .iter()
+        .map(|lesson| lesson_keys(lesson).into_iter().collect())
+        .collect();
+
+    let mut reports = Vec::new();
+    for locale in locales {
+        let bundle = Bundle::load(directory, &locale)?;
+        let mut missing: Vec<String> = reference
+            .keys()
+            .filter(|key| !bundle.has_message(key))
+            .map(str::to_string)
+            .collect();
+        for keys in &lesson_keys {
+            if keys.iter().any(|key| bundle.has_message(key)) {
+                missing.extend(keys.iter().filter(|key| !bundle.has_message(key)).cloned());
+            }
+        }
+        let unused = bundle
+            .keys()
+            .filter(|key| {
+                !reference.has_message(key) && !lesson_keys.iter().any(|keys| keys.contains(*key))
+            })
+            .map(str::to_string)
+            .collect();
+        reports.push(LocaleReport {
+            locale,
+            missing,
+            unused,
+        });
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "\
+# Shown in the window title
+app-title = Learn Liberty
+greeting = Hello, { $name }!
+items-collected = { $count ->
+    [0] You have no items
+    [one] You collected one item
+   *[other] You collected { $count } items
+}
+welcome = { app-title }: { greeting }
+";
+
+    fn localizer(fr: &str) -> Localizer {
+        Localizer::new(vec![
+            Bundle::from_source("fr-FR", fr).unwrap(),
+            Bundle::from_source(DEFAULT_LOCALE, EN).unwrap(),
+        ])
+    }
+
+    #[test]
+    fn test_arguments_plurals_and_references() {
+        let en = Localizer::new(vec![Bundle::from_source(DEFAULT_LOCALE, EN).unwrap()]);
+
+        assert_eq!(
+            en.text("greeting", &[("name", "Ferris".into())]),
+            "Hello, Ferris!"
+        );
+        let count = |n: u32| en.text("items-collected", &[("count", n.into())]);
+        assert_eq!(count(0), "You have no items");
+        assert_eq!(count(1), "You collected one item");
+        assert_eq!(count(7), "You collected 7 items");
+        assert_eq!(
+            en.text("welcome", &[("name", "Sam".into())]),
+            "Learn Liberty: Hello, Sam!"
+        );
+        assert_eq!(en.text("missing-key", &[]), "missing-key");
+
+        assert!(Bundle::from_source("en", "broken = { $count ->\n [one] x\n}\n").is_err());
+        assert!(Bundle::from_source("en", "a = 1\na = 2\n").is_err());
+        assert!(Bundle::from_source("en", "1st = no\n").is_err());
+    }
+
+    #[test]
+    fn test_fallback_chain_and_plural_rules() {
+        assert_eq!(
+            fallback_chain("fr-CA", &["fr-FR".to_string()]),
+            vec!["fr-CA", "fr", "fr-FR", "en-US"]
+        );
+        assert_eq!(fallback_chain("en-US", &[]), vec!["en-US", "en"]);
+
+        let fr = localizer("items-collected = { $count ->\n   *[one] { $count } objet\n    [other] { $count } objets\n}\n");
+        assert_eq!(
+            fr.text("items-collected", &[("count", 0u32.into())]),
+            "0 objet"
+        );
+        assert_eq!(
+            fr.text("items-collected", &[("count", 2u32.into())]),
+            "2 objets"
+        );
+        assert_eq!(fr.text("app-title", &[]), "Learn Liberty");
+        assert_eq!(fr.locale(), "fr-FR");
+
+        assert_eq!(plural_category("ru", 21.0), "one");
+        assert_eq!(plural_category("ru", 23.0), "few");
+        assert_eq!(plural_category("pl", 25.0), "many");
+        assert_eq!(plural_category("ja", 1.0), "other");
+    }
+
+    #[test]
+    fn test_localize_lesson() {
+        let mut lesson =
+            EducationalContent::new("intro".to_string(), "Intro".to_string(), String::new());
+        lesson.add_interactive_element(crate::education::InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Text(RichText::plain("Welcome")),
+        ));
+
+        let fr = localizer(
+            "lesson-intro-title = Introduction\nlesson-intro-element-0 = **Bienvenue**\n",
+        );
+        fr.localize_lesson(&mut lesson);
+
+        assert_eq!(lesson.title, "Introduction");
+        assert_eq!(lesson.description, "");
+        let ElementPayload::Text(text) = &lesson.interactive_elements[0].payload else {
+            panic!("expected text");
+        };
+        assert!(text.spans[0].bold);
+        assert_eq!(
+            lesson_keys(&lesson),
+            vec![
+                "lesson-intro-title",
+                "lesson-intro-description",
+                "lesson-intro-element-0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shipped_locales_are_complete() {
+        let lessons = crate::lesson_loader::load_lessons("lessons").unwrap();
+        let reports = check_locales("assets/locales", &lessons).unwrap();
+
+        assert!(reports.len() >= 2);
+        for report in &reports {
+            assert!(report.is_clean(), "{:?}", report);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+//! Learning record store sync module for Learn Liberty
+//!
+//! This module pushes xAPI statements to an LRS endpoint. Statements are
+//! first written to a durable queue on disk so nothing is lost while the
+//! machine is offline; the sync client then posts them in batches with
+//! basic auth and backs off exponentially after a failed attempt. The
+//! client speaks plain HTTP/1.1, so a school LRS served over HTTPS is
+//! reached through a local TLS-terminating proxy. Every statement is given
+//! a UUID before it is queued, so a batch resent after a lost response is
+//! recognised by the LRS instead of being stored twice.
+
+use crate::analytics::uuid_v4;
+use crate::config::LrsSettings;
+use crate::save::write_atomic;
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Statements sent per request
+pub const DEFAULT_BATCH_SIZE: usize = 50;
+
+/// How often the background sync retries while nothing new is queued
+pub const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+const XAPI_VERSION: &str = "1.0.3";
+
+/// Statements waiting to be sent, one JSON object per line. Lines that
+/// cannot be parsed are moved to a `.rejected` file next to the queue.
+pub struct StatementQueue {
+    path: PathBuf,
+}
+
+impl StatementQueue {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where unparsable lines are moved
+    pub fn rejected_path(&self) -> PathBuf {
+        self.path.with_extension("rejected")
+    }
+
+    /// Append statements, giving an `id` to any statement without one
+    pub fn push(&self, statements: &[Value]) -> anyhow::Result<()> {
+        if statements.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = String::new();
+        for statement in statements {
+            let mut statement = statement.clone();
+            if let Some(object) = statement.as_object_mut() {
+                object
+                    .entry("id")
+                    .or_insert_with(|| Value::String(uuid_v4()));
+            }
+            lines.push_str(&serde_json::to_string(&statement)?);
+            lines.push('\n');
+        }
+        append_synced(&self.path, &lines)
+    }
+
+    /// Every queued statement, oldest first. Unparsable lines are moved to
+    /// `rejected_path` so one damaged line cannot stall the queue.
+    pub fn pending(&self) -> anyhow::Result<Vec<Value>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let contents = fs::read_to_string(&self.path)?;
+        let mut statements = Vec::new();
+        let mut rejected = String::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(statement) => statements.push(statement),
+                Err(e) => {
+                    eprintln!("Quarantining queued statement: {}", e);
+                    rejected.push_str(line);
+                    rejected.push('\n');
+                }
+            }
+        }
+
+        if !rejected.is_empty() {
+            append_synced(&self.rejected_path(), &rejected)?;
+            self.rewrite(&statements)?;
+        }
+        Ok(statements)
+    }
+
+    pub fn len(&self) -> anyhow::Result<usize> {
+        Ok(self.pending()?.len())
+    }
+
+    pub fn is_empty(&self) -> anyhow::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Drop the oldest `count` statements once they have been delivered
+    pub fn acknowledge(&self, count: usize) -> anyhow::Result<()> {
+        let pending = self.pending()?;
+        self.rewrite(pending.get(count..).unwrap_or_default())
+    }
+
+    fn rewrite(&self, statements: &[Value]) -> anyhow::Result<()> {
+        let mut contents = String::new();
+        for statement in statements {
+            contents.push_str(&serde_json::to_string(statement)?);
+            contents.push('\n');
+        }
+        write_atomic(&self.path, &contents)
+    }
+}
+
+/// Append to a file and wait for it to reach the disk
+fn append_synced(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Exponential backoff between failed sync attempts
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    pub base: Duration,
+    pub max: Duration,
+    failures: u32,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            failures: 0,
+        }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Record a failure and return how long to wait before retrying
+    pub fn fail(&mut self) -> Duration {
+        let factor = 2u32.saturating_pow(self.failures.min(16));
+        self.failures += 1;
+        self.base.saturating_mul(factor).min(self.max)
+    }
+
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5), Duration::from_secs(600))
+    }
+}
+
+/// Posts statement batches to an LRS over HTTP/1.1
+#[derive(Debug, Clone)]
+pub struct LrsClient {
+    host: String,
+    port: u16,
+    path: String,
+    authorization: String,
+    timeout: Duration,
+}
+
+impl LrsClient {
+    pub fn new(settings: &LrsSettings) -> anyhow::Result<Self> {
+        let rest = settings
+            .endpoint
+            .strip_prefix("http://")
+            .ok_or_else(|| anyhow::anyhow!("LRS endpoint must be an http:// URL"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            anyhow::bail!("LRS endpoint has no host: {}", settings.endpoint);
+        }
+
+        let credentials = format!("{}:{}", settings.username, settings.password);
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: format!("{}/statements", path.trim_end_matches('/')),
+            authorization: format!("Basic {}", base64(credentials.as_bytes())),
+            timeout: Duration::from_secs(settings.timeout_secs),
+        })
+    }
+
+    /// POST a batch to the statements resource, failing on any non-2xx
+    /// response
+    pub async fn send(&self, statements: &[Value]) -> anyhow::Result<()> {
+        let body = serde_json::to_string(statements)?;
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nAuthorization: {}\r\n\
+             X-Experience-API-Version: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            self.port,
+            self.authorization,
+            XAPI_VERSION,
+            body.len(),
+            body
+        );
+
+        let exchange = async {
+            let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            stream.write_all(request.as_bytes()).await?;
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            anyhow::Ok(response)
+        };
+        let response = tokio::time::timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| anyhow::anyhow!("LRS request timed out"))??;
+
+        let status_line = String::from_utf8_lossy(&response)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let status: u16 = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Malformed LRS response: {:?}", status_line))?;
+        if !(200..300).contains(&status) {
+            anyhow::bail!("LRS rejected statements: {}", status_line);
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the durable queue flowing to the LRS
+pub struct LrsSync {
+    pub client: LrsClient,
+    pub queue: StatementQueue,
+    pub backoff: Backoff,
+    pub batch_size: usize,
+    retry_at: Option<Instant>,
+}
+
+impl LrsSync {
+    pub fn new(client: LrsClient, queue: StatementQueue) -> Self {
+        Self {
+            client,
+            queue,
+            backoff: Backoff::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            retry_at: None,
+        }
+    }
+
+    /// Sync to the endpoint in the `[lrs]` settings through a queue file
+    pub fn from_settings(
+        settings: &LrsSettings,
+        queue_path: impl Into<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            LrsClient::new(settings)?,
+            StatementQueue::new(queue_path),
+        ))
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Queue statements durably; they are sent on the next flush
+    pub fn enqueue(&self, statements: &[Value]) -> anyhow::Result<()> {
+        self.queue.push(statements)
+    }
+
+    /// When the next attempt is allowed after a failure
+    pub fn retry_at(&self) -> Option<Instant> {
+        self.retry_at
+    }
+
+    /// Send queued statements until the queue is empty or a request fails.
+    /// Returns how many were delivered. While backing off this does
+    /// nothing and returns zero.
+    pub async fn flush(&mut self) -> anyhow::Result<usize> {
+        if self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return Ok(0);
+        }
+
+        let pending = self.queue.pending()?;
+        let mut delivered = 0;
+        for batch in pending.chunks(self.batch_size.max(1)) {
+            if let Err(e) = self.client.send(batch).await {
+                self.queue.acknowledge(delivered)?;
+                self.retry_at = Some(Instant::now() + self.backoff.fail());
+                return Err(e);
+            }
+            delivered += batch.len();
+        }
+
+        self.queue.acknowledge(delivered)?;
+        self.backoff.reset();
+        self.retry_at = None;
+        Ok(delivered)
+    }
+}
+
+/// Runs an `LrsSync` on a tokio task. Statements handed to it are queued
+/// and flushed straight away, and the queue is retried every interval, so
+/// the frame loop never waits on the network.
+pub struct LrsWorker {
+    sender: mpsc::UnboundedSender<Vec<Value>>,
+    task: JoinHandle<()>,
+}
+
+impl LrsWorker {
+    /// Start the background sync. Must be called from within a tokio
+    /// runtime.
+    pub fn spawn(mut sync: LrsSync, interval: Duration) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<Value>>();
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    received = receiver.recv() => match received {
+                        Some(statements) => {
+                            if let Err(e) = sync.enqueue(&statements) {
+                                eprintln!("Failed to queue statements: {}", e);
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = sync.flush().await {
+                    eprintln!("LRS sync failed: {}", e);
+                }
+            }
+            // Statements that still fail stay queued for the next run
+            let _ = sync.flush().await;
+        });
+
+        Self { sender, task }
+    }
+
+    /// Queue statements to be sent in the background
+    pub fn send(&self, statements: Vec<Value>) {
+        if statements.is_empty() {
+            return;
+        }
+        if self.sender.send(statements).is_err() {
+            eprintln!("LRS sync has stopped");
+        }
+    }
+
+    /// Stop accepting statements and make one last attempt to send the
+    /// queue
+    pub async fn shutdown(self) {
+        drop(self.sender);
+        let _ = self.task.await;
+    }
+}
+
+/// Standard base64 with padding, as used by basic auth
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> shift) as usize & 0x3F] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(endpoint: &str) -> LrsSettings {
+        LrsSettings {
+            endpoint: endpoint.to_string(),
+            username: "key".to_string(),
+            password: "secret".to_string(),
+            timeout_secs: 5,
+            base_iri: crate::analytics::DEFAULT_BASE_IRI.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(
+            base64(b"Aladdin:open sesame"),
+            "QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+
+    #[test]
+    fn test_client_parses_endpoint() {
+        let client = LrsClient::new(&settings("http://lrs.school:8080/xapi/")).unwrap();
+        assert_eq!(client.host, "lrs.school");
+        assert_eq!(client.port, 8080);
+        assert_eq!(client.path, "/xapi/statements");
+        assert_eq!(client.authorization, "Basic a2V5OnNlY3JldA==");
+
+        assert_eq!(LrsClient::new(&settings("http://lrs")).unwrap().port, 80);
+        assert!(LrsClient::new(&settings("https://lrs.school")).is_err());
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+
+        let delays: Vec<u64> = (0..4).map(|_| backoff.fail().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5]);
+        backoff.reset();
+        assert_eq!(backoff.fail(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_queue_acknowledge_keeps_rest() {
+        let path = std::env::temp_dir().join(format!(
+            "learn_liberty_lrs_queue_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let queue = StatementQueue::new(&path);
+
+        queue
+            .push(&[serde_json::json!({"n": 1}), serde_json::json!({"n": 2})])
+            .unwrap();
+        queue.push(&[serde_json::json!({"n": 3})]).unwrap();
+        queue.acknowledge(2).unwrap();
+
+        let pending = queue.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0]["n"], 3);
+        queue.acknowledge(1).unwrap();
+        assert!(queue.is_empty().unwrap());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_queue_ids_and_quarantine() {
+        let path = std::env::temp_dir().join(format!(
+            "learn_liberty_lrs_quarantine_{}.jsonl",
+            std::process::id()
+        ));
+        let queue = StatementQueue::new(&path);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(queue.rejected_path());
+
+        queue
+            .push(&[
+                serde_json::json!({"n": 1}),
+                serde_json::json!({"id": "kept", "n": 2}),
+            ])
+            .unwrap();
+        append_synced(&path, "{\"n\": 3\n").unwrap();
+        queue.push(&[serde_json::json!({"n": 4})]).unwrap();
+
+        let pending = queue.pending().unwrap();
+        let numbers: Vec<&Value> = pending.iter().map(|statement| &statement["n"]).collect();
+        assert_eq!(numbers, vec![1, 2, 4]);
+        assert_eq!(pending[0]["id"].as_str().unwrap().len(), 36);
+        assert_ne!(pending[0]["id"], pending[2]["id"]);
+        assert_eq!(pending[1]["id"], "kept");
+
+        assert_eq!(
+            fs::read_to_string(queue.rejected_path()).unwrap(),
+            "{\"n\": 3\n"
+        );
+        assert_eq!(queue.pending().unwrap(), pending);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(queue.rejected_path());
+    }
+}
@@ -1,35 +1,208 @@
-mod education;
-mod graphics;
-mod state;
-mod window;
-
-use graphics::GraphicsEngine;
-use state::AppState;
+use learn_liberty_app::achievement::AchievementDatabase;
+use learn_liberty_app::autosave::{self, Autosave};
+use learn_liberty_app::inventory::ItemDatabase;
+use learn_liberty_app::lesson_catalog::LessonCatalog;
+use learn_liberty_app::localization::Localizer;
+use learn_liberty_app::lrs::{LrsSync, LrsWorker, DEFAULT_SYNC_INTERVAL};
+use learn_liberty_app::profile::unix_timestamp;
+use learn_liberty_app::quest::QuestLog;
+use learn_liberty_app::save::SaveData;
+use learn_liberty_app::scene::{self, GameContent, SceneStack, TitleScreen};
+use learn_liberty_app::window::InputEvent;
+use learn_liberty_app::{AppState, GraphicsEngine, ProfileStore, Settings, WindowManager};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
 use std::time::Instant;
-use window::WindowManager;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     env_logger::init();
 
-    let window_manager = WindowManager::new("Learn Liberty - Educational RPG");
+    // Tool subcommands run instead of the game; other arguments, such as
+    // ones added by a launcher, are ignored
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args
+        .first()
+        .is_some_and(|arg| learn_liberty_app::cli::is_command(arg))
+    {
+        learn_liberty_app::cli::run(&args)?;
+        return Ok(());
+    }
+    for arg in &args {
+        eprintln!("Ignoring unknown argument: {}", arg);
+    }
+
+    let settings = Settings::load("config/settings.toml").unwrap_or_else(|e| {
+        eprintln!("Using default settings: {}", e);
+        Settings::default()
+    });
+
+    let achievements =
+        AchievementDatabase::load("assets/data/achievements.toml").unwrap_or_else(|e| {
+            eprintln!("Failed to load achievements: {}", e);
+            AchievementDatabase::default()
+        });
+
+    let localization = &settings.localization;
+    let localizer = Localizer::load(
+        &localization.directory,
+        &localization.locale,
+        &localization.fallback,
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Failed to load translations: {}", e);
+        Localizer::default()
+    });
+    let title = localizer
+        .get("app-title", &[])
+        .unwrap_or_else(|| settings.window.title.clone());
+
+    let window_manager = WindowManager::new(&title);
     let window = window_manager.window();
-    let mut graphics_engine = GraphicsEngine::new(&window).await?;
-    let mut app_state = AppState::default();
+    let mut graphics_engine = GraphicsEngine::new(window).await?;
+    let quests = QuestLog::load("assets/data/quests.toml").unwrap_or_else(|e| {
+        eprintln!("Failed to load quests: {}", e);
+        QuestLog::new()
+    });
+    let mut app_state = AppState {
+        window_size: (graphics_engine.width, graphics_engine.height),
+        quests,
+        ..AppState::default()
+    };
+    let profiles = ProfileStore::new("profiles");
+    let learners = profiles.list().unwrap_or_else(|e| {
+        eprintln!("Failed to load learner profiles: {}", e);
+        Vec::new()
+    });
+    let lessons = LessonCatalog::load("lessons").unwrap_or_else(|e| {
+        eprintln!("Failed to load lessons: {}", e);
+        LessonCatalog::default()
+    });
+    for e in lessons.validate_assets(Path::new("assets")) {
+        eprintln!("{}", e);
+    }
+    let items = ItemDatabase::load("assets/data/items.toml").unwrap_or_else(|e| {
+        eprintln!("Failed to load items: {}", e);
+        ItemDatabase::default()
+    });
+    let content = GameContent {
+        lessons,
+        items,
+        default_lesson: settings.education.current_lesson.clone(),
+    };
+    // Statements for the LRS are queued next to the profiles so they
+    // survive until the endpoint can be reached
+    let lrs_queue = profiles.directory().join("lrs_queue.jsonl");
+    let lrs_worker = match settings
+        .lrs
+        .as_ref()
+        .map(|lrs| LrsSync::from_settings(lrs, lrs_queue))
+    {
+        Some(Ok(sync)) => Some(LrsWorker::spawn(sync, DEFAULT_SYNC_INTERVAL)),
+        Some(Err(e)) => {
+            eprintln!("LRS sync disabled: {}", e);
+            None
+        }
+        None => None,
+    };
+    let lrs = Rc::new(RefCell::new(lrs_worker));
+    let running_lrs = Rc::clone(&lrs);
+    let content = Rc::new(content);
+    let title_screen = TitleScreen::with_profiles(learners).with_content(Rc::clone(&content));
+    let mut scenes = SceneStack::new(Box::new(title_screen), &mut app_state);
+    // Autosaving starts once a learner is picked; the event loop shares the
+    // writer with the shutdown below
+    let autosave: Rc<RefCell<Option<Autosave>>> = Rc::default();
+    let running_autosave = Rc::clone(&autosave);
+    let mut autosave_learner: Option<String> = None;
 
     let mut last_time = Instant::now();
 
-    window_manager.run(move |_window| {
+    window_manager.run(move |_window, inputs| {
         let current_time = Instant::now();
         let delta_time = last_time.elapsed().as_secs_f64();
         last_time = current_time;
 
+        for input in &inputs {
+            if let InputEvent::Resize { width, height } = input {
+                graphics_engine.resize(*width, *height);
+            }
+            scenes.handle_input(&mut app_state, input);
+        }
+
         app_state.update(delta_time);
+        scenes.update(&mut app_state, delta_time);
+        if app_state.learner_id != autosave_learner {
+            autosave_learner = app_state.learner_id.clone();
+            match autosave::resume_learner(&profiles, &settings, &mut app_state) {
+                Ok(writer) => *running_autosave.borrow_mut() = Some(writer),
+                Err(e) => eprintln!("Failed to resume autosaves: {}", e),
+            }
+        }
+        if let Some(writer) = running_autosave.borrow_mut().as_mut() {
+            writer.tick(delta_time, &app_state, &settings);
+        }
+
+        let events = app_state.drain_learning_events();
+        if !events.is_empty() {
+            let actor = app_state.actor();
+            let logged = profiles
+                .events(&actor.id)
+                .and_then(|log| log.append_all(&events));
+            if let Err(e) = logged {
+                eprintln!("Failed to log learning events for {}: {}", actor.id, e);
+            }
+            if let (Some(worker), Some(lrs)) = (running_lrs.borrow().as_ref(), &settings.lrs) {
+                worker.send(events.iter().map(|e| e.to_xapi(&lrs.base_iri)).collect());
+            }
+        }
 
+        app_state.apply_quest_rewards(&content.items);
+        app_state.record_pending_achievements(&achievements, unix_timestamp());
+
+        if let Err(e) = scenes.render(&app_state, &mut graphics_engine) {
+            eprintln!("Render error: {}", e);
+        }
+        if let Some(toast) = app_state.achievement_toasts.current() {
+            let message = localizer.text(
+                "achievement-unlocked",
+                &[("name", toast.name.as_str().into())],
+            );
+            scene::draw_toast(&app_state, &mut graphics_engine, &message);
+        }
         if let Err(e) = graphics_engine.render() {
             eprintln!("Render error: {}", e);
         }
+
+        if scenes.should_quit() {
+            // Quitting from the pause menu and closing the window both end
+            // here, so progress since the last autosave is kept either way
+            scenes.exit_all(&mut app_state);
+            if let Some(writer) = running_autosave.borrow_mut().as_mut() {
+                writer.save_now(SaveData::capture(&app_state, &settings));
+            }
+            if let Some(id) = app_state.learner_id.as_deref() {
+                let saved = profiles.load(id).and_then(|mut profile| {
+                    profile.progress.capture(&app_state, unix_timestamp());
+                    profiles.save(&profile)
+                });
+                if let Err(e) = saved {
+                    eprintln!("Failed to save profile {}: {}", id, e);
+                }
+            }
+            return false;
+        }
+        true
     });
 
+    let writer = autosave.borrow_mut().take();
+    if let Some(writer) = writer {
+        writer.shutdown().await;
+    }
+    let worker = lrs.borrow_mut().take();
+    if let Some(worker) = worker {
+        worker.shutdown().await;
+    }
     Ok(())
 }
@@ -0,0 +1,731 @@
+//! Content package module for Learn Liberty
+//!
+//! This module exports the lesson catalog as a zip an LMS can import,
+//! either as a SCORM 1.2 package or an IMS Common Cartridge. Every lesson
+//! becomes an HTML page, its referenced media is copied in next to it and
+//! a generated `imsmanifest.xml` lists the lessons in prerequisite order.
+//! Media paths must stay inside the package, so absolute paths and `..`
+//! components are rejected. The zip is written uncompressed by a small
+//! built-in writer and is limited to the 4 GiB of the classic zip format.
+
+use crate::education::{EducationalContent, ElementPayload};
+use crate::lesson_catalog::LessonCatalog;
+use crate::save::crc32;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+pub const MANIFEST_FILE: &str = "imsmanifest.xml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageFormat {
+    Scorm12,
+    CommonCartridge,
+}
+
+impl FromStr for PackageFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> anyhow::Result<Self> {
+        match source.to_ascii_lowercase().as_str() {
+            "scorm" | "scorm12" | "scorm1.2" => Ok(PackageFormat::Scorm12),
+            "cc" | "imscc" | "common-cartridge" => Ok(PackageFormat::CommonCartridge),
+            _ => anyhow::bail!("Unknown package format: {}", source),
+        }
+    }
+}
+
+/// Escape text for use in XML and HTML content and attributes
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Turn a lesson id into a valid XML identifier
+fn identifier(prefix: &str, lesson_id: &str) -> String {
+    let cleaned: String = lesson_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_{}", prefix, cleaned)
+}
+
+fn lesson_href(lesson: &EducationalContent) -> String {
+    format!("lessons/{}/index.html", identifier("lesson", &lesson.id))
+}
+
+/// Where an asset is stored in the package. Fails for paths that could
+/// escape the media directory when the package is unpacked.
+fn media_href(asset: &str) -> anyhow::Result<String> {
+    let escapes = asset.starts_with(['/', '\\'])
+        || asset
+            .split(['/', '\\'])
+            .any(|part| part == ".." || part.contains(':'))
+        || Path::new(asset)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if asset.is_empty() || escapes {
+        anyhow::bail!("Media path {:?} must be relative to the asset root", asset);
+    }
+    Ok(format!("media/{}", asset))
+}
+
+/// Generate `imsmanifest.xml` for the catalog
+pub fn manifest(
+    catalog: &LessonCatalog,
+    format: PackageFormat,
+    title: &str,
+) -> anyhow::Result<String> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    match format {
+        PackageFormat::Scorm12 => xml.push_str(
+            "<manifest identifier=\"learn_liberty\" version=\"1.0\" \
+             xmlns=\"http://www.imsproject.org/xsd/imscp_rootv1p1p2\" \
+             xmlns:adlcp=\"http://www.adlnet.org/xsd/adlcp_rootv1p2\">\n\
+             \x20 <metadata>\n\
+             \x20   <schema>ADL SCORM</schema>\n\
+             \x20   <schemaversion>1.2</schemaversion>\n\
+             \x20 </metadata>\n",
+        ),
+        PackageFormat::CommonCartridge => xml.push_str(
+            "<manifest identifier=\"learn_liberty\" \
+             xmlns=\"http://www.imsglobal.org/xsd/imsccv1p1/imscp_v1p1\">\n\
+             \x20 <metadata>\n\
+             \x20   <schema>IMS Common Cartridge</schema>\n\
+             \x20   <schemaversion>1.1.0</schemaversion>\n\
+             \x20 </metadata>\n",
+        ),
+    }
+
+    let title = escape(title);
+    xml.push_str("  <organizations default=\"organization\">\n");
+    let indent = match format {
+        PackageFormat::Scorm12 => {
+            let _ = writeln!(
+                xml,
+                "    <organization identifier=\"organization\">\n      <title>{}</title>",
+                title
+            );
+            "      "
+        }
+        PackageFormat::CommonCartridge => {
+            let _ = writeln!(
+                xml,
+                "    <organization identifier=\"organization\" structure=\"rooted-hierarchy\">\n      \
+                 <item identifier=\"root\">\n        <title>{}</title>",
+                title
+            );
+            "        "
+        }
+    };
+
+    for lesson in catalog.ordered() {
+        let _ = writeln!(
+            xml,
+            "{}<item identifier=\"{}\" identifierref=\"{}\">",
+            indent,
+            identifier("item", &lesson.id),
+            identifier("resource", &lesson.id)
+        );
+        let _ = writeln!(xml, "{}  <title>{}</title>", indent, escape(&lesson.title));
+        if format == PackageFormat::Scorm12 && !lesson.prerequisites.is_empty() {
+            let script: Vec<String> = lesson
+                .prerequisites
+                .iter()
+                .map(|id| identifier("item", id))
+                .collect();
+            let _ = writeln!(
+                xml,
+                "{}  <adlcp:prerequisites type=\"aicc_script\">{}</adlcp:prerequisites>",
+                indent,
+                script.join("&amp;")
+            );
+        }
+        let _ = writeln!(xml, "{}</item>", indent);
+    }
+    if format == PackageFormat::CommonCartridge {
+        xml.push_str("      </item>\n");
+    }
+    xml.push_str("    </organization>\n  </organizations>\n  <resources>\n");
+
+    for lesson in catalog.ordered() {
+        let href = lesson_href(lesson);
+        let kind = match format {
+            PackageFormat::Scorm12 => "type=\"webcontent\" adlcp:scormtype=\"sco\"",
+            PackageFormat::CommonCartridge => "type=\"webcontent\"",
+        };
+        let _ = writeln!(
+            xml,
+            "    <resource identifier=\"{}\" {} href=\"{}\">",
+            identifier("resource", &lesson.id),
+            kind,
+            escape(&href)
+        );
+        let _ = writeln!(xml, "      <file href=\"{}\"/>", escape(&href));
+        for asset in lesson.asset_references() {
+            let _ = writeln!(
+                xml,
+                "      <file href=\"{}\"/>",
+                escape(&media_href(asset)?)
+            );
+        }
+        xml.push_str("    </resource>\n");
+    }
+    xml.push_str("  </resources>\n</manifest>\n");
+    Ok(xml)
+}
+
+/// Reports completion to a SCORM 1.2 LMS when the page is closed
+const SCORM_SCRIPT: &str = r#"<script>
+function findApi(win) {
+  for (var depth = 0; win && depth < 10; depth++) {
+    if (win.API) return win.API;
+    if (win.parent === win) break;
+    win = win.parent;
+  }
+  return window.opener ? window.opener.API : null;
+}
+var api = findApi(window);
+if (api) {
+  api.LMSInitialize("");
+  window.addEventListener("unload", function () {
+    api.LMSSetValue("cmi.core.lesson_status", "completed");
+    api.LMSCommit("");
+    api.LMSFinish("");
+  });
+}
+</script>
+"#;
+
+/// Write a section body: blank lines separate paragraphs and fenced
+/// blocks become code listings
+fn section_html(html: &mut String, body: &str) {
+    let mut paragraph: Vec<&str> = Vec::new();
+    // Language and lines of the fenced block being read
+    let mut code: Option<(&str, Vec<&str>)> = None;
+    let flush = |html: &mut String, paragraph: &mut Vec<&str>| {
+        let text = paragraph.join("\n");
+        if !text.trim().is_empty() {
+            let _ = writeln!(html, "<p>{}</p>", escape(text.trim()));
+        }
+        paragraph.clear();
+    };
+
+    for line in body.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((language, lines)), Some(_)) => {
+                code_html(html, language, &lines.join("\n"));
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(language)) => {
+                flush(html, &mut paragraph);
+                code = Some((language.trim(), Vec::new()));
+            }
+            (None, None) if line.trim().is_empty() => flush(html, &mut paragraph),
+            (None, None) => paragraph.push(line),
+        }
+    }
+    if let Some((language, lines)) = code {
+        code_html(html, language, &lines.join("\n"));
+    }
+    flush(html, &mut paragraph);
+}
+
+fn code_html(html: &mut String, language: &str, code: &str) {
+    let _ = writeln!(
+        html,
+        "<pre><code class=\"language-{}\">{}</code></pre>",
+        escape(language),
+        escape(code)
+    );
+}
+
+/// Render one lesson as a standalone HTML page. Code blocks that are part
+/// of a section are shown there and not repeated with the elements.
+pub fn lesson_html(lesson: &EducationalContent, format: PackageFormat) -> anyhow::Result<String> {
+    // Pages live two directories below the package root
+    let root = "../../";
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n",
+        escape(&lesson.title)
+    );
+    if format == PackageFormat::Scorm12 {
+        html.push_str(SCORM_SCRIPT);
+    }
+    let _ = write!(
+        html,
+        "</head>\n<body>\n<h1>{}</h1>\n<p>{}</p>\n",
+        escape(&lesson.title),
+        escape(&lesson.description)
+    );
+
+    for section in &lesson.sections {
+        let level = section.level.clamp(2, 6);
+        let _ = writeln!(html, "<h{0}>{1}</h{0}>", level, escape(&section.title));
+        section_html(&mut html, &section.body);
+    }
+
+    for element in &lesson.interactive_elements {
+        match &element.payload {
+            ElementPayload::Text(text) => {
+                let _ = writeln!(html, "<p>{}</p>", escape(&text.to_plain()));
+            }
+            ElementPayload::Image { asset, alt_text } => {
+                let _ = writeln!(
+                    html,
+                    "<img src=\"{}{}\" alt=\"{}\">",
+                    root,
+                    escape(&media_href(asset)?),
+                    escape(alt_text)
+                );
+            }
+            ElementPayload::Video { asset, .. } => {
+                let _ = writeln!(
+                    html,
+                    "<video controls src=\"{}{}\"></video>",
+                    root,
+                    escape(&media_href(asset)?)
+                );
+            }
+            ElementPayload::Code { language, code } => {
+                let in_section = lesson
+                    .sections
+                    .iter()
+                    .any(|section| section.body.contains(code.as_str()));
+                if !in_section {
+                    code_html(&mut html, language, code);
+                }
+            }
+            ElementPayload::Exercise {
+                title,
+                instructions,
+                ..
+            } => {
+                let _ = writeln!(
+                    html,
+                    "<section class=\"exercise\"><h3>{}</h3><p>{}</p></section>",
+                    escape(title),
+                    escape(&instructions.to_plain())
+                );
+            }
+            ElementPayload::Quiz(quiz) => {
+                let _ = writeln!(
+                    html,
+                    "<section class=\"quiz\"><h3>{}</h3><ol>",
+                    escape(&quiz.title)
+                );
+                for question in &quiz.questions {
+                    let _ = writeln!(html, "<li>{}</li>", escape(&question.prompt));
+                }
+                html.push_str("</ol></section>\n");
+            }
+            ElementPayload::Button { .. } => {}
+        }
+    }
+
+    for asset in &lesson.media {
+        let _ = writeln!(
+            html,
+            "<p><a href=\"{}{}\">{}</a></p>",
+            root,
+            escape(&media_href(asset)?),
+            escape(asset)
+        );
+    }
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+/// Build the package zip in memory. Media is read from below `asset_root`
+/// and every referenced file must exist.
+pub fn build_package(
+    catalog: &LessonCatalog,
+    asset_root: &Path,
+    format: PackageFormat,
+    title: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new();
+    zip.add(MANIFEST_FILE, manifest(catalog, format, title)?.as_bytes())?;
+
+    let mut copied = Vec::new();
+    for lesson in catalog.ordered() {
+        // Rendering checks every media path before any file is read
+        let page = lesson_html(lesson, format)?;
+        lesson.validate_assets(asset_root)?;
+        zip.add(&lesson_href(lesson), page.as_bytes())?;
+        for asset in lesson.asset_references() {
+            let href = media_href(asset)?;
+            if !copied.contains(&href) {
+                zip.add(&href, &fs::read(asset_root.join(asset))?)?;
+                copied.push(href);
+            }
+        }
+    }
+    Ok(zip.finish())
+}
+
+pub fn write_package(
+    catalog: &LessonCatalog,
+    asset_root: &Path,
+    format: PackageFormat,
+    title: &str,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let bytes = build_package(catalog, asset_root, format, title)?;
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, bytes)?;
+    Ok(())
+}
+
+/// Writes a zip archive with every entry stored uncompressed
+#[derive(Default)]
+pub struct ZipWriter {
+    buffer: Vec<u8>,
+    central: Vec<u8>,
+    names: Vec<String>,
+}
+
+impl ZipWriter {
+    const LOCAL_HEADER: u32 = 0x0403_4b50;
+    const CENTRAL_HEADER: u32 = 0x0201_4b50;
+    const END_OF_CENTRAL: u32 = 0x0605_4b50;
+    /// 1980-01-01 00:00, so identical input gives an identical archive
+    const DOS_DATE: u16 = 0x0021;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, data: &[u8]) -> anyhow::Result<()> {
+        if self.names.iter().any(|existing| existing == name) {
+            anyhow::bail!("Duplicate zip entry: {}", name);
+        }
+        if self.names.len() >= u16::MAX as usize || name.len() > u16::MAX as usize {
+            anyhow::bail!("Zip entry {} exceeds the archive limits", name);
+        }
+        // Entry sizes and offsets, including where the central directory
+        // starts, are 32-bit
+        let buffer_end = self.buffer.len() + 30 + name.len() + data.len();
+        let central_end = self.central.len() + 46 + name.len();
+        if buffer_end > u32::MAX as usize || central_end > u32::MAX as usize {
+            anyhow::bail!("Zip archive would exceed 4 GiB at entry {}", name);
+        }
+        let offset = self.buffer.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        put32(&mut self.buffer, Self::LOCAL_HEADER);
+        put_entry_fields(&mut self.buffer, crc, size, name);
+        self.buffer.extend_from_slice(name.as_bytes());
+        self.buffer.extend_from_slice(data);
+
+        put32(&mut self.central, Self::CENTRAL_HEADER);
+        put16(&mut self.central, 20); // version made by
+        put_entry_fields(&mut self.central, crc, size, name);
+        put16(&mut self.central, 0); // comment length
+        put16(&mut self.central, 0); // disk number
+        put16(&mut self.central, 0); // internal attributes
+        put32(&mut self.central, 0); // external attributes
+        put32(&mut self.central, offset);
+        self.central.extend_from_slice(name.as_bytes());
+
+        self.names.push(name.to_string());
+        Ok(())
+    }
+
+    /// Append the central directory. `add` keeps every offset in range.
+    pub fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.buffer.len() as u32;
+        let central_size = self.central.len() as u32;
+        let count = self.names.len() as u16;
+
+        self.buffer.extend_from_slice(&self.central);
+        put32(&mut self.buffer, Self::END_OF_CENTRAL);
+        put16(&mut self.buffer, 0);
+        put16(&mut self.buffer, 0);
+        put16(&mut self.buffer, count);
+        put16(&mut self.buffer, count);
+        put32(&mut self.buffer, central_size);
+        put32(&mut self.buffer, central_offset);
+        put16(&mut self.buffer, 0);
+        self.buffer
+    }
+}
+
+/// Fields shared by the local and central directory headers
+fn put_entry_fields(buffer: &mut Vec<u8>, crc: u32, size: u32, name: &str) {
+    put16(buffer, 20); // version needed
+    put16(buffer, 0x0800); // UTF-8 names
+    put16(buffer, 0); // stored
+    put16(buffer, 0); // time
+    put16(buffer, ZipWriter::DOS_DATE);
+    put32(buffer, crc);
+    put32(buffer, size);
+    put32(buffer, size);
+    put16(buffer, name.len() as u16);
+    put16(buffer, 0); // extra length
+}
+
+fn put16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Read the entries of an uncompressed zip, checking each CRC
+#[cfg(test)]
+pub(crate) fn read_zip(bytes: &[u8]) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let u16_at = |at: usize| -> anyhow::Result<u16> {
+        let slice = bytes
+            .get(at..at + 2)
+            .ok_or_else(|| anyhow::anyhow!("Truncated zip"))?;
+        Ok(u16::from_le_bytes([slice[0], slice[1]]))
+    };
+    let u32_at = |at: usize| -> anyhow::Result<u32> {
+        let slice = bytes
+            .get(at..at + 4)
+            .ok_or_else(|| anyhow::anyhow!("Truncated zip"))?;
+        Ok(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+    };
+
+    let end = bytes
+        .len()
+        .checked_sub(22)
+        .filter(|&end| u32_at(end).ok() == Some(ZipWriter::END_OF_CENTRAL))
+        .ok_or_else(|| anyhow::anyhow!("Missing end of central directory"))?;
+    let count = u16_at(end + 10)? as usize;
+    let mut at = u32_at(end + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(at)? != ZipWriter::CENTRAL_HEADER {
+            anyhow::bail!("Corrupt central directory");
+        }
+        if u16_at(at + 10)? != 0 {
+            anyhow::bail!("Only stored entries are supported");
+        }
+        let crc = u32_at(at + 16)?;
+        let size = u32_at(at + 20)? as usize;
+        let name_len = u16_at(at + 28)? as usize;
+        let extra_len = u16_at(at + 30)? as usize;
+        let comment_len = u16_at(at + 32)? as usize;
+        let local = u32_at(at + 42)? as usize;
+        let name = bytes
+            .get(at + 46..at + 46 + name_len)
+            .ok_or_else(|| anyhow::anyhow!("Truncated zip"))?;
+        let name = String::from_utf8(name.to_vec())?;
+
+        let data_start = local + 30 + u16_at(local + 26)? as usize + u16_at(local + 28)? as usize;
+        let data = bytes
+            .get(data_start..data_start + size)
+            .ok_or_else(|| anyhow::anyhow!("Truncated zip entry {}", name))?;
+        if crc32(data) != crc {
+            anyhow::bail!("Checksum mismatch in {}", name);
+        }
+        entries.push((name, data.to_vec()));
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::education::InteractiveElement;
+
+    fn catalog() -> LessonCatalog {
+        let mut first = EducationalContent::new(
+            "01-basics".to_string(),
+            "Basics & Setup".to_string(),
+            "Start <here>".to_string(),
+        );
+        first.add_interactive_element(InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Image {
+                asset: "images/logo.png".to_string(),
+                alt_text: "Logo".to_string(),
+            },
+        ));
+        let mut second = EducationalContent::new(
+            "02-ownership".to_string(),
+            "Ownership".to_string(),
+            "Moves".to_string(),
+        );
+        second.prerequisites.push("01-basics".to_string());
+        LessonCatalog::new(vec![second, first]).unwrap()
+    }
+
+    fn asset_root() -> std::path::PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("learn_liberty_package_{}", std::process::id()));
+        fs::create_dir_all(root.join("images")).unwrap();
+        fs::write(root.join("images/logo.png"), b"not really a png").unwrap();
+        root
+    }
+
+    /// Check that tags nest properly and return the opening tags in order
+    fn element_names(xml: &str) -> Vec<String> {
+        let mut stack: Vec<String> = Vec::new();
+        let mut opened = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').unwrap() + start;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with('?') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop().as_deref(), Some(name), "mismatched </{}>", name);
+                continue;
+            }
+            let name = tag.split_whitespace().next().unwrap().trim_end_matches('/');
+            opened.push(name.to_string());
+            if !tag.ends_with('/') {
+                stack.push(name.to_string());
+            }
+        }
+        assert!(stack.is_empty(), "unclosed tags: {:?}", stack);
+        opened
+    }
+
+    fn attribute_values<'a>(xml: &'a str, attribute: &str) -> Vec<&'a str> {
+        let needle = format!(" {}=\"", attribute);
+        xml.match_indices(&needle)
+            .map(|(at, _)| {
+                let value = &xml[at + needle.len()..];
+                &value[..value.find('"').unwrap()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scorm_manifest_structure() {
+        let xml = manifest(&catalog(), PackageFormat::Scorm12, "Learn Liberty").unwrap();
+        let names = element_names(&xml);
+
+        assert_eq!(names[0], "manifest");
+        assert!(names.contains(&"schemaversion".to_string()));
+        assert!(xml.contains("<schemaversion>1.2</schemaversion>"));
+        assert_eq!(names.iter().filter(|name| *name == "resource").count(), 2);
+
+        let resources = attribute_values(&xml, "identifier");
+        for reference in attribute_values(&xml, "identifierref") {
+            assert!(resources.contains(&reference), "dangling {}", reference);
+        }
+        let first = xml.find("item_01-basics\"").unwrap();
+        let second = xml.find("item_02-ownership\"").unwrap();
+        assert!(first < second);
+        assert!(xml.contains("<adlcp:prerequisites type=\"aicc_script\">item_01-basics<"));
+        assert!(xml.contains("Basics &amp; Setup"));
+    }
+
+    #[test]
+    fn test_common_cartridge_manifest_structure() {
+        let xml = manifest(&catalog(), PackageFormat::CommonCartridge, "Learn Liberty").unwrap();
+        let names = element_names(&xml);
+
+        assert!(xml.contains("<schema>IMS Common Cartridge</schema>"));
+        assert!(xml.contains("structure=\"rooted-hierarchy\""));
+        assert_eq!(names.iter().filter(|name| *name == "item").count(), 3);
+        assert!(!xml.contains("adlcp"));
+    }
+
+    #[test]
+    fn test_package_contains_manifest_pages_and_media() {
+        let bytes = build_package(
+            &catalog(),
+            &asset_root(),
+            PackageFormat::Scorm12,
+            "Learn Liberty",
+        )
+        .unwrap();
+        let entries = read_zip(&bytes).unwrap();
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names[0], MANIFEST_FILE);
+        let manifest = String::from_utf8(entries[0].1.clone()).unwrap();
+        for href in attribute_values(&manifest, "href") {
+            assert!(names.contains(&href), "missing {}", href);
+        }
+        let page = &entries[1].1;
+        assert!(String::from_utf8_lossy(page).contains("LMSInitialize"));
+        assert!(String::from_utf8_lossy(page).contains("Start &lt;here&gt;"));
+    }
+
+    #[test]
+    fn test_missing_media_fails_export() {
+        let empty = std::env::temp_dir().join("learn_liberty_package_empty_assets");
+        assert!(build_package(&catalog(), &empty, PackageFormat::Scorm12, "x").is_err());
+        assert!("zip".parse::<PackageFormat>().is_err());
+        assert_eq!(
+            "cc".parse::<PackageFormat>().unwrap(),
+            PackageFormat::CommonCartridge
+        );
+    }
+
+    #[test]
+    fn test_media_paths_stay_inside_the_package() {
+        assert_eq!(
+            media_href("images/logo.png").unwrap(),
+            "media/images/logo.png"
+        );
+        for asset in [
+            "../secret.txt",
+            "images/../../x",
+            "/etc/passwd",
+            "C:\\x",
+            "\\x",
+            "",
+        ] {
+            assert!(media_href(asset).is_err(), "{}", asset);
+        }
+
+        let mut lesson =
+            EducationalContent::new("escape".to_string(), "Escape".to_string(), String::new());
+        lesson.media.push("../../outside.png".to_string());
+        let catalog = LessonCatalog::new(vec![lesson]).unwrap();
+        let error = build_package(&catalog, &asset_root(), PackageFormat::Scorm12, "x");
+        assert!(error.unwrap_err().to_string().contains("outside.png"));
+    }
+
+    #[test]
+    fn test_section_code_is_written_once() {
+        let lesson = crate::lesson_loader::parse_lesson(
+            "# Code\n\n## Example\n\nBefore.\n\n```rust\nlet a = 1 < 2;\n```\n\nAfter.\n",
+            "code",
+        )
+        .unwrap();
+        let html = lesson_html(&lesson, PackageFormat::CommonCartridge).unwrap();
+
+        assert_eq!(html.matches("let a = 1 &lt; 2;").count(), 1);
+        assert!(html.contains("<pre><code class=\"language-rust\">let a = 1 &lt; 2;</code></pre>"));
+        assert!(html.contains("<p>Before.</p>"));
+        assert!(html.contains("<p>After.</p>"));
+        assert!(!html.contains("```"));
+    }
+}
@@ -0,0 +1,462 @@
+//! Learner profile module for Learn Liberty
+//!
+//! This module lets several learners share one machine. Each profile has
+//! its own directory holding `profile.json`, its own save slots and its
+//! learning event log, so one learner's progress, quiz attempts and time
+//! spent never leak into another's.
+
+use crate::achievement::AchievementProgress;
+use crate::adaptive::MasteryModel;
+use crate::analytics::EventLog;
+use crate::hint::{HintTracker, HintUsage};
+use crate::lesson_session::LessonSession;
+use crate::localization::DEFAULT_LOCALE;
+use crate::review::ReviewScheduler;
+use crate::save::{write_atomic, SaveManager};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PROFILE_FILE: &str = "profile.json";
+
+const EVENTS_FILE: &str = "events.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LearnerPreferences {
+    pub text_scale: f32,
+    pub audio_enabled: bool,
+    pub high_contrast: bool,
+    pub locale: String,
+}
+
+impl Default for LearnerPreferences {
+    fn default() -> Self {
+        Self {
+            text_scale: 1.0,
+            audio_enabled: true,
+            high_contrast: false,
+            locale: DEFAULT_LOCALE.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuizAttempt {
+    pub lesson_id: String,
+    pub score: f32,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// A point in a learner's progress through one lesson
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressEntry {
+    pub lesson_id: String,
+    pub progress: f32,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LearnerProgress {
+    pub current_lesson_id: String,
+    pub completed_lessons: Vec<String>,
+    pub history: Vec<ProgressEntry>,
+    pub quiz_attempts: Vec<QuizAttempt>,
+    /// Active seconds spent in each lesson
+    pub time_spent: BTreeMap<String, f64>,
+    /// Spaced-repetition schedule for quiz questions this learner has seen
+    pub reviews: ReviewScheduler,
+    /// Per-topic mastery estimates used to adapt quiz difficulty
+    pub mastery: MasteryModel,
+    /// Hints revealed per question or exercise, for teachers to review
+    pub hint_usage: Vec<HintUsage>,
+    /// Achievement counters and unlocks
+    pub achievements: AchievementProgress,
+}
+
+impl LearnerProgress {
+    /// Fold a finished (or interrupted) lesson session into the progress
+    pub fn record_session(&mut self, session: &LessonSession, timestamp: u64) {
+        let lesson_id = session.lesson_id.clone();
+
+        *self.time_spent.entry(lesson_id.clone()).or_insert(0.0) += session.active_time;
+        self.quiz_attempts
+            .extend(session.quiz_scores.iter().map(|&score| QuizAttempt {
+                lesson_id: lesson_id.clone(),
+                score,
+                timestamp,
+            }));
+        self.history.push(ProgressEntry {
+            lesson_id: lesson_id.clone(),
+            progress: session.progress(),
+            timestamp,
+        });
+        if session.is_complete() && !self.is_lesson_completed(&lesson_id) {
+            self.completed_lessons.push(lesson_id.clone());
+        }
+        self.current_lesson_id = lesson_id;
+    }
+
+    /// Copy lesson progress the app state gathered while this learner
+    /// played, including the sessions of the lessons they closed
+    pub fn capture(&mut self, state: &AppState, timestamp: u64) {
+        for lesson_id in &state.completed_lessons {
+            if !self.is_lesson_completed(lesson_id) {
+                self.completed_lessons.push(lesson_id.clone());
+            }
+        }
+        if !state.current_lesson_id.is_empty() {
+            self.current_lesson_id = state.current_lesson_id.clone();
+            self.history.push(ProgressEntry {
+                lesson_id: state.current_lesson_id.clone(),
+                progress: state.lesson_progress,
+                timestamp,
+            });
+        }
+        self.achievements = state.achievements.clone();
+        self.reviews = state.reviews.clone();
+        self.quiz_attempts = state.learner_progress.quiz_attempts.clone();
+        self.time_spent = state.learner_progress.time_spent.clone();
+        self.hint_usage = state.learner_progress.hint_usage.clone();
+        self.mastery = state.learner_progress.mastery.clone();
+    }
+
+    /// Record the hints a learner revealed during one lesson
+    pub fn record_hints(&mut self, lesson_id: &str, hints: &HintTracker, timestamp: u64) {
+        self.hint_usage
+            .extend(hints.usage().map(|(target_id, revealed)| HintUsage {
+                lesson_id: lesson_id.to_string(),
+                target_id: target_id.to_string(),
+                tiers_revealed: revealed as u32,
+                timestamp,
+            }));
+    }
+
+    /// Total hint tiers revealed in a lesson
+    pub fn hints_used(&self, lesson_id: &str) -> u32 {
+        self.hint_usage
+            .iter()
+            .filter(|usage| usage.lesson_id == lesson_id)
+            .map(|usage| usage.tiers_revealed)
+            .sum()
+    }
+
+    pub fn is_lesson_completed(&self, lesson_id: &str) -> bool {
+        self.completed_lessons.iter().any(|id| id == lesson_id)
+    }
+
+    /// Most recent recorded progress for a lesson
+    pub fn lesson_progress(&self, lesson_id: &str) -> f32 {
+        if self.is_lesson_completed(lesson_id) {
+            return 1.0;
+        }
+        self.history
+            .iter()
+            .rev()
+            .find(|entry| entry.lesson_id == lesson_id)
+            .map_or(0.0, |entry| entry.progress)
+    }
+
+    pub fn total_time_spent(&self) -> f64 {
+        self.time_spent.values().sum()
+    }
+
+    pub fn best_quiz_score(&self, lesson_id: &str) -> Option<f32> {
+        self.quiz_attempts
+            .iter()
+            .filter(|attempt| attempt.lesson_id == lesson_id)
+            .map(|attempt| attempt.score)
+            .reduce(f32::max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnerProfile {
+    pub id: String,
+    pub display_name: String,
+    /// Asset path of the avatar shown on the title screen
+    pub avatar: String,
+    #[serde(default)]
+    pub preferences: LearnerPreferences,
+    #[serde(default)]
+    pub progress: LearnerProgress,
+}
+
+impl LearnerProfile {
+    pub fn new(id: String, display_name: String) -> Self {
+        Self {
+            id,
+            display_name,
+            avatar: String::new(),
+            preferences: LearnerPreferences::default(),
+            progress: LearnerProgress::default(),
+        }
+    }
+
+    /// Make this learner the active one, replacing any lesson state left
+    /// over from a previous learner
+    pub fn apply_to(&self, state: &mut AppState) {
+        let lesson_id = self.progress.current_lesson_id.clone();
+        let progress = self.progress.lesson_progress(&lesson_id);
+        state.learner_id = Some(self.id.clone());
+        state.learner_name = Some(self.display_name.clone());
+        state.advance_lesson(lesson_id, progress);
+        state.completed_lessons = self.progress.completed_lessons.clone();
+        state.achievements = self.progress.achievements.clone();
+        state.reviews = self.progress.reviews.clone();
+        state.learner_progress = self.progress.clone();
+    }
+}
+
+/// Reads and writes learner profiles, one directory per learner
+pub struct ProfileStore {
+    directory: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn profile_dir(&self, id: &str) -> anyhow::Result<PathBuf> {
+        validate_id(id)?;
+        Ok(self.directory.join(id))
+    }
+
+    pub fn create(&self, id: &str, display_name: &str) -> anyhow::Result<LearnerProfile> {
+        if self.profile_dir(id)?.join(PROFILE_FILE).exists() {
+            anyhow::bail!("Profile {} already exists", id);
+        }
+        let profile = LearnerProfile::new(id.to_string(), display_name.to_string());
+        self.save(&profile)?;
+        Ok(profile)
+    }
+
+    pub fn save(&self, profile: &LearnerProfile) -> anyhow::Result<()> {
+        let path = self.profile_dir(&profile.id)?.join(PROFILE_FILE);
+        write_atomic(&path, &serde_json::to_string_pretty(profile)?)
+    }
+
+    pub fn load(&self, id: &str) -> anyhow::Result<LearnerProfile> {
+        let path = self.profile_dir(id)?.join(PROFILE_FILE);
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read profile {}: {}", id, e))?;
+        let profile: LearnerProfile = serde_json::from_str(&contents)?;
+        if profile.id != id {
+            anyhow::bail!("Profile file for {} belongs to {}", id, profile.id);
+        }
+        Ok(profile)
+    }
+
+    /// Remove a learner together with their save slots
+    pub fn delete(&self, id: &str) -> anyhow::Result<()> {
+        fs::remove_dir_all(self.profile_dir(id)?)?;
+        Ok(())
+    }
+
+    /// Every stored profile, ordered by display name. Profiles that fail
+    /// to load are reported and skipped so one bad file cannot lock every
+    /// learner out.
+    pub fn list(&self) -> anyhow::Result<Vec<LearnerProfile>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut profiles = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if !entry.path().join(PROFILE_FILE).exists() {
+                continue;
+            }
+            let Some(id) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            match self.load(&id) {
+                Ok(profile) => profiles.push(profile),
+                Err(e) => eprintln!("Skipping profile {}: {}", id, e),
+            }
+        }
+        profiles.sort_by(|a, b| {
+            a.display_name
+                .to_lowercase()
+                .cmp(&b.display_name.to_lowercase())
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(profiles)
+    }
+
+    /// Learning events recorded for one learner
+    pub fn events(&self, id: &str) -> anyhow::Result<EventLog> {
+        Ok(EventLog::new(self.profile_dir(id)?.join(EVENTS_FILE)))
+    }
+
+    /// Save slots that belong to one learner only
+    pub fn saves(&self, id: &str) -> anyhow::Result<SaveManager> {
+        Ok(SaveManager::new(self.profile_dir(id)?.join("saves")))
+    }
+}
+
+/// Profile ids double as directory names, so they are restricted to a
+/// character set that cannot name another path
+fn validate_id(id: &str) -> anyhow::Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        anyhow::bail!("Invalid profile id: {:?}", id);
+    }
+    Ok(())
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::education::EducationalContent;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "learn_liberty_profile_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_create_list_and_load() {
+        let store = ProfileStore::new(scratch_dir("list"));
+        store.create("sam", "Sam").unwrap();
+        store.create("alex", "alex").unwrap();
+
+        assert!(store.create("sam", "Sam again").is_err());
+        let names: Vec<String> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|profile| profile.display_name)
+            .collect();
+        assert_eq!(names, vec!["alex", "Sam"]);
+        assert_eq!(store.load("sam").unwrap().preferences.text_scale, 1.0);
+    }
+
+    #[test]
+    fn test_list_skips_corrupt_profiles() {
+        let store = ProfileStore::new(scratch_dir("corrupt"));
+        store.create("sam", "Sam").unwrap();
+        store.create("alex", "Alex").unwrap();
+        let broken = store.profile_dir("alex").unwrap().join(PROFILE_FILE);
+        fs::write(&broken, "{ not json").unwrap();
+
+        let ids: Vec<String> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|profile| profile.id)
+            .collect();
+        assert_eq!(ids, vec!["sam"]);
+        assert!(store.load("alex").is_err());
+        let _ = fs::remove_dir_all(store.directory());
+    }
+
+    #[test]
+    fn test_ids_cannot_escape_store() {
+        let store = ProfileStore::new(scratch_dir("ids"));
+
+        assert!(store.create("../other", "Other").is_err());
+        assert!(store.create("", "Nobody").is_err());
+        assert!(store.load("a/b").is_err());
+    }
+
+    #[test]
+    fn test_profiles_are_isolated() {
+        let store = ProfileStore::new(scratch_dir("isolated"));
+        let mut sam = store.create("sam", "Sam").unwrap();
+        store.create("alex", "Alex").unwrap();
+
+        let lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Ownership".to_string(),
+        );
+        let mut session = LessonSession::new(&lesson);
+        session.update(42.0);
+        session.record_quiz_score(0.75);
+        sam.progress.record_session(&session, 100);
+        let mut hints = HintTracker::new();
+        hints.reveal("q1", &[crate::hint::Hint::new("Think about moves")]);
+        sam.progress.record_hints("lesson_1", &hints, 100);
+        store.save(&sam).unwrap();
+
+        let sam = store.load("sam").unwrap();
+        assert!(sam.progress.is_lesson_completed("lesson_1"));
+        assert_eq!(sam.progress.total_time_spent(), 42.0);
+        assert_eq!(sam.progress.best_quiz_score("lesson_1"), Some(0.75));
+        assert_eq!(sam.progress.hints_used("lesson_1"), 1);
+        let alex = store.load("alex").unwrap();
+        assert_eq!(alex.progress, LearnerProgress::default());
+        assert_ne!(
+            store.saves("sam").unwrap().directory(),
+            store.saves("alex").unwrap().directory()
+        );
+    }
+
+    #[test]
+    fn test_apply_and_capture_state() {
+        let mut profile = LearnerProfile::new("sam".to_string(), "Sam".to_string());
+        let mut state = AppState::default();
+        state.complete_lesson("someone_elses_lesson");
+
+        profile.apply_to(&mut state);
+        assert_eq!(state.learner_id.as_deref(), Some("sam"));
+        assert!(state.completed_lessons.is_empty());
+
+        state.advance_lesson("lesson_2".to_string(), 0.5);
+        state.complete_lesson("lesson_1");
+        state.reviews.record("ownership", "moves", 1.0, 5.0, 20_000);
+        let lesson = EducationalContent::new(
+            "lesson_2".to_string(),
+            "Lesson 2".to_string(),
+            "Borrowing".to_string(),
+        );
+        let mut session = LessonSession::new(&lesson);
+        session.update(30.0);
+        session.record_quiz_score(0.5);
+        state.learner_progress.record_session(&session, 150);
+        let mut hints = HintTracker::new();
+        hints.reveal("q1", &[crate::hint::Hint::new("Think about borrows")]);
+        state.learner_progress.record_hints("lesson_2", &hints, 150);
+        profile.progress.capture(&state, 200);
+        assert_eq!(profile.progress.total_time_spent(), 30.0);
+        assert_eq!(profile.progress.best_quiz_score("lesson_2"), Some(0.5));
+        assert_eq!(profile.progress.hints_used("lesson_2"), 1);
+        assert!(profile
+            .progress
+            .reviews
+            .card("ownership", "moves")
+            .is_some());
+        assert_eq!(profile.progress.current_lesson_id, "lesson_2");
+        assert_eq!(profile.progress.lesson_progress("lesson_2"), 0.5);
+        assert_eq!(profile.progress.lesson_progress("lesson_1"), 1.0);
+    }
+}
@@ -0,0 +1,624 @@
+//! Quest module for Learn Liberty
+//!
+//! This module expresses curriculum progression as game quests. A quest
+//! owns a list of objectives (talk to an NPC, reach a zone, complete a
+//! lesson) that are either worked through in order or in parallel.
+//! Quest definitions are loaded from a data file, and game triggers fed
+//! through the quest log complete their objectives.
+
+use crate::education::EducationalContent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuestState {
+    Locked,
+    Active,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectiveOrder {
+    /// Objectives must be completed one after another
+    #[default]
+    Sequential,
+    /// Objectives may be completed in any order
+    Parallel,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ObjectiveKind {
+    TalkTo {
+        npc_id: String,
+    },
+    ReachZone {
+        zone_id: String,
+    },
+    CompleteLesson {
+        lesson_id: String,
+        quiz_score_threshold: Option<f32>,
+    },
+    CollectItem {
+        item_id: String,
+        quantity: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Objective {
+    pub id: String,
+    pub description: String,
+    pub kind: ObjectiveKind,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Reward {
+    Experience(u32),
+    Item { item_id: String, quantity: u32 },
+    UnlockLesson(String),
+}
+
+/// Something that happened in the game world that may progress a quest
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuestTrigger {
+    TalkedTo(String),
+    EnteredZone(String),
+    LessonCompleted {
+        lesson_id: String,
+        quiz_score: Option<f32>,
+    },
+    /// The player now holds `total` of the given item
+    ItemCollected {
+        item_id: String,
+        total: u32,
+    },
+}
+
+/// Notification emitted by the quest log when quest state changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QuestEvent {
+    Unlocked {
+        quest_id: String,
+    },
+    ObjectiveCompleted {
+        quest_id: String,
+        objective_id: String,
+    },
+    Completed {
+        quest_id: String,
+        rewards: Vec<Reward>,
+    },
+    Failed {
+        quest_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub state: QuestState,
+    pub order: ObjectiveOrder,
+    pub objectives: Vec<Objective>,
+    pub rewards: Vec<Reward>,
+    pub prerequisites: Vec<String>,
+}
+
+impl Objective {
+    pub fn new(id: String, description: String, kind: ObjectiveKind) -> Self {
+        Self {
+            id,
+            description,
+            kind,
+            completed: false,
+        }
+    }
+
+    /// Create an objective that requires finishing the given lesson with a
+    /// quiz score at or above its completion threshold
+    pub fn complete_lesson(id: String, lesson: &EducationalContent) -> Self {
+        Self::new(
+            id,
+            format!("Complete \"{}\"", lesson.title),
+            ObjectiveKind::CompleteLesson {
+                lesson_id: lesson.id.clone(),
+                quiz_score_threshold: lesson.completion_criteria.quiz_score_threshold,
+            },
+        )
+    }
+
+    pub fn is_satisfied_by(&self, trigger: &QuestTrigger) -> bool {
+        match (&self.kind, trigger) {
+            (ObjectiveKind::TalkTo { npc_id }, QuestTrigger::TalkedTo(other)) => npc_id == other,
+            (ObjectiveKind::ReachZone { zone_id }, QuestTrigger::EnteredZone(other)) => {
+                zone_id == other
+            }
+            (
+                ObjectiveKind::CompleteLesson {
+                    lesson_id,
+                    quiz_score_threshold,
+                },
+                QuestTrigger::LessonCompleted {
+                    lesson_id: other,
+                    quiz_score,
+                },
+            ) => {
+                lesson_id == other
+                    && match quiz_score_threshold {
+                        Some(threshold) => quiz_score.is_some_and(|score| score >= *threshold),
+                        None => true,
+                    }
+            }
+            (
+                ObjectiveKind::CollectItem { item_id, quantity },
+                QuestTrigger::ItemCollected {
+                    item_id: other,
+                    total,
+                },
+            ) => item_id == other && total >= quantity,
+            _ => false,
+        }
+    }
+}
+
+impl Quest {
+    pub fn new(id: String, title: String, description: String, order: ObjectiveOrder) -> Self {
+        Self {
+            id,
+            title,
+            description,
+            state: QuestState::Locked,
+            order,
+            objectives: Vec::new(),
+            rewards: Vec::new(),
+            prerequisites: Vec::new(),
+        }
+    }
+
+    pub fn add_objective(&mut self, objective: Objective) {
+        self.objectives.push(objective);
+    }
+
+    pub fn add_reward(&mut self, reward: Reward) {
+        self.rewards.push(reward);
+    }
+
+    pub fn add_prerequisite(&mut self, quest_id: String) {
+        self.prerequisites.push(quest_id);
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.objectives.iter().all(|objective| objective.completed)
+    }
+
+    /// Objectives that can currently be progressed
+    pub fn open_objectives(&self) -> Vec<&Objective> {
+        let pending = self
+            .objectives
+            .iter()
+            .filter(|objective| !objective.completed);
+        match self.order {
+            ObjectiveOrder::Sequential => pending.take(1).collect(),
+            ObjectiveOrder::Parallel => pending.collect(),
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.objectives.is_empty() {
+            return 0.0;
+        }
+        let done = self.objectives.iter().filter(|o| o.completed).count();
+        done as f32 / self.objectives.len() as f32
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct QuestFile {
+    #[serde(default)]
+    quest: Vec<QuestDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuestDefinition {
+    id: String,
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    order: ObjectiveOrder,
+    #[serde(default)]
+    objective: Vec<ObjectiveDefinition>,
+    #[serde(default)]
+    reward: Vec<Reward>,
+    #[serde(default)]
+    prerequisites: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectiveDefinition {
+    id: String,
+    description: String,
+    kind: ObjectiveKind,
+}
+
+/// Tracks every quest known to the player and the events they produced
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuestLog {
+    pub quests: Vec<Quest>,
+    #[serde(skip)]
+    events: Vec<QuestEvent>,
+}
+
+impl QuestLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a log from quest definitions. Duplicate ids and unknown
+    /// prerequisites are rejected. A quest without objectives completes as
+    /// soon as it unlocks, handing out its rewards.
+    pub fn from_toml_str(source: &str) -> anyhow::Result<Self> {
+        let file: QuestFile = toml::from_str(source)?;
+        let ids: HashSet<&str> = file.quest.iter().map(|quest| quest.id.as_str()).collect();
+        if ids.len() != file.quest.len() {
+            anyhow::bail!("Duplicate quest ids in quest definitions");
+        }
+
+        let mut log = Self::new();
+        for definition in &file.quest {
+            if let Some(unknown) = definition
+                .prerequisites
+                .iter()
+                .find(|id| !ids.contains(id.as_str()))
+            {
+                anyhow::bail!("Quest {} requires unknown quest {}", definition.id, unknown);
+            }
+        }
+        for definition in file.quest {
+            let mut quest = Quest::new(
+                definition.id,
+                definition.title,
+                definition.description,
+                definition.order,
+            );
+            for objective in definition.objective {
+                quest.add_objective(Objective::new(
+                    objective.id,
+                    objective.description,
+                    objective.kind,
+                ));
+            }
+            quest.rewards = definition.reward;
+            quest.prerequisites = definition.prerequisites;
+            log.quests.push(quest);
+        }
+        log.refresh_unlocks();
+        Ok(log)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&source)
+    }
+
+    /// Add a quest and unlock it straight away if its prerequisites are met
+    pub fn add_quest(&mut self, quest: Quest) {
+        self.quests.push(quest);
+        self.refresh_unlocks();
+    }
+
+    pub fn quest(&self, quest_id: &str) -> Option<&Quest> {
+        self.quests.iter().find(|quest| quest.id == quest_id)
+    }
+
+    pub fn active_quests(&self) -> impl Iterator<Item = &Quest> {
+        self.quests
+            .iter()
+            .filter(|quest| quest.state == QuestState::Active)
+    }
+
+    /// Feed a game trigger through every active quest
+    pub fn handle(&mut self, trigger: &QuestTrigger) {
+        let mut completed_any = false;
+
+        for quest in self
+            .quests
+            .iter_mut()
+            .filter(|quest| quest.state == QuestState::Active)
+        {
+            let matching: Vec<String> = quest
+                .open_objectives()
+                .into_iter()
+                .filter(|objective| objective.is_satisfied_by(trigger))
+                .map(|objective| objective.id.clone())
+                .collect();
+
+            for objective in quest
+                .objectives
+                .iter_mut()
+                .filter(|objective| matching.contains(&objective.id))
+            {
+                objective.completed = true;
+                self.events.push(QuestEvent::ObjectiveCompleted {
+                    quest_id: quest.id.clone(),
+                    objective_id: objective.id.clone(),
+                });
+            }
+
+            if !matching.is_empty() && quest.is_complete() {
+                quest.state = QuestState::Completed;
+                completed_any = true;
+                self.events.push(QuestEvent::Completed {
+                    quest_id: quest.id.clone(),
+                    rewards: quest.rewards.clone(),
+                });
+            }
+        }
+
+        if completed_any {
+            self.refresh_unlocks();
+        }
+    }
+
+    pub fn fail_quest(&mut self, quest_id: &str) -> anyhow::Result<()> {
+        let quest = self
+            .quests
+            .iter_mut()
+            .find(|quest| quest.id == quest_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown quest: {}", quest_id))?;
+
+        if quest.state != QuestState::Active {
+            anyhow::bail!("Quest {} is not active ({:?})", quest_id, quest.state);
+        }
+
+        quest.state = QuestState::Failed;
+        self.events.push(QuestEvent::Failed {
+            quest_id: quest_id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Take the events produced since the last call
+    pub fn drain_events(&mut self) -> Vec<QuestEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Activate quests whose prerequisites are complete. A quest without
+    /// objectives has nothing left to do, so it completes as soon as it
+    /// unlocks, which may unlock further quests in turn.
+    fn refresh_unlocks(&mut self) {
+        loop {
+            let completed: Vec<String> = self
+                .quests
+                .iter()
+                .filter(|quest| quest.state == QuestState::Completed)
+                .map(|quest| quest.id.clone())
+                .collect();
+
+            let mut completed_any = false;
+            for quest in self
+                .quests
+                .iter_mut()
+                .filter(|quest| quest.state == QuestState::Locked)
+            {
+                if !quest.prerequisites.iter().all(|id| completed.contains(id)) {
+                    continue;
+                }
+                quest.state = QuestState::Active;
+                self.events.push(QuestEvent::Unlocked {
+                    quest_id: quest.id.clone(),
+                });
+                if quest.is_complete() {
+                    quest.state = QuestState::Completed;
+                    completed_any = true;
+                    self.events.push(QuestEvent::Completed {
+                        quest_id: quest.id.clone(),
+                        rewards: quest.rewards.clone(),
+                    });
+                }
+            }
+            if !completed_any {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn talk_quest(id: &str, order: ObjectiveOrder) -> Quest {
+        let mut quest = Quest::new(
+            id.to_string(),
+            "Meet the Villagers".to_string(),
+            "Say hello".to_string(),
+            order,
+        );
+        quest.add_objective(Objective::new(
+            "talk_mayor".to_string(),
+            "Talk to the mayor".to_string(),
+            ObjectiveKind::TalkTo {
+                npc_id: "mayor".to_string(),
+            },
+        ));
+        quest.add_objective(Objective::new(
+            "reach_library".to_string(),
+            "Find the library".to_string(),
+            ObjectiveKind::ReachZone {
+                zone_id: "library".to_string(),
+            },
+        ));
+        quest
+    }
+
+    #[test]
+    fn test_sequential_objectives_complete_in_order() {
+        let mut log = QuestLog::new();
+        log.add_quest(talk_quest("intro", ObjectiveOrder::Sequential));
+
+        log.handle(&QuestTrigger::EnteredZone("library".to_string()));
+        assert_eq!(log.quest("intro").unwrap().progress(), 0.0);
+
+        log.handle(&QuestTrigger::TalkedTo("mayor".to_string()));
+        log.handle(&QuestTrigger::EnteredZone("library".to_string()));
+
+        assert_eq!(log.quest("intro").unwrap().state, QuestState::Completed);
+    }
+
+    #[test]
+    fn test_parallel_objectives_and_unlocks() {
+        let mut log = QuestLog::new();
+        log.add_quest(talk_quest("intro", ObjectiveOrder::Parallel));
+        let mut follow_up = talk_quest("follow_up", ObjectiveOrder::Parallel);
+        follow_up.add_prerequisite("intro".to_string());
+        log.add_quest(follow_up);
+
+        assert_eq!(log.quest("follow_up").unwrap().state, QuestState::Locked);
+
+        log.handle(&QuestTrigger::EnteredZone("library".to_string()));
+        log.handle(&QuestTrigger::TalkedTo("mayor".to_string()));
+
+        assert_eq!(log.quest("intro").unwrap().state, QuestState::Completed);
+        assert_eq!(log.quest("follow_up").unwrap().state, QuestState::Active);
+
+        let events = log.drain_events();
+        assert!(events.contains(&QuestEvent::Unlocked {
+            quest_id: "follow_up".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_lesson_objective_uses_quiz_threshold() {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Rust Basics".to_string(),
+            "Ownership".to_string(),
+        );
+        lesson.completion_criteria.quiz_score_threshold = Some(0.8);
+
+        let mut quest = Quest::new(
+            "study".to_string(),
+            "Study".to_string(),
+            "Finish lesson one".to_string(),
+            ObjectiveOrder::Sequential,
+        );
+        quest.add_objective(Objective::complete_lesson("finish".to_string(), &lesson));
+        quest.add_reward(Reward::UnlockLesson("lesson_2".to_string()));
+
+        let mut log = QuestLog::new();
+        log.add_quest(quest);
+
+        log.handle(&QuestTrigger::LessonCompleted {
+            lesson_id: "lesson_1".to_string(),
+            quiz_score: Some(0.5),
+        });
+        assert_eq!(log.quest("study").unwrap().state, QuestState::Active);
+
+        log.handle(&QuestTrigger::LessonCompleted {
+            lesson_id: "lesson_1".to_string(),
+            quiz_score: Some(0.9),
+        });
+        assert_eq!(log.quest("study").unwrap().state, QuestState::Completed);
+    }
+
+    #[test]
+    fn test_collect_item_objective() {
+        let mut quest = Quest::new(
+            "coins".to_string(),
+            "Pocket Money".to_string(),
+            "Collect three coins".to_string(),
+            ObjectiveOrder::Sequential,
+        );
+        quest.add_objective(Objective::new(
+            "collect".to_string(),
+            "Collect coins".to_string(),
+            ObjectiveKind::CollectItem {
+                item_id: "crab_coin".to_string(),
+                quantity: 3,
+            },
+        ));
+
+        let mut log = QuestLog::new();
+        log.add_quest(quest);
+
+        log.handle(&QuestTrigger::ItemCollected {
+            item_id: "crab_coin".to_string(),
+            total: 2,
+        });
+        assert_eq!(log.quest("coins").unwrap().state, QuestState::Active);
+
+        log.handle(&QuestTrigger::ItemCollected {
+            item_id: "crab_coin".to_string(),
+            total: 3,
+        });
+        assert_eq!(log.quest("coins").unwrap().state, QuestState::Completed);
+    }
+
+    #[test]
+    fn test_quest_without_objectives_completes_on_unlock() {
+        let mut log = QuestLog::new();
+        let mut follow_up = talk_quest("follow_up", ObjectiveOrder::Parallel);
+        follow_up.add_prerequisite("milestone".to_string());
+        log.add_quest(follow_up);
+        log.add_quest(Quest::new(
+            "milestone".to_string(),
+            "Milestone".to_string(),
+            String::new(),
+            ObjectiveOrder::Sequential,
+        ));
+
+        assert_eq!(log.quest("milestone").unwrap().state, QuestState::Completed);
+        assert_eq!(log.quest("follow_up").unwrap().state, QuestState::Active);
+    }
+
+    #[test]
+    fn test_load_quest_definitions() {
+        let log = QuestLog::load("assets/data/quests.toml").unwrap();
+        assert!(log.active_quests().count() > 0);
+
+        let welcome = r#"
+            [[quest]]
+            id = "welcome"
+            title = "Welcome to Liberty"
+            reward = [{ Experience = 10 }]
+        "#;
+        let mut log = QuestLog::from_toml_str(welcome).unwrap();
+        assert_eq!(log.quest("welcome").unwrap().state, QuestState::Completed);
+        assert!(log.drain_events().contains(&QuestEvent::Completed {
+            quest_id: "welcome".to_string(),
+            rewards: vec![Reward::Experience(10)],
+        }));
+
+        let unknown = r#"
+            [[quest]]
+            id = "late"
+            title = "Late"
+            prerequisites = ["missing"]
+
+            [[quest.objective]]
+            id = "talk"
+            description = "Talk to the mayor"
+            kind = { TalkTo = { npc_id = "mayor" } }
+        "#;
+        assert!(QuestLog::from_toml_str(unknown).is_err());
+    }
+
+    #[test]
+    fn test_fail_quest_and_round_trip() {
+        let mut log = QuestLog::new();
+        log.add_quest(talk_quest("intro", ObjectiveOrder::Sequential));
+
+        assert!(log.fail_quest("missing").is_err());
+        log.fail_quest("intro").unwrap();
+        assert!(log.fail_quest("intro").is_err());
+
+        let json = serde_json::to_string(&log).unwrap();
+        let restored: QuestLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.quest("intro").unwrap().state, QuestState::Failed);
+    }
+}
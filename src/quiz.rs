@@ -0,0 +1,515 @@
+//! Quiz module for Learn Liberty
+//!
+//! This module gives `ElementType::Quiz` elements a structured model.
+//! Quizzes hold weighted questions of several kinds, are scored with
+//! partial credit, and can present their options in a seeded random
+//! order. The resulting score is what `CompletionCriteria` checks against
+//! `quiz_score_threshold`.
+
+use crate::education::{CompletionCriteria, ElementPayload, InteractiveElement};
+use crate::hint::{total_penalty, Hint, HintTracker};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn default_difficulty() -> f32 {
+    0.5
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuestionKind {
+    MultipleChoice {
+        options: Vec<String>,
+        correct: usize,
+    },
+    MultiSelect {
+        options: Vec<String>,
+        correct: Vec<usize>,
+    },
+    TrueFalse {
+        answer: bool,
+    },
+    FillInTheBlank {
+        accepted: Vec<String>,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// `items` are listed in their correct order unless `correct_order`
+    /// gives the indices of `items` in the right sequence
+    Ordering {
+        items: Vec<String>,
+        #[serde(default)]
+        correct_order: Vec<usize>,
+    },
+    Numeric {
+        answer: f64,
+        #[serde(default)]
+        tolerance: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Question {
+    pub id: String,
+    pub prompt: String,
+    #[serde(flatten)]
+    pub kind: QuestionKind,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Topics the question exercises, used by the mastery model
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// From 0.0 (trivial) to 1.0 (hard)
+    #[serde(default = "default_difficulty")]
+    pub difficulty: f32,
+    /// Tiers of help, revealed in order
+    #[serde(default)]
+    pub hints: Vec<Hint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quiz {
+    pub id: String,
+    pub title: String,
+    pub questions: Vec<Question>,
+}
+
+/// A learner's response to one question
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Answer {
+    Choice(usize),
+    Choices(Vec<usize>),
+    Bool(bool),
+    Text(String),
+    /// Indices into the presented items, in the order the learner chose
+    Order(Vec<usize>),
+    Number(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuestionResult {
+    pub question_id: String,
+    /// Credit between 0.0 and 1.0, after hint penalties
+    pub credit: f32,
+    pub weight: f32,
+    #[serde(default)]
+    pub hints_used: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuizResult {
+    pub quiz_id: String,
+    pub questions: Vec<QuestionResult>,
+    /// Weighted score between 0.0 and 1.0
+    pub score: f32,
+}
+
+impl QuizResult {
+    /// Whether the score satisfies the lesson's quiz threshold
+    pub fn passes(&self, criteria: &CompletionCriteria) -> bool {
+        criteria
+            .quiz_score_threshold
+            .is_none_or(|threshold| self.score >= threshold)
+    }
+}
+
+impl QuestionKind {
+    /// Credit between 0.0 and 1.0 for an answer
+    pub fn credit(&self, answer: &Answer) -> f32 {
+        match (self, answer) {
+            (QuestionKind::MultipleChoice { correct, .. }, Answer::Choice(choice)) => {
+                (choice == correct) as u8 as f32
+            }
+            (QuestionKind::MultiSelect { correct, .. }, Answer::Choices(choices)) => {
+                if correct.is_empty() {
+                    return choices.is_empty() as u8 as f32;
+                }
+                let chosen: HashSet<&usize> = choices.iter().collect();
+                let hits = chosen.iter().filter(|c| correct.contains(c)).count() as f32;
+                let misses = chosen.len() as f32 - hits;
+                ((hits - misses) / correct.len() as f32).max(0.0)
+            }
+            (QuestionKind::TrueFalse { answer }, Answer::Bool(given)) => {
+                (answer == given) as u8 as f32
+            }
+            (
+                QuestionKind::FillInTheBlank {
+                    accepted,
+                    case_sensitive,
+                },
+                Answer::Text(text),
+            ) => {
+                let text = text.trim();
+                accepted.iter().any(|expected| {
+                    if *case_sensitive {
+                        expected.trim() == text
+                    } else {
+                        expected.trim().eq_ignore_ascii_case(text)
+                    }
+                }) as u8 as f32
+            }
+            (
+                QuestionKind::Ordering {
+                    items,
+                    correct_order,
+                },
+                Answer::Order(order),
+            ) => {
+                if items.is_empty() {
+                    return 1.0;
+                }
+                let expected = expected_order(items.len(), correct_order);
+                let in_place = expected
+                    .iter()
+                    .zip(order)
+                    .filter(|(expected, given)| expected == given)
+                    .count();
+                in_place as f32 / items.len() as f32
+            }
+            (QuestionKind::Numeric { answer, tolerance }, Answer::Number(given)) => {
+                ((given - answer).abs() <= tolerance.abs()) as u8 as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Present options in a random order, keeping the answer key in step
+    fn shuffle(&mut self, rng: &mut SeededRng) {
+        match self {
+            QuestionKind::MultipleChoice { options, correct } => {
+                let permutation = rng.permutation(options.len());
+                *options = permutation.iter().map(|&i| options[i].clone()).collect();
+                *correct = position_of(&permutation, *correct);
+            }
+            QuestionKind::MultiSelect { options, correct } => {
+                let permutation = rng.permutation(options.len());
+                *options = permutation.iter().map(|&i| options[i].clone()).collect();
+                for index in correct.iter_mut() {
+                    *index = position_of(&permutation, *index);
+                }
+                correct.sort_unstable();
+            }
+            QuestionKind::Ordering {
+                items,
+                correct_order,
+            } => {
+                let expected = expected_order(items.len(), correct_order);
+                let permutation = rng.permutation(items.len());
+                *items = permutation.iter().map(|&i| items[i].clone()).collect();
+                *correct_order = expected
+                    .iter()
+                    .map(|&i| position_of(&permutation, i))
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn expected_order(len: usize, correct_order: &[usize]) -> Vec<usize> {
+    if correct_order.is_empty() {
+        (0..len).collect()
+    } else {
+        correct_order.to_vec()
+    }
+}
+
+/// Where `original` ended up after applying `permutation`
+fn position_of(permutation: &[usize], original: usize) -> usize {
+    permutation
+        .iter()
+        .position(|&i| i == original)
+        .unwrap_or(original)
+}
+
+impl Quiz {
+    /// Take a validated copy of the quiz carried by an element
+    pub fn from_element(element: &InteractiveElement) -> anyhow::Result<Self> {
+        let ElementPayload::Quiz(quiz) = &element.payload else {
+            anyhow::bail!("Element is not a quiz: {:?}", element.element_type());
+        };
+        quiz.validate()?;
+        Ok(quiz.clone())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut ids = HashSet::new();
+        for question in &self.questions {
+            if !ids.insert(question.id.as_str()) {
+                anyhow::bail!("Quiz {} has duplicate question {}", self.id, question.id);
+            }
+            if question.weight <= 0.0 {
+                anyhow::bail!("Question {} must have a positive weight", question.id);
+            }
+            if !(0.0..=1.0).contains(&question.difficulty) {
+                anyhow::bail!(
+                    "Question {} difficulty must be between 0 and 1",
+                    question.id
+                );
+            }
+            let in_range = match &question.kind {
+                QuestionKind::MultipleChoice { options, correct } => *correct < options.len(),
+                QuestionKind::MultiSelect { options, correct } => {
+                    !options.is_empty() && correct.iter().all(|&i| i < options.len())
+                }
+                QuestionKind::FillInTheBlank { accepted, .. } => !accepted.is_empty(),
+                QuestionKind::Ordering {
+                    items,
+                    correct_order,
+                } => {
+                    correct_order.is_empty() || {
+                        let mut sorted = correct_order.clone();
+                        sorted.sort_unstable();
+                        sorted == (0..items.len()).collect::<Vec<_>>()
+                    }
+                }
+                QuestionKind::TrueFalse { .. } | QuestionKind::Numeric { .. } => true,
+            };
+            if !in_range {
+                anyhow::bail!("Question {} has an invalid answer key", question.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// A copy of the quiz with every question's options shuffled. The same
+    /// seed always gives the same order.
+    pub fn shuffled(&self, seed: u64) -> Self {
+        let mut rng = SeededRng::new(seed);
+        let mut quiz = self.clone();
+        for question in &mut quiz.questions {
+            question.kind.shuffle(&mut rng);
+        }
+        quiz
+    }
+
+    /// Score answers keyed by question id. Unanswered questions earn no
+    /// credit.
+    pub fn score(&self, answers: &HashMap<String, Answer>) -> QuizResult {
+        self.score_with_hints(answers, &HintTracker::default())
+    }
+
+    /// Score answers, deducting the penalty of every hint tier the learner
+    /// revealed from that question's credit
+    pub fn score_with_hints(
+        &self,
+        answers: &HashMap<String, Answer>,
+        hints: &HintTracker,
+    ) -> QuizResult {
+        let questions: Vec<QuestionResult> = self
+            .questions
+            .iter()
+            .map(|question| {
+                let hints_used = hints.revealed(&question.id).min(question.hints.len());
+                let credit = answers
+                    .get(&question.id)
+                    .map_or(0.0, |answer| question.kind.credit(answer));
+                QuestionResult {
+                    question_id: question.id.clone(),
+                    credit: (credit - total_penalty(&question.hints, hints_used)).max(0.0),
+                    weight: question.weight,
+                    hints_used: hints_used as u32,
+                }
+            })
+            .collect();
+
+        let total_weight: f32 = questions.iter().map(|q| q.weight).sum();
+        let earned: f32 = questions.iter().map(|q| q.weight * q.credit).sum();
+        let score = if total_weight > 0.0 {
+            earned / total_weight
+        } else {
+            0.0
+        };
+
+        QuizResult {
+            quiz_id: self.id.clone(),
+            questions,
+            score,
+        }
+    }
+}
+
+/// Small deterministic generator (SplitMix64) for shuffling
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Fisher-Yates shuffle of `0..len`
+    pub fn permutation(&mut self, len: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..len).collect();
+        for i in (1..len).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            indices.swap(i, j);
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIZ: &str = r#"{
+        "id": "ownership_quiz",
+        "title": "Ownership",
+        "questions": [
+            {"id": "q1", "prompt": "Who owns a moved value?", "kind": "multiple_choice",
+             "options": ["The old binding", "The new binding", "Nobody"], "correct": 1,
+             "hints": [{"text": "A move transfers ownership."},
+                       {"text": "Look at the binding on the left.", "penalty": 0.5}]},
+            {"id": "q2", "prompt": "Which types are Copy?", "kind": "multi_select",
+             "options": ["i32", "String", "bool", "Vec<u8>"], "correct": [0, 2], "weight": 2.0},
+            {"id": "q3", "prompt": "Borrows can outlive owners.", "kind": "true_false", "answer": false},
+            {"id": "q4", "prompt": "The ___ checker enforces borrowing.", "kind": "fill_in_the_blank",
+             "accepted": ["borrow"]},
+            {"id": "q5", "prompt": "Order the steps", "kind": "ordering",
+             "items": ["declare", "borrow", "drop"]},
+            {"id": "q6", "prompt": "Size of u32 in bytes?", "kind": "numeric", "answer": 4.0, "tolerance": 0.0}
+        ]
+    }"#;
+
+    fn quiz() -> Quiz {
+        let element: InteractiveElement = serde_json::from_value(serde_json::json!({
+            "element_type": "Quiz",
+            "position": [0.0, 0.0],
+            "data": QUIZ,
+        }))
+        .unwrap();
+        Quiz::from_element(&element).unwrap()
+    }
+
+    fn perfect_answers() -> HashMap<String, Answer> {
+        HashMap::from([
+            ("q1".to_string(), Answer::Choice(1)),
+            ("q2".to_string(), Answer::Choices(vec![0, 2])),
+            ("q3".to_string(), Answer::Bool(false)),
+            ("q4".to_string(), Answer::Text(" Borrow ".to_string())),
+            ("q5".to_string(), Answer::Order(vec![0, 1, 2])),
+            ("q6".to_string(), Answer::Number(4.0)),
+        ])
+    }
+
+    #[test]
+    fn test_perfect_score() {
+        let result = quiz().score(&perfect_answers());
+
+        assert_eq!(result.score, 1.0);
+        assert!(result.questions.iter().all(|q| q.credit == 1.0));
+    }
+
+    #[test]
+    fn test_partial_credit_and_weights() {
+        let quiz = quiz();
+        let mut answers = perfect_answers();
+        answers.insert("q2".to_string(), Answer::Choices(vec![0, 1]));
+        answers.insert("q5".to_string(), Answer::Order(vec![0, 2, 1]));
+        answers.remove("q6");
+
+        let result = quiz.score(&answers);
+
+        let credit = |id: &str| {
+            result
+                .questions
+                .iter()
+                .find(|q| q.question_id == id)
+                .unwrap()
+                .credit
+        };
+        assert_eq!(credit("q2"), 0.0);
+        assert!((credit("q5") - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(credit("q6"), 0.0);
+        let expected = (1.0 + 0.0 * 2.0 + 1.0 + 1.0 + 1.0 / 3.0 + 0.0) / 7.0;
+        assert!((result.score - expected).abs() < 1e-6);
+
+        let criteria = CompletionCriteria {
+            quiz_score_threshold: Some(0.8),
+            ..CompletionCriteria::default()
+        };
+        assert!(!result.passes(&criteria));
+        assert!(quiz.score(&perfect_answers()).passes(&criteria));
+    }
+
+    #[test]
+    fn test_hint_penalties_reduce_credit() {
+        let quiz = quiz();
+        let mut hints = HintTracker::new();
+        hints.reveal("q1", &quiz.questions[0].hints);
+
+        let result = quiz.score_with_hints(&perfect_answers(), &hints);
+        assert_eq!(result.questions[0].hints_used, 1);
+        assert!((result.questions[0].credit - 0.9).abs() < 1e-6);
+        assert!(result.score < 1.0);
+
+        hints.reveal("q1", &quiz.questions[0].hints);
+        hints.reveal("q1", &quiz.questions[0].hints);
+        let result = quiz.score_with_hints(&perfect_answers(), &hints);
+        assert_eq!(result.questions[0].hints_used, 2);
+        assert!((result.questions[0].credit - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_seeded_shuffle_keeps_answer_key() {
+        let quiz = quiz();
+        let first = quiz.shuffled(42);
+        let second = quiz.shuffled(42);
+        assert_eq!(first, second);
+
+        let QuestionKind::MultipleChoice { options, correct } = &first.questions[0].kind else {
+            panic!("expected multiple choice");
+        };
+        assert_eq!(options[*correct], "The new binding");
+
+        let QuestionKind::Ordering {
+            items,
+            correct_order,
+        } = &first.questions[4].kind
+        else {
+            panic!("expected ordering");
+        };
+        let ordered: Vec<&str> = correct_order.iter().map(|&i| items[i].as_str()).collect();
+        assert_eq!(ordered, vec!["declare", "borrow", "drop"]);
+
+        let mut answers = perfect_answers();
+        answers.insert("q1".to_string(), Answer::Choice(*correct));
+        answers.insert("q5".to_string(), Answer::Order(correct_order.clone()));
+        let QuestionKind::MultiSelect { correct, .. } = &first.questions[1].kind else {
+            panic!("expected multi select");
+        };
+        answers.insert("q2".to_string(), Answer::Choices(correct.clone()));
+        assert_eq!(first.score(&answers).score, 1.0);
+    }
+
+    #[test]
+    fn test_invalid_quizzes_rejected() {
+        let mut quiz = quiz();
+        quiz.questions[0].kind = QuestionKind::MultipleChoice {
+            options: vec!["only".to_string()],
+            correct: 3,
+        };
+        assert!(quiz.validate().is_err());
+
+        let text = InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Text(crate::education::RichText::plain(QUIZ)),
+        );
+        assert!(Quiz::from_element(&text).is_err());
+    }
+}
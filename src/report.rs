@@ -0,0 +1,368 @@
+//! Teacher report module for Learn Liberty
+//!
+//! This module summarises a class from its learner profiles: completion
+//! and progress per lesson, quiz scores, time spent, hints used and the
+//! topics each learner is struggling with. The summary is written as CSV
+//! for spreadsheets, JSON for other tools and a self-contained HTML page.
+
+use crate::lesson_catalog::LessonCatalog;
+use crate::package::escape;
+use crate::profile::LearnerProfile;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Topics with a mastery estimate below this are reported as struggling
+pub const STRUGGLING_MASTERY: f32 = 0.4;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LessonSummary {
+    pub lesson_id: String,
+    pub title: String,
+    pub completed: bool,
+    pub progress: f32,
+    pub best_quiz_score: Option<f32>,
+    pub quiz_attempts: usize,
+    pub time_spent_secs: f64,
+    pub hints_used: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnerSummary {
+    pub id: String,
+    pub display_name: String,
+    pub lessons_completed: usize,
+    pub total_time_secs: f64,
+    pub average_quiz_score: Option<f32>,
+    pub hints_used: u32,
+    /// Weakest first
+    pub struggling_topics: Vec<String>,
+    pub lessons: Vec<LessonSummary>,
+}
+
+impl LearnerSummary {
+    pub fn new(profile: &LearnerProfile, catalog: &LessonCatalog) -> Self {
+        let progress = &profile.progress;
+        let lessons: Vec<LessonSummary> = catalog
+            .ordered()
+            .map(|lesson| LessonSummary {
+                lesson_id: lesson.id.clone(),
+                title: lesson.title.clone(),
+                completed: progress.is_lesson_completed(&lesson.id),
+                progress: progress.lesson_progress(&lesson.id),
+                best_quiz_score: progress.best_quiz_score(&lesson.id),
+                quiz_attempts: progress
+                    .quiz_attempts
+                    .iter()
+                    .filter(|attempt| attempt.lesson_id == lesson.id)
+                    .count(),
+                time_spent_secs: progress.time_spent.get(&lesson.id).copied().unwrap_or(0.0),
+                hints_used: progress.hints_used(&lesson.id),
+            })
+            .collect();
+
+        let scores: Vec<f32> = lessons.iter().filter_map(|l| l.best_quiz_score).collect();
+        let average_quiz_score = if scores.is_empty() {
+            None
+        } else {
+            Some(scores.iter().sum::<f32>() / scores.len() as f32)
+        };
+
+        let mut struggling: Vec<(&String, f32)> = progress
+            .mastery
+            .topics
+            .iter()
+            .filter(|(_, &mastery)| mastery < STRUGGLING_MASTERY)
+            .map(|(topic, &mastery)| (topic, mastery))
+            .collect();
+        struggling.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        Self {
+            id: profile.id.clone(),
+            display_name: profile.display_name.clone(),
+            lessons_completed: lessons.iter().filter(|l| l.completed).count(),
+            total_time_secs: progress.total_time_spent(),
+            average_quiz_score,
+            hints_used: progress.hint_usage.iter().map(|u| u.tiers_revealed).sum(),
+            struggling_topics: struggling.into_iter().map(|(t, _)| t.clone()).collect(),
+            lessons,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassReport {
+    pub title: String,
+    /// Seconds since the Unix epoch
+    pub generated_at: u64,
+    pub lesson_count: usize,
+    pub learners: Vec<LearnerSummary>,
+}
+
+impl ClassReport {
+    pub fn new(
+        title: &str,
+        catalog: &LessonCatalog,
+        profiles: &[LearnerProfile],
+        generated_at: u64,
+    ) -> Self {
+        Self {
+            title: title.to_string(),
+            generated_at,
+            lesson_count: catalog.len(),
+            learners: profiles
+                .iter()
+                .map(|profile| LearnerSummary::new(profile, catalog))
+                .collect(),
+        }
+    }
+
+    /// One row per learner and lesson
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "learner_id,learner_name,lesson_id,lesson_title,completed,progress,\
+             best_quiz_score,quiz_attempts,time_spent_secs,hints_used\n",
+        );
+        for learner in &self.learners {
+            for lesson in &learner.lessons {
+                let fields = [
+                    learner.id.clone(),
+                    learner.display_name.clone(),
+                    lesson.lesson_id.clone(),
+                    lesson.title.clone(),
+                    lesson.completed.to_string(),
+                    format!("{:.2}", lesson.progress),
+                    lesson
+                        .best_quiz_score
+                        .map_or(String::new(), |score| format!("{:.2}", score)),
+                    lesson.quiz_attempts.to_string(),
+                    format!("{:.0}", lesson.time_spent_secs),
+                    lesson.hints_used.to_string(),
+                ];
+                let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// A single HTML page with inline styles and no external resources
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{0}</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2em; }}\n\
+             table {{ border-collapse: collapse; margin-bottom: 2em; }}\n\
+             th, td {{ border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }}\n\
+             td.done {{ background: #d8f5d0; }}\n\
+             .struggling {{ color: #b00020; }}\n\
+             </style>\n</head>\n<body>\n<h1>{0}</h1>\n<p>Generated {1}, {2} learners, {3} lessons</p>\n",
+            escape(&self.title),
+            crate::analytics::rfc3339(self.generated_at),
+            self.learners.len(),
+            self.lesson_count
+        );
+
+        html.push_str(
+            "<h2>Summary</h2>\n<table>\n<tr><th>Learner</th><th>Completed</th>\
+             <th>Average quiz</th><th>Time (min)</th><th>Hints</th><th>Struggling with</th></tr>\n",
+        );
+        for learner in &self.learners {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}/{}</td><td>{}</td><td>{:.0}</td><td>{}</td>\
+                 <td class=\"struggling\">{}</td></tr>",
+                escape(&learner.display_name),
+                learner.lessons_completed,
+                self.lesson_count,
+                learner
+                    .average_quiz_score
+                    .map_or("-".to_string(), |score| format!("{:.0}%", score * 100.0)),
+                learner.total_time_secs / 60.0,
+                learner.hints_used,
+                escape(&learner.struggling_topics.join(", "))
+            );
+        }
+        html.push_str("</table>\n");
+
+        if let Some(first) = self.learners.first() {
+            html.push_str("<h2>Progress by lesson</h2>\n<table>\n<tr><th>Learner</th>");
+            for lesson in &first.lessons {
+                let _ = write!(html, "<th>{}</th>", escape(&lesson.title));
+            }
+            html.push_str("</tr>\n");
+            for learner in &self.learners {
+                let _ = write!(html, "<tr><td>{}</td>", escape(&learner.display_name));
+                for lesson in &learner.lessons {
+                    let class = if lesson.completed {
+                        " class=\"done\""
+                    } else {
+                        ""
+                    };
+                    let _ = write!(html, "<td{}>{:.0}%</td>", class, lesson.progress * 100.0);
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Write `report.csv`, `report.json` and `report.html` into `directory`
+    pub fn write(&self, directory: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(directory)?;
+        fs::write(directory.join("report.csv"), self.to_csv())?;
+        fs::write(directory.join("report.json"), self.to_json()?)?;
+        fs::write(directory.join("report.html"), self.to_html())?;
+        Ok(())
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::education::EducationalContent;
+    use crate::hint::{Hint, HintTracker};
+    use crate::lesson_session::LessonSession;
+
+    fn catalog() -> LessonCatalog {
+        let lessons = ["basics", "ownership"]
+            .into_iter()
+            .map(|id| {
+                EducationalContent::new(id.to_string(), format!("Lesson {}", id), String::new())
+            })
+            .collect();
+        LessonCatalog::new(lessons).unwrap()
+    }
+
+    fn profiles() -> Vec<LearnerProfile> {
+        let mut sam = LearnerProfile::new("sam".to_string(), "Sam \"The Crab\", Jr".to_string());
+        let catalog = catalog();
+        let mut session = LessonSession::new(catalog.get("basics").unwrap());
+        session.update(120.0);
+        session.record_quiz_score(0.5);
+        sam.progress.record_session(&session, 0);
+        let mut hints = HintTracker::new();
+        hints.reveal("q1", &[Hint::new("a"), Hint::new("b")]);
+        hints.reveal("q1", &[Hint::new("a"), Hint::new("b")]);
+        sam.progress.record_hints("basics", &hints, 0);
+        sam.progress.mastery.observe("borrowing", 0.0).unwrap();
+        sam.progress.mastery.observe("ownership", 1.0).unwrap();
+
+        vec![
+            sam,
+            LearnerProfile::new("alex".to_string(), "Alex".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_learner_summary_aggregates_progress() {
+        let report = ClassReport::new("Class 5B", &catalog(), &profiles(), 0);
+        let sam = &report.learners[0];
+
+        assert_eq!(sam.lessons_completed, 1);
+        assert_eq!(sam.total_time_secs, 120.0);
+        assert_eq!(sam.average_quiz_score, Some(0.5));
+        assert_eq!(sam.hints_used, 2);
+        assert_eq!(sam.struggling_topics, vec!["borrowing"]);
+        assert_eq!(sam.lessons[0].hints_used, 2);
+        assert_eq!(report.learners[1].lessons_completed, 0);
+    }
+
+    #[test]
+    fn test_csv_rows_and_quoting() {
+        let csv = ClassReport::new("Class 5B", &catalog(), &profiles(), 0).to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 1 + 2 * 2);
+        assert!(lines[0].starts_with("learner_id,learner_name,lesson_id"));
+        assert!(lines[1].starts_with("sam,\"Sam \"\"The Crab\"\", Jr\",basics,"));
+        assert!(lines[1].contains(",true,1.00,0.50,1,120,2"));
+    }
+
+    #[test]
+    fn test_json_and_html_outputs() {
+        let report = ClassReport::new("Class <5B>", &catalog(), &profiles(), 0);
+        let parsed: ClassReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, report);
+
+        let html = report.to_html();
+        assert!(html.contains("<h1>Class &lt;5B&gt;</h1>"));
+        assert!(html.contains("<td class=\"done\">100%</td>"));
+        assert!(!html.contains("<link") && !html.contains("<script"));
+    }
+
+    #[test]
+    fn test_report_from_a_played_session() {
+        use crate::education::{ElementPayload, InteractiveElement};
+        use crate::quiz::{Question, QuestionKind, Quiz};
+        use crate::scene::{GameContent, Overworld, SceneStack};
+        use crate::window::InputEvent;
+        use std::rc::Rc;
+
+        let mut basics =
+            EducationalContent::new("basics".to_string(), "Basics".to_string(), String::new());
+        basics.add_interactive_element(InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Quiz(Quiz {
+                id: "borrowing".to_string(),
+                title: "Borrowing".to_string(),
+                questions: vec![Question {
+                    id: "shared".to_string(),
+                    prompt: "Can two shared borrows coexist?".to_string(),
+                    kind: QuestionKind::TrueFalse { answer: true },
+                    weight: 1.0,
+                    topics: vec!["borrowing".to_string()],
+                    difficulty: 0.5,
+                    hints: vec![Hint::new("Reading never conflicts")],
+                }],
+            }),
+        ));
+        let content = GameContent {
+            lessons: LessonCatalog::new(vec![basics.clone()]).unwrap(),
+            default_lesson: "basics".to_string(),
+            ..GameContent::default()
+        };
+
+        let mut sam = LearnerProfile::new("sam".to_string(), "Sam".to_string());
+        let mut state = crate::state::AppState::default();
+        sam.apply_to(&mut state);
+        let overworld = Overworld::default().with_content(Rc::new(content));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+        let press = |key: &str| InputEvent::KeyPress {
+            key: key.to_string(),
+        };
+        stack.handle_input(&mut state, &press("l"));
+        stack.update(&mut state, 90.0);
+        for key in ["q", "F1", "f", "Enter", "Enter", "Escape"] {
+            stack.handle_input(&mut state, &press(key));
+        }
+        sam.progress.capture(&state, 0);
+
+        let catalog = LessonCatalog::new(vec![basics]).unwrap();
+        let report = ClassReport::new("Class 5B", &catalog, &[sam], 0);
+        let summary = &report.learners[0];
+        assert_eq!(summary.total_time_secs, 90.0);
+        assert_eq!(summary.average_quiz_score, Some(0.0));
+        assert_eq!(summary.hints_used, 1);
+        assert_eq!(summary.struggling_topics, vec!["borrowing"]);
+        assert_eq!(summary.lessons[0].quiz_attempts, 1);
+    }
+}
@@ -0,0 +1,456 @@
+//! Spaced repetition module for Learn Liberty
+//!
+//! This module schedules reviews of quiz questions a learner has already
+//! seen, using the SM-2 algorithm. Each answer is graded from its credit
+//! and how long the learner took, which moves the question's next review
+//! further out or brings it back tomorrow. Days are plain numbers passed
+//! in by the caller so schedules can be replayed deterministically; the
+//! game counts them in the learner's local time so a new day starts at
+//! their midnight.
+
+use crate::profile::unix_timestamp;
+use crate::quiz::{Quiz, QuizResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Answers at or under this many seconds count as confident recall
+pub const CONFIDENT_RESPONSE_SECS: f32 = 10.0;
+
+const MIN_EASINESS: f32 = 1.3;
+const INITIAL_EASINESS: f32 = 2.5;
+
+/// Today as whole local days since the Unix epoch
+pub fn today() -> u32 {
+    local_day(unix_timestamp())
+}
+
+/// Whole local days since the Unix epoch at `timestamp`
+pub fn local_day(timestamp: u64) -> u32 {
+    let local = timestamp as i64 + local_utc_offset(timestamp);
+    (local.max(0) / 86_400) as u32
+}
+
+/// Seconds east of UTC in the local time zone at `timestamp`, read from
+/// the zone file named by `TZ` or from `/etc/localtime`. The file is read
+/// once per run. Unknown zones count as UTC, and daylight saving rules
+/// past the file's last listed transition are not applied.
+pub fn local_utc_offset(timestamp: u64) -> i64 {
+    static ZONE: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+    let zone = ZONE.get_or_init(|| {
+        let path = match std::env::var("TZ") {
+            Ok(zone) if !zone.is_empty() => {
+                let zone = zone.trim_start_matches(':');
+                if zone.starts_with('/') {
+                    PathBuf::from(zone)
+                } else {
+                    Path::new("/usr/share/zoneinfo").join(zone)
+                }
+            }
+            _ => PathBuf::from("/etc/localtime"),
+        };
+        std::fs::read(path).ok()
+    });
+    zone.as_deref()
+        .and_then(|bytes| tzif_offset(bytes, timestamp as i64))
+        .unwrap_or(0)
+}
+
+/// The UTC offset a TZif zone file gives for `timestamp`
+fn tzif_offset(bytes: &[u8], timestamp: i64) -> Option<i64> {
+    let be_u32 = |at: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+    };
+    // isutcnt, isstdcnt, leapcnt, timecnt, typecnt, charcnt
+    let header = |at: usize| -> Option<[usize; 6]> {
+        if bytes.get(at..at + 4)? != b"TZif" {
+            return None;
+        }
+        let mut counts = [0; 6];
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count = be_u32(at + 20 + i * 4)? as usize;
+        }
+        Some(counts)
+    };
+
+    let mut counts = header(0)?;
+    let (mut data, mut time_size) = (44, 4);
+    if bytes[4] >= b'2' {
+        // Version 2 files repeat the data with 64-bit times after the
+        // 32-bit block
+        let [isut, isstd, leap, times, types, chars] = counts;
+        let next = data + times * 5 + types * 6 + chars + leap * 8 + isstd + isut;
+        counts = header(next)?;
+        data = next + 44;
+        time_size = 8;
+    }
+
+    let [_, _, _, times, types, _] = counts;
+    let indices = data + times * time_size;
+    let mut current = 0;
+    for i in 0..times {
+        let raw = bytes.get(data + i * time_size..data + (i + 1) * time_size)?;
+        let at = match time_size {
+            8 => i64::from_be_bytes(raw.try_into().ok()?),
+            _ => i32::from_be_bytes(raw.try_into().ok()?) as i64,
+        };
+        if at > timestamp {
+            break;
+        }
+        current = *bytes.get(indices + i)? as usize;
+    }
+    if current >= types {
+        return None;
+    }
+    Some(be_u32(indices + times + current * 6)? as i32 as i64)
+}
+
+/// Grade an answer on the SM-2 scale of 0 to 5. Anything below 3 is a
+/// lapse and restarts the question's schedule.
+pub fn grade(credit: f32, response_secs: f32) -> u8 {
+    if credit >= 1.0 {
+        if response_secs <= CONFIDENT_RESPONSE_SECS {
+            5
+        } else if response_secs <= CONFIDENT_RESPONSE_SECS * 3.0 {
+            4
+        } else {
+            3
+        }
+    } else if credit >= 0.5 {
+        2
+    } else if credit > 0.0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    pub quiz_id: String,
+    pub question_id: String,
+    pub easiness: f32,
+    /// Days until the next review after the last one
+    pub interval: u32,
+    pub repetitions: u32,
+    pub lapses: u32,
+    pub due: u32,
+    pub last_reviewed: u32,
+}
+
+impl ReviewCard {
+    pub fn new(quiz_id: String, question_id: String, today: u32) -> Self {
+        Self {
+            quiz_id,
+            question_id,
+            easiness: INITIAL_EASINESS,
+            interval: 0,
+            repetitions: 0,
+            lapses: 0,
+            due: today,
+            last_reviewed: today,
+        }
+    }
+
+    pub fn is_due(&self, today: u32) -> bool {
+        self.due <= today
+    }
+
+    /// Apply one graded review
+    pub fn review(&mut self, quality: u8, today: u32) {
+        let quality = quality.min(5);
+        if quality >= 3 {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f32 * self.easiness).round() as u32,
+            };
+            self.repetitions += 1;
+        } else {
+            self.interval = 1;
+            self.repetitions = 0;
+            self.lapses += 1;
+        }
+
+        let miss = (5 - quality) as f32;
+        self.easiness = (self.easiness + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASINESS);
+        self.last_reviewed = today;
+        self.due = today + self.interval;
+    }
+}
+
+/// Every question one learner has seen, keyed by `quiz_id/question_id`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReviewScheduler {
+    pub cards: BTreeMap<String, ReviewCard>,
+}
+
+impl ReviewScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(quiz_id: &str, question_id: &str) -> String {
+        format!("{}/{}", quiz_id, question_id)
+    }
+
+    pub fn card(&self, quiz_id: &str, question_id: &str) -> Option<&ReviewCard> {
+        self.cards.get(&Self::key(quiz_id, question_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Record one answer, adding the question to the schedule the first
+    /// time it is seen
+    pub fn record(
+        &mut self,
+        quiz_id: &str,
+        question_id: &str,
+        credit: f32,
+        response_secs: f32,
+        today: u32,
+    ) {
+        self.cards
+            .entry(Self::key(quiz_id, question_id))
+            .or_insert_with(|| ReviewCard::new(quiz_id.to_string(), question_id.to_string(), today))
+            .review(grade(credit, response_secs), today);
+    }
+
+    /// Record a scored quiz. Response times are looked up by question id;
+    /// questions without one are treated as answered at a normal pace.
+    pub fn record_result(
+        &mut self,
+        result: &QuizResult,
+        response_secs: &BTreeMap<String, f32>,
+        today: u32,
+    ) {
+        for question in &result.questions {
+            let secs = response_secs
+                .get(&question.question_id)
+                .copied()
+                .unwrap_or(CONFIDENT_RESPONSE_SECS);
+            self.record(
+                &result.quiz_id,
+                &question.question_id,
+                question.credit,
+                secs,
+                today,
+            );
+        }
+    }
+
+    /// Cards due today, most overdue first and hardest first among equals
+    pub fn due(&self, today: u32) -> Vec<&ReviewCard> {
+        let mut due: Vec<&ReviewCard> = self
+            .cards
+            .values()
+            .filter(|card| card.is_due(today))
+            .collect();
+        due.sort_by(|a, b| {
+            a.due
+                .cmp(&b.due)
+                .then_with(|| a.easiness.total_cmp(&b.easiness))
+        });
+        due
+    }
+
+    /// Build today's review challenge from the due questions found in
+    /// `quizzes`, or `None` when nothing is due
+    pub fn daily_review(&self, today: u32, limit: usize, quizzes: &[Quiz]) -> Option<Quiz> {
+        let questions: Vec<_> = self
+            .due(today)
+            .into_iter()
+            .filter_map(|card| {
+                quizzes
+                    .iter()
+                    .find(|quiz| quiz.id == card.quiz_id)?
+                    .questions
+                    .iter()
+                    .find(|question| question.id == card.question_id)
+                    .map(|question| {
+                        let mut question = question.clone();
+                        question.id = Self::key(&card.quiz_id, &card.question_id);
+                        question
+                    })
+            })
+            .take(limit)
+            .collect();
+
+        if questions.is_empty() {
+            return None;
+        }
+        Some(Quiz {
+            id: format!("review_{}", today),
+            title: "Daily Review".to_string(),
+            questions,
+        })
+    }
+
+    /// Record a scored daily review built by `daily_review`
+    pub fn record_review(
+        &mut self,
+        result: &QuizResult,
+        response_secs: &BTreeMap<String, f32>,
+        today: u32,
+    ) {
+        for question in &result.questions {
+            if let Some(card) = self.cards.get_mut(&question.question_id) {
+                let secs = response_secs
+                    .get(&question.question_id)
+                    .copied()
+                    .unwrap_or(CONFIDENT_RESPONSE_SECS);
+                card.review(grade(question.credit, secs), today);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Answer, Question, QuestionKind};
+    use std::collections::HashMap;
+
+    fn quiz() -> Quiz {
+        Quiz {
+            id: "ownership".to_string(),
+            title: "Ownership".to_string(),
+            questions: ["moves", "borrows"]
+                .into_iter()
+                .map(|id| Question {
+                    id: id.to_string(),
+                    prompt: format!("Does Rust have {}?", id),
+                    kind: QuestionKind::TrueFalse { answer: true },
+                    weight: 1.0,
+                    topics: Vec::new(),
+                    difficulty: 0.5,
+                    hints: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// A zone file with one transition at `at` from UTC to `offset`
+    fn tzif(version: u8, at: i64, offset: i32) -> Vec<u8> {
+        let block = |time: &[u8], offset: i32| {
+            let mut bytes = b"TZif".to_vec();
+            bytes.push(version);
+            bytes.extend([0; 15]);
+            for count in [0u32, 0, 0, 1, 2, 4] {
+                bytes.extend(count.to_be_bytes());
+            }
+            bytes.extend(time);
+            bytes.push(1);
+            bytes.extend(0i32.to_be_bytes());
+            bytes.extend([0, 0]);
+            bytes.extend(offset.to_be_bytes());
+            bytes.extend([1, 0]);
+            bytes.extend(b"UTC\0");
+            bytes
+        };
+        let mut bytes = block(&(at as i32).to_be_bytes(), offset);
+        if version >= b'2' {
+            // Only the 64-bit block should be read from a version 2 file
+            bytes[44 + 4 + 1 + 6..44 + 4 + 1 + 10].copy_from_slice(&0i32.to_be_bytes());
+            bytes.extend(block(&at.to_be_bytes(), offset));
+            bytes.extend(b"\nUTC0\n");
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_zone_file_offsets() {
+        for version in [0, b'2'] {
+            let zone = tzif(version, 1_000, 3_600);
+            assert_eq!(tzif_offset(&zone, 999), Some(0));
+            assert_eq!(tzif_offset(&zone, 1_000), Some(3_600));
+            assert_eq!(tzif_offset(&zone, 5_000_000_000), Some(3_600));
+        }
+        assert_eq!(tzif_offset(b"not a zone file", 0), None);
+        assert_eq!(tzif_offset(&tzif(b'2', 0, 0)[..60], 0), None);
+    }
+
+    #[test]
+    fn test_grade_uses_credit_and_time() {
+        assert_eq!(grade(1.0, 4.0), 5);
+        assert_eq!(grade(1.0, 20.0), 4);
+        assert_eq!(grade(1.0, 90.0), 3);
+        assert_eq!(grade(0.5, 4.0), 2);
+        assert_eq!(grade(0.0, 4.0), 0);
+    }
+
+    #[test]
+    fn test_intervals_grow_and_lapses_reset() {
+        let mut card = ReviewCard::new("q".to_string(), "a".to_string(), 0);
+
+        card.review(5, 0);
+        assert_eq!((card.interval, card.due), (1, 1));
+        card.review(5, 1);
+        assert_eq!((card.interval, card.due), (6, 7));
+        card.review(4, 7);
+        assert_eq!(card.interval, 16);
+        assert!(card.easiness > INITIAL_EASINESS);
+
+        card.review(1, 24);
+        assert_eq!((card.interval, card.repetitions, card.lapses), (1, 0, 1));
+        assert_eq!(card.due, 25);
+        for day in 25..40 {
+            card.review(0, day);
+        }
+        assert_eq!(card.easiness, MIN_EASINESS);
+    }
+
+    #[test]
+    fn test_simulated_weeks_of_reviews() {
+        let quizzes = vec![quiz()];
+        let quiz = &quizzes[0];
+        let mut scheduler = ReviewScheduler::new();
+        let start = 20_000;
+
+        let mut answers = HashMap::new();
+        answers.insert("moves".to_string(), Answer::Bool(true));
+        answers.insert("borrows".to_string(), Answer::Bool(false));
+        scheduler.record_result(&quiz.score(&answers), &BTreeMap::new(), start);
+        assert_eq!(scheduler.len(), 2);
+
+        let mut reviews = Vec::new();
+        for day in start + 1..start + 28 {
+            if let Some(review) = scheduler.daily_review(day, 10, &quizzes) {
+                let answers = review
+                    .questions
+                    .iter()
+                    .map(|question| (question.id.clone(), Answer::Bool(true)))
+                    .collect();
+                scheduler.record_review(&review.score(&answers), &BTreeMap::new(), day);
+                reviews.push((day - start, review.questions.len()));
+            }
+        }
+
+        assert_eq!(
+            reviews,
+            vec![(1, 2), (2, 1), (7, 1), (8, 1), (19, 1), (23, 1)]
+        );
+        assert!(scheduler.due(start + 23).is_empty());
+    }
+
+    #[test]
+    fn test_daily_review_limit_and_missing_quiz() {
+        let mut scheduler = ReviewScheduler::new();
+        scheduler.record("ownership", "moves", 0.0, 5.0, 10);
+        scheduler.record("ownership", "borrows", 0.0, 5.0, 10);
+        scheduler.record("gone", "question", 0.0, 5.0, 10);
+
+        assert_eq!(scheduler.due(11).len(), 3);
+        let review = scheduler.daily_review(11, 1, &[quiz()]).unwrap();
+        assert_eq!(review.id, "review_11");
+        assert_eq!(review.questions.len(), 1);
+        assert!(scheduler.daily_review(10, 5, &[quiz()]).is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! Save game module for Learn Liberty
+//!
+//! This module stores learner progress in numbered save slots. Each file
+//! is an envelope holding a format version, a CRC-32 checksum and the save
+//! data itself. Files are written to a temporary path and renamed into
+//! place so a crash mid-write never leaves a half-written slot behind, and
+//! older formats are migrated forward when loaded. Version 1 is the first
+//! format, so there are no migrations yet; each later version adds one.
+
+use crate::config::Settings;
+use crate::inventory::{Inventory, WorldPickup};
+use crate::quest::QuestLog;
+use crate::review::ReviewScheduler;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Version written by this build
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// Migrates a save from version `index + 1` to `index + 2`
+type Migration = fn(Value) -> anyhow::Result<Value>;
+
+const MIGRATIONS: &[Migration] = &[];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveData {
+    pub player_position: (f32, f32),
+    pub inventory: Inventory,
+    #[serde(default = "WorldPickup::town")]
+    pub pickups: Vec<WorldPickup>,
+    pub quests: QuestLog,
+    #[serde(default)]
+    pub experience: u32,
+    #[serde(default)]
+    pub unlocked_lessons: Vec<String>,
+    #[serde(default)]
+    pub reviews: ReviewScheduler,
+    pub current_lesson_id: String,
+    pub lesson_progress: f32,
+    pub completed_lessons: Vec<String>,
+    pub play_time: f64,
+    pub settings: Settings,
+}
+
+impl SaveData {
+    /// Capture the progress held in the app state
+    pub fn capture(state: &AppState, settings: &Settings) -> Self {
+        Self {
+            player_position: state.player_position,
+            inventory: state.inventory.clone(),
+            pickups: state.pickups.clone(),
+            quests: state.quests.clone(),
+            experience: state.experience,
+            unlocked_lessons: state.unlocked_lessons.clone(),
+            reviews: state.reviews.clone(),
+            current_lesson_id: state.current_lesson_id.clone(),
+            lesson_progress: state.lesson_progress,
+            completed_lessons: state.completed_lessons.clone(),
+            play_time: state.time,
+            settings: settings.clone(),
+        }
+    }
+
+    /// Restore the progress held in the app state
+    pub fn apply_to(&self, state: &mut AppState) {
+        state.advance_lesson(self.current_lesson_id.clone(), self.lesson_progress);
+        state.completed_lessons = self.completed_lessons.clone();
+        state.time = self.play_time;
+        state.player_position = self.player_position;
+        state.inventory = self.inventory.clone();
+        state.pickups = self.pickups.clone();
+        state.quests = self.quests.clone();
+        state.experience = self.experience;
+        state.unlocked_lessons = self.unlocked_lessons.clone();
+        state.reviews = self.reviews.clone();
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveEnvelope {
+    version: u32,
+    checksum: u32,
+    data: Value,
+}
+
+/// CRC-32 (IEEE) of the given bytes
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn checksum(data: &Value) -> anyhow::Result<u32> {
+    Ok(crc32(serde_json::to_string(data)?.as_bytes()))
+}
+
+/// Bring save data written by any older version up to the current format
+pub fn migrate(version: u32, data: Value) -> anyhow::Result<Value> {
+    migrate_with(MIGRATIONS, version, data)
+}
+
+fn migrate_with(migrations: &[Migration], version: u32, mut data: Value) -> anyhow::Result<Value> {
+    let current = migrations.len() as u32 + 1;
+    if version == 0 || version > current {
+        anyhow::bail!("Unsupported save format version {}", version);
+    }
+    for migration in &migrations[(version - 1) as usize..] {
+        data = migration(data)?;
+    }
+    Ok(data)
+}
+
+pub fn encode(save: &SaveData) -> anyhow::Result<String> {
+    let data = serde_json::to_value(save)?;
+    let envelope = SaveEnvelope {
+        version: SAVE_FORMAT_VERSION,
+        checksum: checksum(&data)?,
+        data,
+    };
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+pub fn decode(contents: &str) -> anyhow::Result<SaveData> {
+    let envelope: SaveEnvelope = serde_json::from_str(contents)?;
+    if checksum(&envelope.data)? != envelope.checksum {
+        anyhow::bail!("Save data is corrupted: checksum mismatch");
+    }
+    let data = migrate(envelope.version, envelope.data)?;
+    Ok(serde_json::from_value(data)?)
+}
+
+/// Reads and writes numbered save slots inside a directory
+pub struct SaveManager {
+    directory: PathBuf,
+}
+
+impl SaveManager {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn slot_path(&self, slot: u32) -> PathBuf {
+        self.directory.join(format!("slot_{}.json", slot))
+    }
+
+    pub fn save(&self, slot: u32, save: &SaveData) -> anyhow::Result<()> {
+        write_atomic(&self.slot_path(slot), &encode(save)?)
+    }
+
+    pub fn load(&self, slot: u32) -> anyhow::Result<SaveData> {
+        let contents = fs::read_to_string(self.slot_path(slot))?;
+        decode(&contents)
+    }
+
+    pub fn delete(&self, slot: u32) -> anyhow::Result<()> {
+        fs::remove_file(self.slot_path(slot))?;
+        Ok(())
+    }
+
+    /// Slots that currently hold a save, in ascending order
+    pub fn slots(&self) -> anyhow::Result<Vec<u32>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut slots: Vec<u32> = fs::read_dir(&self.directory)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("slot_")?
+                    .strip_suffix(".json")?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+        slots.sort_unstable();
+        Ok(slots)
+    }
+}
+
+/// Write a file by writing a sibling temporary file and renaming it over
+/// the destination
+pub fn write_atomic(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let mut file = fs::File::create(&temporary)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ItemDatabase;
+    use crate::quest::{QuestState, QuestTrigger};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "learn_liberty_save_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn sample_save() -> SaveData {
+        let mut state = AppState::default();
+        state.advance_lesson("lesson_2".to_string(), 0.4);
+        state.complete_lesson("lesson_1");
+        state.update(12.5);
+        SaveData::capture(&state, &Settings::default())
+    }
+
+    #[test]
+    fn test_crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_save_and_load_slots() {
+        let manager = SaveManager::new(scratch_dir("slots"));
+        let mut save = sample_save();
+        save.player_position = (48.0, 96.0);
+
+        manager.save(1, &save).unwrap();
+        manager.save(3, &save).unwrap();
+
+        assert_eq!(manager.slots().unwrap(), vec![1, 3]);
+        let loaded = manager.load(3).unwrap();
+        assert_eq!(loaded.player_position, (48.0, 96.0));
+        assert_eq!(loaded.completed_lessons, vec!["lesson_1"]);
+
+        let mut state = AppState::default();
+        loaded.apply_to(&mut state);
+        assert_eq!(state.current_lesson_id, "lesson_2");
+        assert_eq!(state.time, 12.5);
+
+        manager.delete(1).unwrap();
+        assert_eq!(manager.slots().unwrap(), vec![3]);
+        let _ = fs::remove_dir_all(manager.directory());
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let encoded = encode(&sample_save()).unwrap();
+        let tampered = encoded.replace("lesson_2", "lesson_9");
+
+        assert!(decode(&encoded).is_ok());
+        assert!(decode(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_position_inventory_and_quests_round_trip() {
+        let items = ItemDatabase::load("assets/data/items.toml").unwrap();
+        let mut state = AppState {
+            quests: QuestLog::load("assets/data/quests.toml").unwrap(),
+            player_position: (272.0, -32.0),
+            ..AppState::default()
+        };
+        state.inventory.add(&items, "crab_coin", 7).unwrap();
+        state.trigger(QuestTrigger::EnteredZone("library".to_string()));
+
+        let save =
+            decode(&encode(&SaveData::capture(&state, &Settings::default())).unwrap()).unwrap();
+        let mut restored = AppState::default();
+        save.apply_to(&mut restored);
+
+        assert_eq!(restored.player_position, (272.0, -32.0));
+        assert_eq!(restored.inventory, state.inventory);
+        assert_eq!(restored.inventory.count("crab_coin"), 7);
+        let quest = restored.quests.quest("explore_liberty").unwrap();
+        assert_eq!(quest.state, QuestState::Active);
+        assert!(quest.objectives[0].completed);
+        assert_eq!(restored.quests.quests.len(), state.quests.quests.len());
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        fn add_inventory(mut data: Value) -> anyhow::Result<Value> {
+            data["inventory"] = Value::from("empty");
+            Ok(data)
+        }
+        fn rename_time(mut data: Value) -> anyhow::Result<Value> {
+            let time = data["time"].take();
+            data["play_time"] = time;
+            Ok(data)
+        }
+        let migrations: &[Migration] = &[add_inventory, rename_time];
+        let data = serde_json::json!({ "time": 2.0 });
+
+        let migrated = migrate_with(migrations, 1, data.clone()).unwrap();
+        assert_eq!(migrated["inventory"], "empty");
+        assert_eq!(migrated["play_time"], 2.0);
+        let from_v2 = migrate_with(migrations, 2, data.clone()).unwrap();
+        assert!(from_v2.get("inventory").is_none());
+        assert!(migrate_with(migrations, 4, data.clone()).is_err());
+        assert!(migrate(0, data.clone()).is_err());
+        assert!(migrate(SAVE_FORMAT_VERSION + 1, data).is_err());
+    }
+}
@@ -0,0 +1,1907 @@
+//! Scene management module for Learn Liberty
+//!
+//! This module provides a scene stack so that the title screen, the
+//! overworld, lesson and quiz views and the pause menu each own their
+//! state and hooks. Input is routed to the top scene only, while overlay
+//! scenes let the scenes beneath them keep rendering. Scenes draw their
+//! menus and panels with the UI toolkit.
+
+use crate::action::ButtonAction;
+use crate::analytics::LearningEvent;
+use crate::education::{EducationalContent, ElementPayload};
+use crate::exercise::{ExerciseCheck, ExerciseRunner, RunOutcome};
+use crate::graphics::{DrawCommand, GraphicsEngine};
+use crate::hint::{Hint, HintTracker};
+use crate::inventory::ItemDatabase;
+use crate::layout::{ElementLayout, Layout};
+use crate::lesson_catalog::LessonCatalog;
+use crate::lesson_runtime::LessonRuntime;
+use crate::profile::{unix_timestamp, LearnerProfile};
+use crate::quest::QuestTrigger;
+use crate::quiz::{Answer, QuestionKind, Quiz, QuizResult};
+use crate::review;
+use crate::state::AppState;
+use crate::ui::{
+    lesson_panel, ExerciseView, LayoutTree, Rect, Ui, UiState, CHAR_WIDTH, FONT_SIZE, PADDING,
+    PROGRESS_HEIGHT, SPACING, TITLE_HEIGHT,
+};
+use crate::window::InputEvent;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// What the scene stack should do after a scene handled an update or input
+pub enum SceneTransition {
+    None,
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+    Quit,
+}
+
+pub trait Scene {
+    fn name(&self) -> &str;
+
+    /// Overlay scenes are drawn on top of the scene below them
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    fn enter(&mut self, _state: &mut AppState) {}
+
+    fn exit(&mut self, _state: &mut AppState) {}
+
+    fn update(&mut self, _state: &mut AppState, _delta_time: f64) -> SceneTransition {
+        SceneTransition::None
+    }
+
+    fn handle_input(&mut self, _state: &mut AppState, _input: &InputEvent) -> SceneTransition {
+        SceneTransition::None
+    }
+
+    fn render(&self, _state: &AppState, _graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    quit_requested: bool,
+}
+
+impl SceneStack {
+    pub fn new(initial: Box<dyn Scene>, state: &mut AppState) -> Self {
+        let mut stack = Self::default();
+        stack.apply(SceneTransition::Push(initial), state);
+        stack
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn top(&self) -> Option<&dyn Scene> {
+        self.scenes.last().map(|scene| scene.as_ref())
+    }
+
+    pub fn scene_names(&self) -> Vec<&str> {
+        self.scenes.iter().map(|scene| scene.name()).collect()
+    }
+
+    /// True once a scene asked to quit or the last scene was popped
+    pub fn should_quit(&self) -> bool {
+        self.quit_requested || self.scenes.is_empty()
+    }
+
+    pub fn update(&mut self, state: &mut AppState, delta_time: f64) {
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.update(state, delta_time);
+            self.apply(transition, state);
+        }
+    }
+
+    /// Route input to the top scene. Resizes are also recorded in the
+    /// state so scenes beneath it lay out for the new size once shown, and
+    /// closing the window quits like the pause menu does.
+    pub fn handle_input(&mut self, state: &mut AppState, input: &InputEvent) {
+        match input {
+            InputEvent::Resize { width, height } => state.window_size = (*width, *height),
+            InputEvent::CloseRequested => {
+                self.quit_requested = true;
+                return;
+            }
+            _ => {}
+        }
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.handle_input(state, input);
+            self.apply(transition, state);
+        }
+    }
+
+    /// Render the top scene and every scene visible beneath its overlays
+    pub fn render(&self, state: &AppState, graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        for scene in &self.scenes[self.first_visible()..] {
+            scene.render(state, graphics)?;
+        }
+        Ok(())
+    }
+
+    pub fn visible_scene_names(&self) -> Vec<&str> {
+        self.scenes[self.first_visible()..]
+            .iter()
+            .map(|scene| scene.name())
+            .collect()
+    }
+
+    pub fn apply(&mut self, transition: SceneTransition, state: &mut AppState) {
+        match transition {
+            SceneTransition::None => {}
+            SceneTransition::Push(mut scene) => {
+                scene.enter(state);
+                self.scenes.push(scene);
+            }
+            SceneTransition::Pop => {
+                if let Some(mut scene) = self.scenes.pop() {
+                    scene.exit(state);
+                }
+            }
+            SceneTransition::Replace(scene) => {
+                self.apply(SceneTransition::Pop, state);
+                self.apply(SceneTransition::Push(scene), state);
+            }
+            SceneTransition::Quit => self.quit_requested = true,
+        }
+    }
+
+    /// Pop every scene so each one can hand its state over before the game
+    /// quits
+    pub fn exit_all(&mut self, state: &mut AppState) {
+        while !self.scenes.is_empty() {
+            self.apply(SceneTransition::Pop, state);
+        }
+    }
+
+    fn first_visible(&self) -> usize {
+        self.scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0)
+    }
+}
+
+fn key_of(input: &InputEvent) -> Option<&str> {
+    match input {
+        InputEvent::KeyPress { key } => Some(key.as_str()),
+        _ => None,
+    }
+}
+
+fn window_size(state: &AppState) -> (f32, f32) {
+    (state.window_size.0 as f32, state.window_size.1 as f32)
+}
+
+/// Draw a panel that keeps no UI state between frames
+fn draw_panel(
+    state: &AppState,
+    graphics: &mut GraphicsEngine,
+    id: &str,
+    title: &str,
+    body: impl FnOnce(&mut Ui),
+) {
+    let (width, height) = window_size(state);
+    let mut panel = UiState::new();
+    let mut ui = panel.frame(width, height);
+    ui.panel(id, title, body);
+    ui.finish().render(graphics);
+}
+
+/// Draw a notification, such as an unlocked achievement, in the top right
+/// corner over every scene
+pub fn draw_toast(state: &AppState, graphics: &mut GraphicsEngine, message: &str) {
+    let (width, _) = window_size(state);
+    let toast_width = (message.chars().count() as f32 * CHAR_WIDTH + 2.0 * PADDING).min(width);
+    let x = (width - toast_width - PADDING).max(0.0);
+    graphics.draw(DrawCommand::Quad {
+        x,
+        y: PADDING,
+        width: toast_width,
+        height: FONT_SIZE + 2.0 * PADDING,
+        color: TOAST_COLOR,
+    });
+    graphics.draw(DrawCommand::Text {
+        x: x + PADDING,
+        y: 2.0 * PADDING,
+        size: FONT_SIZE,
+        text: message.to_string(),
+        color: [1.0; 4],
+    });
+}
+
+const TOAST_COLOR: [f32; 4] = [0.15, 0.2, 0.3, 0.9];
+
+/// Lessons and items the scenes open and hand out, loaded once at startup
+#[derive(Debug, Default)]
+pub struct GameContent {
+    pub lessons: LessonCatalog,
+    pub items: ItemDatabase,
+    /// Opened from the overworld when the learner has no lesson in progress
+    pub default_lesson: String,
+}
+
+impl GameContent {
+    /// Every quiz in every lesson, which daily reviews draw from
+    pub fn quizzes(&self) -> Vec<Quiz> {
+        self.lessons
+            .iter()
+            .flat_map(|lesson| &lesson.interactive_elements)
+            .filter_map(|element| match &element.payload {
+                ElementPayload::Quiz(quiz) => Some(quiz.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The first scene, where a learner picks their profile before playing
+#[derive(Default)]
+pub struct TitleScreen {
+    pub profiles: Vec<LearnerProfile>,
+    pub selected: usize,
+    /// Handed on to the overworld once a profile is picked
+    pub content: Rc<GameContent>,
+}
+
+impl TitleScreen {
+    pub fn with_profiles(profiles: Vec<LearnerProfile>) -> Self {
+        Self {
+            profiles,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_content(mut self, content: Rc<GameContent>) -> Self {
+        self.content = content;
+        self
+    }
+
+    pub fn selected_profile(&self) -> Option<&LearnerProfile> {
+        self.profiles.get(self.selected)
+    }
+
+    /// The profile menu for a window of the given size. It holds no state
+    /// of its own, so rendering and click handling rebuild it alike.
+    pub fn menu(&self, width: f32, height: f32) -> LayoutTree {
+        let mut menu = UiState::new();
+        if let Some(profile) = self.selected_profile() {
+            menu.focus(&profile_button(&profile.id));
+        }
+        let mut ui = menu.frame(width, height);
+        ui.panel("title", "Learn Liberty", |ui| {
+            if self.profiles.is_empty() {
+                ui.label("Press Enter to start");
+            } else {
+                ui.label("Choose your profile");
+                for profile in &self.profiles {
+                    ui.button(&profile_button(&profile.id), &profile.display_name);
+                }
+            }
+        });
+        ui.finish()
+    }
+
+    fn start(&self, state: &mut AppState) -> SceneTransition {
+        if let Some(profile) = self.selected_profile() {
+            profile.apply_to(state);
+        }
+        let overworld = Overworld::default().with_content(Rc::clone(&self.content));
+        SceneTransition::Replace(Box::new(overworld))
+    }
+}
+
+fn profile_button(profile_id: &str) -> String {
+    format!("profile/{}", profile_id)
+}
+
+impl Scene for TitleScreen {
+    fn name(&self) -> &str {
+        "TitleScreen"
+    }
+
+    fn handle_input(&mut self, state: &mut AppState, input: &InputEvent) -> SceneTransition {
+        if let InputEvent::MouseClick { x, y } = input {
+            let (width, height) = window_size(state);
+            let menu = self.menu(width, height);
+            let clicked = menu.hit_test(*x, *y).map(|node| node.id.as_str());
+            if let Some(index) = self
+                .profiles
+                .iter()
+                .position(|profile| clicked == Some(profile_button(&profile.id).as_str()))
+            {
+                self.selected = index;
+                return self.start(state);
+            }
+        }
+
+        let count = self.profiles.len();
+        match key_of(input) {
+            Some("ArrowUp") if count > 0 => self.selected = (self.selected + count - 1) % count,
+            Some("ArrowDown") if count > 0 => self.selected = (self.selected + 1) % count,
+            Some("Enter") => return self.start(state),
+            Some("Escape") => return SceneTransition::Quit,
+            _ => {}
+        }
+        SceneTransition::None
+    }
+
+    fn render(&self, state: &AppState, graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        let (width, height) = window_size(state);
+        self.menu(width, height).render(graphics);
+        Ok(())
+    }
+}
+
+/// A named area of the world; walking into one reports `EnteredZone`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub id: String,
+    pub bounds: Rect,
+}
+
+impl Region {
+    pub fn new(id: &str, bounds: Rect) -> Self {
+        Self {
+            id: id.to_string(),
+            bounds,
+        }
+    }
+
+    /// The town the player explores: the village around the starting
+    /// point with the library, harbor and forge in the other quarters
+    pub fn town() -> Vec<Region> {
+        let size = Overworld::REGION_SIZE;
+        vec![
+            Region::new("village", Rect::new(-size, -size, size * 2.0, size * 2.0)),
+            Region::new("library", Rect::new(size, -size, size * 2.0, size * 2.0)),
+            Region::new("harbor", Rect::new(-size, size, size * 2.0, size * 2.0)),
+            Region::new("forge", Rect::new(size, size, size * 2.0, size * 2.0)),
+        ]
+    }
+}
+
+/// A townsperson the player can talk to; talking reports `TalkedTo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Npc {
+    pub id: String,
+    pub name: String,
+    pub position: (f32, f32),
+}
+
+impl Npc {
+    pub fn new(id: &str, name: &str, position: (f32, f32)) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            position,
+        }
+    }
+
+    /// The people living in town
+    pub fn town() -> Vec<Npc> {
+        vec![
+            Npc::new("ferris", "Ferris", (48.0, -48.0)),
+            Npc::new("librarian", "Librarian", (384.0, -128.0)),
+        ]
+    }
+}
+
+/// The four-way scrolling world the player walks around in. The player's
+/// position lives in the app state so it is saved with the game.
+pub struct Overworld {
+    pub regions: Vec<Region>,
+    pub npcs: Vec<Npc>,
+    current_region: Option<String>,
+    pub content: Rc<GameContent>,
+}
+
+impl Default for Overworld {
+    fn default() -> Self {
+        Self {
+            regions: Region::town(),
+            npcs: Npc::town(),
+            current_region: None,
+            content: Rc::default(),
+        }
+    }
+}
+
+impl Overworld {
+    const STEP: f32 = 16.0;
+    const REGION_SIZE: f32 = 256.0;
+    /// Most questions asked in one daily review
+    const REVIEW_LENGTH: usize = 10;
+    const PLAYER_SIZE: f32 = 16.0;
+    const PLAYER_COLOR: [f32; 4] = [0.9, 0.3, 0.2, 1.0];
+    const PICKUP_SIZE: f32 = 12.0;
+    const PICKUP_COLOR: [f32; 4] = [0.95, 0.8, 0.3, 1.0];
+    const NPC_COLOR: [f32; 4] = [0.25, 0.45, 0.9, 1.0];
+    /// How close the player has to stand to talk to someone
+    const TALK_RANGE: f32 = 32.0;
+    const REGION_COLORS: [[f32; 4]; 4] = [
+        [0.45, 0.7, 0.4, 1.0],
+        [0.6, 0.5, 0.75, 1.0],
+        [0.35, 0.55, 0.8, 1.0],
+        [0.7, 0.45, 0.3, 1.0],
+    ];
+
+    pub fn with_content(mut self, content: Rc<GameContent>) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Open the lesson in progress, or the default lesson if there is none.
+    /// Once it is completed the next unlocked lesson opens instead, and
+    /// lessons whose prerequisites are not completed stay closed unless an
+    /// item the learner holds unlocks them.
+    fn open_lesson(&self, state: &AppState) -> SceneTransition {
+        let lessons = &self.content.lessons;
+        let granted = state.granted_lessons(&self.content.items);
+        let mut lesson_id = if state.current_lesson_id.is_empty() {
+            &self.content.default_lesson
+        } else {
+            &state.current_lesson_id
+        };
+        if state.is_lesson_completed(lesson_id) {
+            if let Some(next) = lessons.recommended_next(&state.completed_lessons, &granted) {
+                lesson_id = &next.id;
+            }
+        }
+        let Some(lesson) = lessons.get(lesson_id) else {
+            eprintln!("Lesson {} is not in the catalog", lesson_id);
+            return SceneTransition::None;
+        };
+        if !lessons.is_unlocked(lesson_id, &state.completed_lessons, &granted) {
+            eprintln!(
+                "Lesson {} is locked until its prerequisites are completed",
+                lesson_id
+            );
+            return SceneTransition::None;
+        }
+        match LessonView::open(lesson, Rc::clone(&self.content), state) {
+            Ok(view) => SceneTransition::Push(Box::new(view)),
+            Err(e) => {
+                eprintln!("Failed to open lesson {}: {}", lesson_id, e);
+                SceneTransition::None
+            }
+        }
+    }
+
+    /// Start today's review of the questions due again, if there are any
+    fn open_review(&self, state: &AppState) -> SceneTransition {
+        let quizzes = self.content.quizzes();
+        match state
+            .reviews
+            .daily_review(review::today(), Self::REVIEW_LENGTH, &quizzes)
+        {
+            Some(quiz) => SceneTransition::Push(Box::new(QuizView::review(quiz))),
+            None => SceneTransition::None,
+        }
+    }
+
+    /// The region the player stands in, if any
+    pub fn region_at(&self, x: f32, y: f32) -> Option<&Region> {
+        self.regions
+            .iter()
+            .find(|region| region.bounds.contains(x, y))
+    }
+
+    /// Report the region the player is in once they step into it
+    fn track_region(&mut self, state: &mut AppState) {
+        let (x, y) = state.player_position;
+        let region = self.region_at(x, y).map(|region| region.id.clone());
+        if region != self.current_region {
+            if let Some(id) = &region {
+                state.trigger(QuestTrigger::EnteredZone(id.clone()));
+            }
+            self.current_region = region;
+        }
+    }
+
+    /// The townsperson close enough to talk to, if any
+    pub fn npc_in_reach(&self, state: &AppState) -> Option<&Npc> {
+        let (x, y) = state.player_position;
+        self.npcs.iter().find(|npc| {
+            let (npc_x, npc_y) = npc.position;
+            (npc_x - x).abs() <= Self::TALK_RANGE && (npc_y - y).abs() <= Self::TALK_RANGE
+        })
+    }
+
+    /// Collect the pickups the player is standing on. Whatever does not fit
+    /// in the inventory stays on the ground.
+    fn collect_pickups(&self, state: &mut AppState) {
+        let (x, y) = state.player_position;
+        let reach = (Self::PLAYER_SIZE + Self::PICKUP_SIZE) / 2.0;
+        let mut collected = Vec::new();
+        for pickup in &mut state.pickups {
+            let (pickup_x, pickup_y) = pickup.position;
+            if (pickup_x - x).abs() > reach || (pickup_y - y).abs() > reach {
+                continue;
+            }
+            match state.inventory.pick_up(&self.content.items, pickup) {
+                Ok(0) => {}
+                Ok(_) => collected.push(pickup.item_id.clone()),
+                Err(e) => eprintln!("Failed to pick up {}: {}", pickup.item_id, e),
+            }
+        }
+        state.pickups.retain(|pickup| pickup.quantity > 0);
+        for item_id in collected {
+            let total = state.inventory.count(&item_id);
+            state.trigger(QuestTrigger::ItemCollected { item_id, total });
+        }
+    }
+}
+
+impl Scene for Overworld {
+    fn name(&self) -> &str {
+        "Overworld"
+    }
+
+    fn enter(&mut self, state: &mut AppState) {
+        self.track_region(state);
+    }
+
+    fn handle_input(&mut self, state: &mut AppState, input: &InputEvent) -> SceneTransition {
+        let (x, y) = &mut state.player_position;
+        match key_of(input) {
+            Some("ArrowUp") => *y -= Self::STEP,
+            Some("ArrowDown") => *y += Self::STEP,
+            Some("ArrowLeft") => *x -= Self::STEP,
+            Some("ArrowRight") => *x += Self::STEP,
+            Some("Escape") => return SceneTransition::Push(Box::new(PauseMenu)),
+            Some("l") => return self.open_lesson(state),
+            Some("r") => return self.open_review(state),
+            Some("e") => {
+                if let Some(npc) = self.npc_in_reach(state) {
+                    state.trigger(QuestTrigger::TalkedTo(npc.id.clone()));
+                }
+            }
+            _ => {}
+        }
+        self.track_region(state);
+        self.collect_pickups(state);
+        SceneTransition::None
+    }
+
+    fn render(&self, state: &AppState, graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        let (width, height) = window_size(state);
+        // The camera follows the player, who stays in the middle of the window
+        let (player_x, player_y) = state.player_position;
+        let (offset_x, offset_y) = (width / 2.0 - player_x, height / 2.0 - player_y);
+
+        for (region, color) in self.regions.iter().zip(Self::REGION_COLORS.iter().cycle()) {
+            let bounds = region.bounds;
+            graphics.draw(DrawCommand::Quad {
+                x: bounds.x + offset_x,
+                y: bounds.y + offset_y,
+                width: bounds.width,
+                height: bounds.height,
+                color: *color,
+            });
+            graphics.draw(DrawCommand::Text {
+                x: bounds.x + offset_x + 8.0,
+                y: bounds.y + offset_y + 8.0,
+                size: FONT_SIZE,
+                text: region.id.clone(),
+                color: [1.0; 4],
+            });
+        }
+        for pickup in &state.pickups {
+            let (x, y) = pickup.position;
+            graphics.draw(DrawCommand::Quad {
+                x: x + offset_x - Self::PICKUP_SIZE / 2.0,
+                y: y + offset_y - Self::PICKUP_SIZE / 2.0,
+                width: Self::PICKUP_SIZE,
+                height: Self::PICKUP_SIZE,
+                color: Self::PICKUP_COLOR,
+            });
+        }
+        for npc in &self.npcs {
+            let (x, y) = npc.position;
+            graphics.draw(DrawCommand::Quad {
+                x: x + offset_x - Self::PLAYER_SIZE / 2.0,
+                y: y + offset_y - Self::PLAYER_SIZE / 2.0,
+                width: Self::PLAYER_SIZE,
+                height: Self::PLAYER_SIZE,
+                color: Self::NPC_COLOR,
+            });
+        }
+        graphics.draw(DrawCommand::Quad {
+            x: (width - Self::PLAYER_SIZE) / 2.0,
+            y: (height - Self::PLAYER_SIZE) / 2.0,
+            width: Self::PLAYER_SIZE,
+            height: Self::PLAYER_SIZE,
+            color: Self::PLAYER_COLOR,
+        });
+
+        let due = state.reviews.due(review::today()).len();
+        if due > 0 {
+            graphics.draw(DrawCommand::Text {
+                x: PADDING,
+                y: PADDING,
+                size: FONT_SIZE,
+                text: format!("R: Daily review ({} due)", due),
+                color: [1.0; 4],
+            });
+        }
+        if let Some(npc) = self.npc_in_reach(state) {
+            graphics.draw(DrawCommand::Text {
+                x: PADDING,
+                y: height - PADDING - FONT_SIZE,
+                size: FONT_SIZE,
+                text: format!("E: Talk to {}", npc.name),
+                color: [1.0; 4],
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Lesson content drawn as a panel over the world. The lesson runtime is
+/// shared with the quiz view the lesson opens.
+pub struct LessonView {
+    pub lesson: EducationalContent,
+    pub runtime: Rc<RefCell<LessonRuntime>>,
+    /// Positions of the lesson's elements for the current window size,
+    /// where the lesson panel places them
+    pub layout: ElementLayout,
+    content: Rc<GameContent>,
+    panel: UiState,
+    /// The lesson panel laid out by the last update
+    tree: Option<LayoutTree>,
+    runner: ExerciseRunner,
+    /// How the last run of each code exercise went, by element index
+    outcomes: BTreeMap<usize, RunOutcome>,
+}
+
+impl LessonView {
+    /// Most questions in a practice quiz
+    const PRACTICE_LENGTH: usize = 5;
+
+    /// Open a lesson for the active learner, continuing from their mastery
+    /// estimates. Lessons with an invalid button action are rejected.
+    pub fn open(
+        lesson: &EducationalContent,
+        content: Rc<GameContent>,
+        state: &AppState,
+    ) -> anyhow::Result<Self> {
+        let runtime = LessonRuntime::new(lesson, Some(&content.items))?
+            .with_actor(state.actor())
+            .with_mastery(state.learner_progress.mastery.clone());
+        Ok(Self {
+            lesson: lesson.clone(),
+            runtime: Rc::new(RefCell::new(runtime)),
+            layout: ElementLayout::new(Layout::default(), &lesson.interactive_elements),
+            content,
+            panel: UiState::new(),
+            tree: None,
+            runner: ExerciseRunner::new(),
+            outcomes: BTreeMap::new(),
+        })
+    }
+
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout.set_layout(layout);
+        self
+    }
+
+    pub fn lesson_id(&self) -> &str {
+        &self.lesson.id
+    }
+
+    /// Use an interactive element: run a button's action or start a quiz
+    pub fn activate(&mut self, state: &mut AppState, element_index: usize) -> SceneTransition {
+        let Some(element) = self.lesson.interactive_elements.get(element_index) else {
+            return SceneTransition::None;
+        };
+        let mut runtime = self.runtime.borrow_mut();
+        let result = match &element.payload {
+            ElementPayload::Quiz(quiz) => runtime.start_quiz(&quiz.id).map(|_| ()),
+            ElementPayload::Exercise { code: Some(_), .. } => {
+                runtime.start_exercise(element_index, &self.runner)
+            }
+            ElementPayload::Button { .. } => runtime
+                .click(element_index, &mut state.inventory, &self.content.items)
+                .map(|action| {
+                    if let ButtonAction::GrantItem { item_id, .. } = action {
+                        let total = state.inventory.count(&item_id);
+                        state.trigger(QuestTrigger::ItemCollected { item_id, total });
+                    }
+                }),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("Lesson {}: {}", self.lesson.id, e);
+        }
+        runtime.session.sync(state);
+
+        if runtime.closed {
+            SceneTransition::Pop
+        } else {
+            drop(runtime);
+            self.open_quiz()
+        }
+    }
+
+    /// Hand the runtime's learning events to the state for logging
+    fn record_events(&self, state: &mut AppState) {
+        let events = self.runtime.borrow_mut().drain_events();
+        state.learning_events.extend(events);
+    }
+
+    /// Show the runtime's quiz in progress, if there is one
+    fn open_quiz(&self) -> SceneTransition {
+        match QuizView::new(Rc::clone(&self.runtime)) {
+            Some(quiz) => SceneTransition::Push(Box::new(quiz)),
+            None => SceneTransition::None,
+        }
+    }
+}
+
+impl Scene for LessonView {
+    fn name(&self) -> &str {
+        "LessonView"
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn enter(&mut self, state: &mut AppState) {
+        let progress = if state.current_lesson_id == self.lesson.id {
+            state.lesson_progress
+        } else {
+            0.0
+        };
+        state.advance_lesson(self.lesson.id.clone(), progress);
+        let (width, height) = state.window_size;
+        self.layout.resize(width, height);
+    }
+
+    fn exit(&mut self, state: &mut AppState) {
+        self.record_events(state);
+        let runtime = self.runtime.borrow();
+        let progress = &mut state.learner_progress;
+        progress.record_session(&runtime.session, unix_timestamp());
+        progress.record_hints(&self.lesson.id, &runtime.hints, unix_timestamp());
+        progress.mastery = runtime.mastery.clone();
+    }
+
+    fn update(&mut self, state: &mut AppState, delta_time: f64) -> SceneTransition {
+        let (width, height) = state.window_size;
+        self.layout.resize(width, height);
+        {
+            let mut runtime = self.runtime.borrow_mut();
+            runtime.session.update(delta_time);
+            match runtime.poll_exercise() {
+                Some((index, Ok(outcome))) => {
+                    self.outcomes.insert(index, outcome);
+                }
+                Some((index, Err(e))) => {
+                    eprintln!("Lesson {}: exercise {}: {}", self.lesson.id, index, e)
+                }
+                None => {}
+            }
+            runtime.session.sync(state);
+        }
+
+        let (width, height) = window_size(state);
+        let content_height = height - 4.0 * PADDING - TITLE_HEIGHT - PROGRESS_HEIGHT - SPACING;
+        let mut ui = self.panel.frame(width, height);
+        let clicked = {
+            let mut runtime = self.runtime.borrow_mut();
+            let running = runtime.running_exercise();
+            let mut exercises = runtime
+                .exercises_mut()
+                .map(|(index, exercise, editor)| {
+                    let expected = match &exercise.check {
+                        ExerciseCheck::Output { expected } => Some(expected.as_str()),
+                        _ => None,
+                    };
+                    let view = ExerciseView {
+                        editor,
+                        running: running == Some(index),
+                        outcome: self.outcomes.get(&index),
+                        expected,
+                    };
+                    (index, view)
+                })
+                .collect();
+            lesson_panel(
+                &mut ui,
+                &self.lesson,
+                state.lesson_progress,
+                content_height,
+                self.layout.positions(),
+                &mut exercises,
+            )
+        };
+        self.tree = Some(ui.finish());
+        let transition = match clicked {
+            Some(index) => self.activate(state, index),
+            None => SceneTransition::None,
+        };
+        self.record_events(state);
+        transition
+    }
+
+    fn handle_input(&mut self, _state: &mut AppState, input: &InputEvent) -> SceneTransition {
+        if let InputEvent::Focus { focused } = input {
+            self.runtime.borrow_mut().session.set_focused(*focused);
+        }
+        // Keys typed into a code editor are code, not shortcuts
+        if self.panel.captures_keys() {
+            self.panel.handle_input(input);
+            return SceneTransition::None;
+        }
+        match key_of(input) {
+            Some("Escape") => SceneTransition::Pop,
+            Some("q") => {
+                let first = self
+                    .runtime
+                    .borrow()
+                    .quizzes()
+                    .first()
+                    .map(|q| q.id.clone());
+                if let Some(quiz_id) = first {
+                    if let Err(e) = self.runtime.borrow_mut().start_quiz(&quiz_id) {
+                        eprintln!("Lesson {}: {}", self.lesson.id, e);
+                    }
+                }
+                self.open_quiz()
+            }
+            Some("p") => {
+                self.runtime
+                    .borrow_mut()
+                    .start_practice(Self::PRACTICE_LENGTH);
+                self.open_quiz()
+            }
+            _ => {
+                self.panel.handle_input(input);
+                SceneTransition::None
+            }
+        }
+    }
+
+    fn render(&self, _state: &AppState, graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        if let Some(tree) = &self.tree {
+            tree.render(graphics);
+        }
+        Ok(())
+    }
+}
+
+/// Where a quiz came from, which decides where hints and results go
+enum QuizSource {
+    Lesson(Rc<RefCell<LessonRuntime>>),
+    /// A daily review, which keeps its own hints
+    Review(HintTracker),
+}
+
+/// Answers a quiz one question at a time: number keys pick options, T and
+/// F answer true or false, typed text fills in blanks and numbers, Enter
+/// moves on and F1 reveals a hint. Answered questions are scheduled for
+/// review.
+pub struct QuizView {
+    source: QuizSource,
+    quiz: Quiz,
+    current: usize,
+    answers: HashMap<String, Answer>,
+    typed: String,
+    /// Seconds spent on the current question and on each answered one
+    elapsed: f64,
+    response_secs: BTreeMap<String, f32>,
+    pub result: Option<QuizResult>,
+}
+
+impl QuizView {
+    /// A view of the runtime's quiz in progress, if there is one
+    pub fn new(runtime: Rc<RefCell<LessonRuntime>>) -> Option<Self> {
+        let quiz = runtime.borrow().active_quiz.clone()?;
+        Some(Self::with_source(QuizSource::Lesson(runtime), quiz))
+    }
+
+    /// A daily review built by `ReviewScheduler::daily_review`
+    pub fn review(quiz: Quiz) -> Self {
+        Self::with_source(QuizSource::Review(HintTracker::new()), quiz)
+    }
+
+    fn with_source(source: QuizSource, quiz: Quiz) -> Self {
+        Self {
+            source,
+            quiz,
+            current: 0,
+            answers: HashMap::new(),
+            typed: String::new(),
+            elapsed: 0.0,
+            response_secs: BTreeMap::new(),
+            result: None,
+        }
+    }
+
+    /// Apply a key to the current question
+    fn answer(&mut self, key: &str) {
+        let Some(question) = self.quiz.questions.get(self.current) else {
+            return;
+        };
+        let choice = key
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1));
+        let answer = self.answers.get(&question.id);
+        let answer = match (&question.kind, key, choice) {
+            (QuestionKind::MultipleChoice { options, .. }, _, Some(choice))
+                if choice < options.len() =>
+            {
+                Answer::Choice(choice)
+            }
+            (QuestionKind::MultiSelect { options, .. }, _, Some(choice))
+                if choice < options.len() =>
+            {
+                let mut choices = match answer {
+                    Some(Answer::Choices(choices)) => choices.clone(),
+                    _ => Vec::new(),
+                };
+                match choices.iter().position(|&c| c == choice) {
+                    Some(position) => {
+                        choices.remove(position);
+                    }
+                    None => choices.push(choice),
+                }
+                Answer::Choices(choices)
+            }
+            (QuestionKind::Ordering { items, .. }, _, Some(choice)) if choice < items.len() => {
+                let mut order = match answer {
+                    Some(Answer::Order(order)) => order.clone(),
+                    _ => Vec::new(),
+                };
+                if !order.contains(&choice) {
+                    order.push(choice);
+                }
+                Answer::Order(order)
+            }
+            (QuestionKind::Ordering { .. }, "Backspace", _) => match answer {
+                Some(Answer::Order(order)) => {
+                    Answer::Order(order[..order.len().saturating_sub(1)].to_vec())
+                }
+                _ => return,
+            },
+            (QuestionKind::TrueFalse { .. }, "t", _) => Answer::Bool(true),
+            (QuestionKind::TrueFalse { .. }, "f", _) => Answer::Bool(false),
+            (QuestionKind::FillInTheBlank { .. } | QuestionKind::Numeric { .. }, _, _) => {
+                match key {
+                    "Backspace" => {
+                        self.typed.pop();
+                    }
+                    "Space" => self.typed.push(' '),
+                    _ if key.chars().count() == 1 => self.typed.push_str(key),
+                    _ => return,
+                }
+                match &question.kind {
+                    QuestionKind::Numeric { .. } => match self.typed.trim().parse() {
+                        Ok(number) => Answer::Number(number),
+                        Err(_) => {
+                            self.answers.remove(&question.id);
+                            return;
+                        }
+                    },
+                    _ => Answer::Text(self.typed.clone()),
+                }
+            }
+            _ => return,
+        };
+        self.answers.insert(question.id.clone(), answer);
+    }
+
+    /// Move to the next question, submitting the quiz after the last one
+    fn next(&mut self, state: &mut AppState) {
+        if let Some(question) = self.quiz.questions.get(self.current) {
+            self.response_secs
+                .insert(question.id.clone(), self.elapsed as f32);
+        }
+        self.current += 1;
+        self.typed.clear();
+        self.elapsed = 0.0;
+        if self.current < self.quiz.questions.len() {
+            return;
+        }
+
+        let today = review::today();
+        match &mut self.source {
+            QuizSource::Lesson(runtime) => {
+                let mut runtime = runtime.borrow_mut();
+                match runtime.submit_quiz(&self.answers) {
+                    Ok(result) => {
+                        state
+                            .reviews
+                            .record_result(&result, &self.response_secs, today);
+                        state.record_quiz_result(&result);
+                        self.result = Some(result);
+                    }
+                    Err(e) => eprintln!("Quiz {}: {}", self.quiz.id, e),
+                }
+                runtime.session.sync(state);
+                state.learning_events.extend(runtime.drain_events());
+            }
+            QuizSource::Review(hints) => {
+                let result = self.quiz.score_with_hints(&self.answers, hints);
+                state
+                    .reviews
+                    .record_review(&result, &self.response_secs, today);
+                state.record_quiz_result(&result);
+                state
+                    .learner_progress
+                    .record_hints(&self.quiz.id, hints, unix_timestamp());
+                state.learning_events.extend(LearningEvent::quiz_answered(
+                    &state.actor(),
+                    &result,
+                    unix_timestamp(),
+                ));
+                self.result = Some(result);
+            }
+        }
+    }
+
+    fn hint(&mut self, state: &mut AppState) {
+        let Some(question) = self.quiz.questions.get(self.current) else {
+            return;
+        };
+        // Running out of hints leaves the revealed ones showing
+        match &mut self.source {
+            QuizSource::Lesson(runtime) => {
+                let mut runtime = runtime.borrow_mut();
+                let _ = runtime.request_hint(&question.id);
+                state.learning_events.extend(runtime.drain_events());
+            }
+            QuizSource::Review(hints) => {
+                if hints.reveal(&question.id, &question.hints).is_some() {
+                    state.learning_events.push(LearningEvent::hint_revealed(
+                        &state.actor(),
+                        &self.quiz.id,
+                        &question.id,
+                        hints.revealed(&question.id),
+                        unix_timestamp(),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// How many hint tiers the learner's mastery suggests offering
+    fn suggested_hints(&self, question_id: &str) -> usize {
+        match &self.source {
+            QuizSource::Lesson(runtime) => {
+                runtime.borrow().hint_level(question_id).unwrap_or(0) as usize
+            }
+            QuizSource::Review(_) => 0,
+        }
+    }
+
+    fn revealed_hints(&self, question_id: &str, hints: &[Hint]) -> Vec<Hint> {
+        match &self.source {
+            QuizSource::Lesson(runtime) => runtime.borrow().revealed_hints(question_id).to_vec(),
+            QuizSource::Review(tracker) => tracker.revealed_hints(question_id, hints).to_vec(),
+        }
+    }
+}
+
+impl Scene for QuizView {
+    fn name(&self) -> &str {
+        "QuizView"
+    }
+
+    fn update(&mut self, _state: &mut AppState, delta_time: f64) -> SceneTransition {
+        self.elapsed += delta_time;
+        SceneTransition::None
+    }
+
+    fn handle_input(&mut self, state: &mut AppState, input: &InputEvent) -> SceneTransition {
+        let Some(key) = key_of(input) else {
+            return SceneTransition::None;
+        };
+        if self.result.is_some() {
+            return match key {
+                "Enter" | "Escape" => SceneTransition::Pop,
+                _ => SceneTransition::None,
+            };
+        }
+        match key {
+            "Escape" => {
+                if let QuizSource::Lesson(runtime) = &self.source {
+                    runtime.borrow_mut().active_quiz = None;
+                }
+                return SceneTransition::Pop;
+            }
+            "Enter" => self.next(state),
+            "F1" => self.hint(state),
+            key => self.answer(key),
+        }
+        SceneTransition::None
+    }
+
+    fn render(&self, state: &AppState, graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        draw_panel(state, graphics, "quiz", &self.quiz.title, |ui| {
+            if let Some(result) = &self.result {
+                ui.label(&format!("Score: {:.0}%", result.score * 100.0));
+                ui.label("Press Enter to continue");
+                return;
+            }
+            let Some(question) = self.quiz.questions.get(self.current) else {
+                return;
+            };
+            let count = self.quiz.questions.len();
+            ui.progress_bar("quiz/progress", self.current as f32 / count as f32);
+            ui.label(&format!("Question {} of {}", self.current + 1, count));
+            ui.label(&question.prompt);
+            let options = match &question.kind {
+                QuestionKind::MultipleChoice { options, .. }
+                | QuestionKind::MultiSelect { options, .. } => options.as_slice(),
+                QuestionKind::Ordering { items, .. } => items.as_slice(),
+                _ => &[],
+            };
+            for (number, option) in options.iter().enumerate() {
+                ui.label(&format!("{}. {}", number + 1, option));
+            }
+            match self.answers.get(&question.id) {
+                Some(answer) => ui.label(&format!("Answer: {}", describe(answer))),
+                None if !self.typed.is_empty() => ui.label(&format!("Answer: {}", self.typed)),
+                None => ui.label("Answer: -"),
+            };
+            let revealed = self.revealed_hints(&question.id, &question.hints);
+            for hint in &revealed {
+                ui.label(&format!("Hint: {}", hint.text));
+            }
+            if revealed.len() < self.suggested_hints(&question.id).min(question.hints.len()) {
+                ui.label("Stuck? Press F1 for a hint");
+            }
+            ui.label("Enter: next question, F1: hint, Escape: leave the quiz");
+        });
+        Ok(())
+    }
+}
+
+fn describe(answer: &Answer) -> String {
+    let numbers = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(|index| (index + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match answer {
+        Answer::Choice(choice) => (choice + 1).to_string(),
+        Answer::Choices(choices) | Answer::Order(choices) => numbers(choices),
+        Answer::Bool(value) => value.to_string(),
+        Answer::Text(text) => text.clone(),
+        Answer::Number(number) => number.to_string(),
+    }
+}
+
+pub struct PauseMenu;
+
+impl Scene for PauseMenu {
+    fn name(&self) -> &str {
+        "PauseMenu"
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn handle_input(&mut self, _state: &mut AppState, input: &InputEvent) -> SceneTransition {
+        match key_of(input) {
+            Some("Escape") => SceneTransition::Pop,
+            Some("q") => SceneTransition::Quit,
+            _ => SceneTransition::None,
+        }
+    }
+
+    fn render(&self, state: &AppState, graphics: &mut GraphicsEngine) -> anyhow::Result<()> {
+        draw_panel(state, graphics, "pause", "Paused", |ui| {
+            ui.label("Escape: resume");
+            ui.label("Q: quit");
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::Verb;
+    use crate::inventory::WorldPickup;
+
+    fn press(key: &str) -> InputEvent {
+        InputEvent::KeyPress {
+            key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_title_screen_replaced_by_overworld() {
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(TitleScreen::default()), &mut state);
+
+        stack.handle_input(&mut state, &press("Enter"));
+
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+        assert!(!stack.should_quit());
+    }
+
+    #[test]
+    fn test_title_screen_selects_profile() {
+        let mut state = AppState::default();
+        let profiles = vec![
+            LearnerProfile::new("alex".to_string(), "Alex".to_string()),
+            LearnerProfile::new("sam".to_string(), "Sam".to_string()),
+        ];
+        let mut stack = SceneStack::new(Box::new(TitleScreen::with_profiles(profiles)), &mut state);
+
+        stack.handle_input(&mut state, &press("ArrowUp"));
+        stack.handle_input(&mut state, &press("Enter"));
+
+        assert_eq!(state.learner_id.as_deref(), Some("sam"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+    }
+
+    fn drawn_text(graphics: &GraphicsEngine) -> Vec<&str> {
+        graphics
+            .last_frame()
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_title_screen_renders_and_selects_by_click() {
+        let profiles = vec![
+            LearnerProfile::new("alex".to_string(), "Alex".to_string()),
+            LearnerProfile::new("sam".to_string(), "Sam".to_string()),
+        ];
+        let title = TitleScreen::with_profiles(profiles.clone());
+        let button = title.menu(800.0, 600.0).find("profile/sam").unwrap().rect;
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(title), &mut state);
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        stack.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+        assert!(drawn_text(&graphics).contains(&"Alex"));
+        assert!(drawn_text(&graphics).contains(&"Sam"));
+
+        stack.handle_input(
+            &mut state,
+            &InputEvent::MouseClick {
+                x: button.x + 2.0,
+                y: button.y + 2.0,
+            },
+        );
+        assert_eq!(state.learner_id.as_deref(), Some("sam"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+    }
+
+    #[test]
+    fn test_overworld_and_overlays_render() {
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(Overworld::default()), &mut state);
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        stack.handle_input(&mut state, &press("Escape"));
+        stack.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+
+        let text = drawn_text(&graphics);
+        assert!(text.contains(&"village"));
+        assert!(text.contains(&"Paused"));
+        assert!(graphics.last_frame().contains(&DrawCommand::Quad {
+            x: 392.0,
+            y: 292.0,
+            width: 16.0,
+            height: 16.0,
+            color: Overworld::PLAYER_COLOR,
+        }));
+    }
+
+    #[test]
+    fn test_input_routed_to_top_scene() {
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(Overworld::default()), &mut state);
+
+        stack.handle_input(&mut state, &press("Escape"));
+        assert_eq!(stack.top().unwrap().name(), "PauseMenu");
+
+        stack.handle_input(&mut state, &press("Escape"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+    }
+
+    #[test]
+    fn test_walking_into_regions_progresses_quests() {
+        let mut state = AppState {
+            quests: crate::quest::QuestLog::load("assets/data/quests.toml").unwrap(),
+            ..AppState::default()
+        };
+        let mut stack = SceneStack::new(Box::new(Overworld::default()), &mut state);
+
+        for _ in 0..16 {
+            stack.handle_input(&mut state, &press("ArrowRight"));
+        }
+        let quest = state.quests.quest("explore_liberty").unwrap();
+        assert_eq!(quest.progress(), 1.0 / 3.0);
+        assert!(quest.objectives[0].completed);
+
+        for _ in 0..16 {
+            stack.handle_input(&mut state, &press("ArrowDown"));
+        }
+        assert!(state.quests.quest("explore_liberty").unwrap().objectives[2].completed);
+    }
+
+    #[test]
+    fn test_talking_to_the_librarian_hands_out_the_key() {
+        let items = ItemDatabase::load("assets/data/items.toml").unwrap();
+        let mut state = AppState {
+            quests: crate::quest::QuestLog::load("assets/data/quests.toml").unwrap(),
+            ..AppState::default()
+        };
+        let mut stack = SceneStack::new(Box::new(Overworld::default()), &mut state);
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        stack.handle_input(&mut state, &press("e"));
+        for _ in 0..24 {
+            stack.handle_input(&mut state, &press("ArrowRight"));
+        }
+        for _ in 0..8 {
+            stack.handle_input(&mut state, &press("ArrowUp"));
+        }
+        stack.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+        assert!(drawn_text(&graphics).contains(&"E: Talk to Librarian"));
+        state.apply_quest_rewards(&items);
+        assert_eq!(state.inventory.count("library_key"), 0);
+
+        stack.handle_input(&mut state, &press("e"));
+        state.apply_quest_rewards(&items);
+        assert_eq!(
+            state.quests.quest("locked_shelf").unwrap().state,
+            crate::quest::QuestState::Completed
+        );
+        assert_eq!(state.inventory.count("library_key"), 1);
+    }
+
+    fn content(lessons: Vec<EducationalContent>) -> Rc<GameContent> {
+        Rc::new(GameContent {
+            lessons: LessonCatalog::new(lessons).unwrap(),
+            items: ItemDatabase::load("assets/data/items.toml").unwrap(),
+            default_lesson: "lesson_1".to_string(),
+        })
+    }
+
+    /// A lesson with a coin button and a two question true or false quiz
+    fn quiz_lesson() -> EducationalContent {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Ownership".to_string(),
+        );
+        lesson.add_interactive_element(crate::education::InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Button {
+                label: "Take a coin".to_string(),
+                action: ButtonAction::GrantItem {
+                    item_id: "crab_coin".to_string(),
+                    quantity: 10,
+                },
+            },
+        ));
+        let question = |id: &str, answer: bool| crate::quiz::Question {
+            id: id.to_string(),
+            prompt: format!("Is {} true?", id),
+            kind: QuestionKind::TrueFalse { answer },
+            weight: 1.0,
+            topics: vec!["ownership".to_string()],
+            difficulty: 0.5,
+            hints: vec![crate::hint::Hint::new("Think it through")],
+        };
+        lesson.add_interactive_element(crate::education::InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Quiz(Quiz {
+                id: "ownership".to_string(),
+                title: "Ownership".to_string(),
+                questions: vec![question("moves", true), question("copies", false)],
+            }),
+        ));
+        lesson.completion_criteria.quiz_score_threshold = Some(0.5);
+        lesson
+    }
+
+    #[test]
+    fn test_overworld_moves_on_to_the_next_unlocked_lesson() {
+        let mut second = EducationalContent::new(
+            "lesson_2".to_string(),
+            "Lesson 2".to_string(),
+            "Borrowing".to_string(),
+        );
+        second.prerequisites = vec!["lesson_1".to_string()];
+        let mut state = AppState::default();
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson(), second]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+
+        state.advance_lesson("lesson_2".to_string(), 0.0);
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+
+        state.advance_lesson("lesson_1".to_string(), 0.0);
+        stack.handle_input(&mut state, &press("l"));
+        for key in ["q", "t", "Enter", "f", "Enter", "Enter", "Escape"] {
+            stack.handle_input(&mut state, &press(key));
+        }
+        assert!(state.is_lesson_completed("lesson_1"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.scene_names(), vec!["Overworld", "LessonView"]);
+        assert_eq!(state.current_lesson_id, "lesson_2");
+    }
+
+    #[test]
+    fn test_items_picked_up_in_the_world_unlock_lessons() {
+        let library = "03-traits-and-window-management";
+        let mut locked = EducationalContent::new(
+            library.to_string(),
+            "Traits".to_string(),
+            "Traits and windows".to_string(),
+        );
+        locked.prerequisites = vec!["lesson_1".to_string()];
+        let mut state = AppState {
+            pickups: vec![WorldPickup::new("library_key", 1, (0.0, 32.0))],
+            ..AppState::default()
+        };
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson(), locked]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+
+        state.advance_lesson(library.to_string(), 0.0);
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+
+        stack.handle_input(&mut state, &press("ArrowDown"));
+        assert_eq!(state.inventory.count("library_key"), 0);
+        stack.handle_input(&mut state, &press("ArrowDown"));
+        assert_eq!(state.inventory.count("library_key"), 1);
+        assert!(state.pickups.is_empty());
+
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.scene_names(), vec!["Overworld", "LessonView"]);
+    }
+
+    #[test]
+    fn test_lesson_overlay_keeps_world_visible() {
+        let mut state = AppState::default();
+        state.advance_lesson("lesson_1".to_string(), 0.25);
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson()]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.visible_scene_names(), vec!["Overworld", "LessonView"]);
+        assert_eq!(state.lesson_progress, 0.25);
+
+        stack.handle_input(&mut state, &press("q"));
+        assert_eq!(stack.visible_scene_names(), vec!["QuizView"]);
+        assert!(stack.render(&state, &mut graphics).is_ok());
+    }
+
+    #[test]
+    fn test_overworld_opens_default_lesson_from_catalog() {
+        let first = "01-rust-basics-and-project-structure";
+        let content = Rc::new(GameContent {
+            lessons: LessonCatalog::load("lessons").unwrap(),
+            items: ItemDatabase::default(),
+            default_lesson: first.to_string(),
+        });
+        let title = TitleScreen::with_profiles(vec![LearnerProfile::new(
+            "alex".to_string(),
+            "Alex".to_string(),
+        )])
+        .with_content(content);
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(title), &mut state);
+
+        stack.handle_input(&mut state, &press("Enter"));
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.scene_names(), vec!["Overworld", "LessonView"]);
+        assert_eq!(state.current_lesson_id, first);
+
+        // An unknown lesson leaves the learner in the world
+        stack.handle_input(&mut state, &press("Escape"));
+        state.advance_lesson("ghost".to_string(), 0.0);
+        stack.handle_input(&mut state, &press("l"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+    }
+
+    #[test]
+    fn test_lesson_view_runs_buttons_and_quizzes() {
+        let quests = r#"
+            [[quest]]
+            id = "crab_savings"
+            title = "Crab Savings"
+            description = "Save up some Crab Coins"
+
+            [[quest.objective]]
+            id = "collect_coins"
+            description = "Hold 10 Crab Coins"
+            kind = { CollectItem = { item_id = "crab_coin", quantity = 10 } }
+        "#;
+        let mut state = AppState {
+            quests: crate::quest::QuestLog::from_toml_str(quests).unwrap(),
+            learner_id: Some("sam".to_string()),
+            learner_name: Some("Sam".to_string()),
+            ..AppState::default()
+        };
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson()]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+        stack.handle_input(&mut state, &press("l"));
+
+        // The coin button is the first widget the keyboard focuses
+        stack.handle_input(&mut state, &press("Tab"));
+        stack.update(&mut state, 0.016);
+        stack.handle_input(&mut state, &press("Enter"));
+        stack.update(&mut state, 0.016);
+        assert_eq!(state.inventory.count("crab_coin"), 10);
+        let quest = state.quests.quest("crab_savings").unwrap();
+        assert_eq!(quest.state, crate::quest::QuestState::Completed);
+
+        stack.handle_input(&mut state, &press("q"));
+        assert_eq!(stack.top().unwrap().name(), "QuizView");
+        for key in ["F1", "t", "Enter", "t", "Enter"] {
+            stack.handle_input(&mut state, &press(key));
+        }
+        // One of two right, less the hint penalty on the first
+        assert!((state.lesson_progress - 0.9).abs() < 1e-5);
+        assert!(!state.is_lesson_completed("lesson_1"));
+
+        stack.handle_input(&mut state, &press("Enter"));
+        stack.handle_input(&mut state, &press("q"));
+        for key in ["t", "Enter", "f", "Enter"] {
+            stack.handle_input(&mut state, &press(key));
+        }
+        assert!(state.is_lesson_completed("lesson_1"));
+        assert!(state.reviews.card("ownership", "copies").is_some());
+
+        stack.handle_input(&mut state, &press("Enter"));
+        stack.handle_input(&mut state, &press("Escape"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+        let progress = &state.learner_progress;
+        assert_eq!(progress.quiz_attempts.len(), 2);
+        // The hint revealed in the first attempt still counts against it
+        assert!((progress.best_quiz_score("lesson_1").unwrap() - 0.95).abs() < 1e-5);
+        assert!(progress.total_time_spent() > 0.0);
+        assert_eq!(progress.hints_used("lesson_1"), 1);
+
+        let events = state.drain_learning_events();
+        assert!(events.iter().all(|event| event.actor.id == "sam"));
+        let hints = events
+            .iter()
+            .filter(|event| event.object.kind == crate::analytics::ActivityKind::Hint)
+            .count();
+        assert_eq!(hints, 1);
+        for verb in [
+            Verb::Started,
+            Verb::Interacted,
+            Verb::Answered,
+            Verb::Completed,
+        ] {
+            assert!(events.iter().any(|event| event.verb == verb), "{:?}", verb);
+        }
+    }
+
+    #[test]
+    fn test_toast_draws_last_and_inside_the_window() {
+        let mut state = AppState {
+            window_size: (200, 100),
+            ..AppState::default()
+        };
+        let stack = SceneStack::new(Box::new(PauseMenu), &mut state);
+        let mut graphics = GraphicsEngine::headless(200, 100);
+
+        stack.render(&state, &mut graphics).unwrap();
+        draw_toast(&state, &mut graphics, "Achievement unlocked: First Steps");
+        graphics.render().unwrap();
+
+        assert_eq!(
+            drawn_text(&graphics).last(),
+            Some(&"Achievement unlocked: First Steps")
+        );
+        let frame = graphics.last_frame();
+        let DrawCommand::Quad { x, width, .. } = &frame[frame.len() - 2] else {
+            panic!("expected the toast background");
+        };
+        assert!(*x >= 0.0 && x + width <= 200.0);
+    }
+
+    #[test]
+    fn test_overworld_offers_daily_review() {
+        let today = review::today();
+        let mut state = AppState::default();
+        state
+            .reviews
+            .record("ownership", "moves", 0.0, 5.0, today - 1);
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson()]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        stack.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+        assert!(drawn_text(&graphics).contains(&"R: Daily review (1 due)"));
+
+        stack.handle_input(&mut state, &press("r"));
+        assert_eq!(stack.top().unwrap().name(), "QuizView");
+        stack.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+        assert!(drawn_text(&graphics).contains(&"Daily Review"));
+
+        stack.handle_input(&mut state, &press("t"));
+        stack.handle_input(&mut state, &press("Enter"));
+        assert!(!state
+            .reviews
+            .card("ownership", "moves")
+            .unwrap()
+            .is_due(today));
+        stack.handle_input(&mut state, &press("Enter"));
+        stack.handle_input(&mut state, &press("r"));
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+    }
+
+    #[test]
+    fn test_lesson_view_session_drives_progress() {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Ownership".to_string(),
+        );
+        lesson.completion_criteria.time_spent_minimum = 10.0;
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(Overworld::default()), &mut state);
+
+        stack.apply(
+            SceneTransition::Push(Box::new(
+                LessonView::open(&lesson, Rc::default(), &state).unwrap(),
+            )),
+            &mut state,
+        );
+        stack.update(&mut state, 5.0);
+        assert_eq!(state.lesson_progress, 0.5);
+
+        stack.handle_input(&mut state, &InputEvent::Focus { focused: false });
+        stack.update(&mut state, 5.0);
+        assert_eq!(state.lesson_progress, 0.5);
+
+        stack.handle_input(&mut state, &InputEvent::Focus { focused: true });
+        stack.update(&mut state, 5.0);
+        assert!(state.is_lesson_completed("lesson_1"));
+    }
+
+    #[test]
+    fn test_practice_carries_mastery_across_lessons() {
+        let mut state = AppState::default();
+        state
+            .learner_progress
+            .mastery
+            .topics
+            .insert("ownership".to_string(), 0.05);
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson()]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        stack.handle_input(&mut state, &press("l"));
+        stack.handle_input(&mut state, &press("p"));
+        assert_eq!(stack.top().unwrap().name(), "QuizView");
+        stack.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+        assert!(drawn_text(&graphics).contains(&"Practice"));
+        assert!(drawn_text(&graphics).contains(&"Stuck? Press F1 for a hint"));
+
+        for key in ["t", "Enter", "t", "Enter", "Enter", "Escape"] {
+            stack.handle_input(&mut state, &press(key));
+        }
+        assert_eq!(stack.scene_names(), vec!["Overworld"]);
+        let learned = state.learner_progress.mastery.mastery("ownership");
+        assert!(learned > 0.05);
+
+        let mut profile = LearnerProfile::new("sam".to_string(), "Sam".to_string());
+        profile.progress.capture(&state, 0);
+        assert_eq!(profile.progress.mastery.mastery("ownership"), learned);
+        let mut next_session = AppState::default();
+        profile.apply_to(&mut next_session);
+        let view = LessonView::open(&quiz_lesson(), content(Vec::new()), &next_session).unwrap();
+        assert_eq!(view.runtime.borrow().mastery.mastery("ownership"), learned);
+    }
+
+    #[test]
+    fn test_lesson_view_draws_its_panel() {
+        let mut state = AppState::default();
+        let lesson = quiz_lesson();
+        let content = content(vec![lesson.clone()]);
+        let mut view = LessonView::open(&lesson, content, &state).unwrap();
+        let mut graphics = GraphicsEngine::headless(800, 600);
+
+        view.enter(&mut state);
+        view.update(&mut state, 0.016);
+        view.render(&state, &mut graphics).unwrap();
+        graphics.render().unwrap();
+
+        let text = drawn_text(&graphics);
+        assert!(text.contains(&"Take a coin"));
+        assert!(text.contains(&"Ownership"));
+        assert_eq!(graphics.last_frame().last(), Some(&DrawCommand::Clip(None)));
+    }
+
+    #[test]
+    fn test_lesson_view_edits_and_runs_exercises() {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Printing".to_string(),
+        );
+        lesson.add_interactive_element(crate::education::InteractiveElement::new(
+            (0.0, 0.0),
+            ElementPayload::Exercise {
+                title: "Greet Ferris".to_string(),
+                instructions: crate::education::RichText::plain("Print a greeting"),
+                hints: Vec::new(),
+                code: Some(crate::exercise::CodeExercise {
+                    starter_code: "fn main() {\n    print!(\"Hello\");\n".to_string(),
+                    check: ExerciseCheck::Output {
+                        expected: "Hello, Ferris!".to_string(),
+                    },
+                }),
+            },
+        ));
+        let mut state = AppState::default();
+        let mut view = LessonView::open(&lesson, Rc::default(), &state).unwrap();
+        view.enter(&mut state);
+        view.update(&mut state, 0.016);
+
+        let click = |view: &mut LessonView, state: &mut AppState, id: &str| {
+            let rect = view.tree.as_ref().unwrap().find(id).unwrap().rect;
+            let input = InputEvent::MouseClick {
+                x: rect.x + 2.0,
+                y: rect.y + 2.0,
+            };
+            view.handle_input(state, &input);
+            view.update(state, 0.016);
+        };
+        let type_text = |view: &mut LessonView, state: &mut AppState, text: &str| {
+            for c in text.chars() {
+                let key = match c {
+                    ' ' => "Space".to_string(),
+                    '\n' => "Enter".to_string(),
+                    c => c.to_string(),
+                };
+                let transition = view.handle_input(state, &press(&key));
+                assert!(matches!(transition, SceneTransition::None));
+            }
+            view.update(state, 0.016);
+        };
+        let run = |view: &mut LessonView, state: &mut AppState| {
+            click(view, state, "lesson_1/element/0");
+            for _ in 0..3000 {
+                if !view.runtime.borrow().exercise_running() {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                view.update(state, 0.016);
+            }
+            let mut graphics = GraphicsEngine::headless(800, 600);
+            view.render(state, &mut graphics).unwrap();
+            graphics.render().unwrap();
+            drawn_text(&graphics)
+                .iter()
+                .map(|text| text.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        click(&mut view, &mut state, "lesson_1/editor/0");
+        type_text(&mut view, &mut state, "}");
+        let text = run(&mut view, &mut state);
+        assert!(text.contains(&"    print!(\"Hello\");".to_string()));
+        assert!(text.contains(&"Failed".to_string()));
+        assert!(text.contains(&"- Hello, Ferris!".to_string()));
+        assert!(text.contains(&"+ Hello".to_string()));
+        assert_eq!(view.runtime.borrow().session.elements_interacted(), 0);
+
+        // Typing a q or a p edits the code instead of opening a quiz
+        click(&mut view, &mut state, "lesson_1/editor/0");
+        view.handle_input(&mut state, &press("Backspace"));
+        type_text(&mut view, &mut state, "    print!(\", Ferris!\");\n}");
+        let text = run(&mut view, &mut state);
+        assert!(text.contains(&"Passed".to_string()));
+        assert_eq!(view.runtime.borrow().session.elements_interacted(), 1);
+    }
+
+    #[test]
+    fn test_lesson_view_lays_out_after_resize() {
+        let mut lesson = EducationalContent::new(
+            "lesson_1".to_string(),
+            "Lesson 1".to_string(),
+            "Ownership".to_string(),
+        );
+        lesson.add_interactive_element(crate::education::InteractiveElement::new(
+            (100.0, 50.0),
+            ElementPayload::Button {
+                label: "Next".to_string(),
+                action: ButtonAction::None,
+            },
+        ));
+        let mut state = AppState::default();
+        let mut view = LessonView::open(&lesson, Rc::default(), &state).unwrap();
+        // Where the panel put the button, from the top left of its content
+        let placed = |view: &LessonView| {
+            let tree = view.tree.as_ref().unwrap();
+            let content = tree.find("lesson_1/content").unwrap().rect;
+            let button = tree.find("lesson_1/element/0").unwrap().rect;
+            (button.x - content.x, button.y - content.y)
+        };
+
+        view.enter(&mut state);
+        view.update(&mut state, 0.016);
+        assert_eq!(view.layout.position(0), Some((100.0, 50.0)));
+        assert_eq!(placed(&view), (100.0, 50.0));
+
+        // Resizes reach the state even while a quiz covers the lesson
+        let mut stack = SceneStack::new(Box::new(PauseMenu), &mut state);
+        stack.handle_input(
+            &mut state,
+            &InputEvent::Resize {
+                width: 1600,
+                height: 1200,
+            },
+        );
+        view.update(&mut state, 0.016);
+        assert_eq!(view.layout.position(0), Some((200.0, 100.0)));
+        assert_eq!(placed(&view), (200.0, 100.0));
+    }
+
+    #[test]
+    fn test_quit_from_pause_menu() {
+        let mut state = AppState::default();
+        let mut stack = SceneStack::new(Box::new(Overworld::default()), &mut state);
+
+        stack.handle_input(&mut state, &press("Escape"));
+        stack.handle_input(&mut state, &press("q"));
+
+        assert!(stack.should_quit());
+    }
+
+    #[test]
+    fn test_closing_the_window_quits_from_any_scene() {
+        let mut state = AppState::default();
+        let overworld = Overworld::default().with_content(content(vec![quiz_lesson()]));
+        let mut stack = SceneStack::new(Box::new(overworld), &mut state);
+        stack.handle_input(&mut state, &press("l"));
+        assert!(!stack.should_quit());
+
+        stack.update(&mut state, 2.0);
+        stack.handle_input(&mut state, &InputEvent::CloseRequested);
+        assert!(stack.should_quit());
+        assert_eq!(stack.scene_names(), vec!["Overworld", "LessonView"]);
+
+        stack.exit_all(&mut state);
+        assert!(stack.is_empty());
+        assert_eq!(state.learner_progress.time_spent["lesson_1"], 2.0);
+    }
+}
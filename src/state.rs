@@ -1,3 +1,14 @@
+use crate::achievement::{
+    AchievementDatabase, AchievementEvent, AchievementProgress, AchievementToasts,
+};
+use crate::analytics::{Actor, LearningEvent};
+use crate::inventory::{Inventory, ItemDatabase, WorldPickup, DEFAULT_INVENTORY_SLOTS};
+use crate::profile::LearnerProgress;
+use crate::quest::{QuestEvent, QuestLog, QuestTrigger, Reward};
+use crate::quiz::QuizResult;
+use crate::review::ReviewScheduler;
+use crate::window::DESIGN_RESOLUTION;
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct AppState {
@@ -5,6 +16,37 @@ pub struct AppState {
     pub time: f64,
     pub lesson_progress: f32,
     pub current_lesson_id: String,
+    pub completed_lessons: Vec<String>,
+    pub learner_id: Option<String>,
+    /// Display name of the active learner, shown in learning records
+    pub learner_name: Option<String>,
+    pub achievements: AchievementProgress,
+    /// Unlock notifications shown over whatever scene is active
+    pub achievement_toasts: AchievementToasts,
+    /// Events that may unlock achievements, waiting to be counted against
+    /// the achievement database
+    pub achievement_events: Vec<AchievementEvent>,
+    /// Current window size in pixels, which scenes lay themselves out for
+    pub window_size: (u32, u32),
+    pub quests: QuestLog,
+    /// Experience earned from quest rewards
+    pub experience: u32,
+    /// Lessons opened to the learner by quest rewards
+    pub unlocked_lessons: Vec<String>,
+    /// Where the player stands in the overworld
+    pub player_position: (f32, f32),
+    pub inventory: Inventory,
+    /// Items still lying in the overworld
+    pub pickups: Vec<WorldPickup>,
+    /// Spaced-repetition schedule of the quiz questions answered so far
+    pub reviews: ReviewScheduler,
+    /// Learning events recorded by scenes, waiting to be written to the
+    /// learner's event log
+    pub learning_events: Vec<LearningEvent>,
+    /// The active learner's progress, which closed lessons add their quiz
+    /// attempts, time spent, hint usage and mastery to until it is saved
+    /// into their profile
+    pub learner_progress: LearnerProgress,
 }
 
 impl Default for AppState {
@@ -14,6 +56,22 @@ impl Default for AppState {
             time: 0.0,
             lesson_progress: 0.0,
             current_lesson_id: String::new(),
+            completed_lessons: Vec::new(),
+            learner_id: None,
+            learner_name: None,
+            achievements: AchievementProgress::default(),
+            achievement_toasts: AchievementToasts::default(),
+            achievement_events: Vec::new(),
+            window_size: DESIGN_RESOLUTION,
+            quests: QuestLog::new(),
+            experience: 0,
+            unlocked_lessons: Vec::new(),
+            player_position: (0.0, 0.0),
+            inventory: Inventory::new(DEFAULT_INVENTORY_SLOTS),
+            pickups: WorldPickup::town(),
+            reviews: ReviewScheduler::new(),
+            learning_events: Vec::new(),
+            learner_progress: LearnerProgress::default(),
         }
     }
 }
@@ -22,6 +80,7 @@ impl AppState {
     pub fn update(&mut self, delta_time: f64) {
         self.frame_count += 1;
         self.time += delta_time;
+        self.achievement_toasts.update(delta_time);
     }
 
     #[allow(dead_code)]
@@ -29,6 +88,147 @@ impl AppState {
         self.current_lesson_id = lesson_id;
         self.lesson_progress = progress;
     }
+
+    pub fn complete_lesson(&mut self, lesson_id: &str) {
+        self.complete_lesson_with_score(lesson_id, None);
+    }
+
+    /// Mark a lesson completed with the best quiz score reached in it. The
+    /// first completion is reported to the quest log.
+    pub fn complete_lesson_with_score(&mut self, lesson_id: &str, quiz_score: Option<f32>) {
+        if !self.is_lesson_completed(lesson_id) {
+            self.completed_lessons.push(lesson_id.to_string());
+            self.trigger(QuestTrigger::LessonCompleted {
+                lesson_id: lesson_id.to_string(),
+                quiz_score,
+            });
+        }
+    }
+
+    /// Report something that happened in the game world to the quest log
+    /// and queue it for achievements
+    pub fn trigger(&mut self, trigger: QuestTrigger) {
+        self.quests.handle(&trigger);
+        self.achievement_events
+            .extend(AchievementEvent::from_trigger(&trigger));
+    }
+
+    /// Queue a scored quiz for achievements
+    pub fn record_quiz_result(&mut self, result: &QuizResult) {
+        self.achievement_events
+            .push(AchievementEvent::from_quiz_result(result));
+    }
+
+    /// Lessons opened to the learner regardless of their prerequisites by
+    /// the items they hold or by quest rewards
+    pub fn granted_lessons(&self, items: &ItemDatabase) -> Vec<String> {
+        let mut lessons = self.inventory.unlocked_lessons(items);
+        lessons.extend(self.unlocked_lessons.iter().cloned());
+        lessons.sort();
+        lessons.dedup();
+        lessons
+    }
+
+    /// Take the quest events produced since the last call and hand out the
+    /// rewards of every completed quest. Collecting a reward item may
+    /// complete further quests, whose rewards are handed out as well.
+    pub fn apply_quest_rewards(&mut self, items: &ItemDatabase) -> Vec<QuestEvent> {
+        let mut handled = Vec::new();
+        loop {
+            let events = self.quests.drain_events();
+            if events.is_empty() {
+                return handled;
+            }
+            for event in &events {
+                if let QuestEvent::Completed { rewards, .. } = event {
+                    for reward in rewards {
+                        self.apply_reward(items, reward);
+                    }
+                }
+            }
+            handled.extend(events);
+        }
+    }
+
+    /// Reward items that do not fit in the inventory are dropped at the
+    /// player's feet
+    fn apply_reward(&mut self, items: &ItemDatabase, reward: &Reward) {
+        match reward {
+            Reward::Experience(amount) => self.experience += amount,
+            Reward::UnlockLesson(lesson_id) => {
+                if !self.unlocked_lessons.contains(lesson_id) {
+                    self.unlocked_lessons.push(lesson_id.clone());
+                }
+            }
+            Reward::Item { item_id, quantity } => {
+                match self.inventory.add(items, item_id, *quantity) {
+                    Ok(leftover) => {
+                        if leftover > 0 {
+                            self.pickups.push(WorldPickup::new(
+                                item_id,
+                                leftover,
+                                self.player_position,
+                            ));
+                        }
+                        let total = self.inventory.count(item_id);
+                        self.trigger(QuestTrigger::ItemCollected {
+                            item_id: item_id.clone(),
+                            total,
+                        });
+                    }
+                    Err(e) => eprintln!("Failed to hand out quest reward: {}", e),
+                }
+            }
+        }
+    }
+
+    pub fn is_lesson_completed(&self, lesson_id: &str) -> bool {
+        self.completed_lessons.iter().any(|id| id == lesson_id)
+    }
+
+    /// Who learning events are attributed to: the active learner, or the
+    /// guest when nobody picked a profile
+    pub fn actor(&self) -> Actor {
+        match &self.learner_id {
+            Some(id) => Actor {
+                id: id.clone(),
+                name: self.learner_name.clone().unwrap_or_else(|| id.clone()),
+            },
+            None => Actor::guest(),
+        }
+    }
+
+    /// Take the learning events recorded since the last call
+    pub fn drain_learning_events(&mut self) -> Vec<LearningEvent> {
+        std::mem::take(&mut self.learning_events)
+    }
+
+    /// Count an event towards achievements and queue a notification for
+    /// each one it unlocks. Returns how many were unlocked.
+    pub fn record_achievement(
+        &mut self,
+        database: &AchievementDatabase,
+        event: &AchievementEvent,
+        timestamp: u64,
+    ) -> usize {
+        let unlocked = self.achievements.handle(database, event, timestamp);
+        let count = unlocked.len();
+        self.achievement_toasts.push(unlocked);
+        count
+    }
+
+    /// Count every queued event towards achievements. Returns how many
+    /// were unlocked.
+    pub fn record_pending_achievements(
+        &mut self,
+        database: &AchievementDatabase,
+        timestamp: u64,
+    ) -> usize {
+        std::mem::take(&mut self.achievement_events)
+            .iter()
+            .map(|event| self.record_achievement(database, event, timestamp))
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +243,8 @@ mod tests {
         assert_eq!(state.time, 0.0);
         assert_eq!(state.lesson_progress, 0.0);
         assert_eq!(state.current_lesson_id, String::new());
+        assert!(state.completed_lessons.is_empty());
+        assert!(state.learner_id.is_none());
     }
 
     #[test]
@@ -69,6 +271,72 @@ mod tests {
         assert_eq!(state.lesson_progress, progress);
     }
 
+    #[test]
+    fn test_app_state_complete_lesson() {
+        let mut state = AppState::default();
+
+        state.complete_lesson("lesson_1");
+        state.complete_lesson("lesson_1");
+
+        assert!(state.is_lesson_completed("lesson_1"));
+        assert!(!state.is_lesson_completed("lesson_2"));
+        assert_eq!(state.completed_lessons.len(), 1);
+    }
+
+    #[test]
+    fn test_lesson_completion_progresses_quests() {
+        let mut state = AppState {
+            quests: QuestLog::load("assets/data/quests.toml").unwrap(),
+            ..AppState::default()
+        };
+
+        state.complete_lesson_with_score("01-rust-basics-and-project-structure", Some(0.9));
+
+        let quest = state.quests.quest("first_lesson").unwrap();
+        assert_eq!(quest.state, crate::quest::QuestState::Completed);
+        assert_eq!(
+            state.quests.quest("crab_savings").unwrap().state,
+            crate::quest::QuestState::Active
+        );
+    }
+
+    #[test]
+    fn test_quest_rewards_are_handed_out() {
+        let items = ItemDatabase::load("assets/data/items.toml").unwrap();
+        let mut state = AppState {
+            quests: QuestLog::load("assets/data/quests.toml").unwrap(),
+            ..AppState::default()
+        };
+        state.apply_quest_rewards(&items);
+        state.inventory.add(&items, "crab_coin", 7).unwrap();
+
+        for zone in ["library", "harbor", "forge"] {
+            state.trigger(QuestTrigger::EnteredZone(zone.to_string()));
+        }
+        state.trigger(QuestTrigger::TalkedTo("librarian".to_string()));
+        state.complete_lesson("01-rust-basics-and-project-structure");
+        let events = state.apply_quest_rewards(&items);
+
+        assert_eq!(state.experience, 50);
+        assert_eq!(state.inventory.count("library_key"), 1);
+        assert_eq!(state.inventory.count("crab_coin"), 12);
+        assert_eq!(
+            state.granted_lessons(&items),
+            vec![
+                "03-traits-and-window-management",
+                "04-collections-and-graphics-pipeline",
+            ]
+        );
+        // The coins from the first lesson also finish the savings quest
+        assert!(events.contains(&QuestEvent::Completed {
+            quest_id: "crab_savings".to_string(),
+            rewards: vec![Reward::UnlockLesson(
+                "04-collections-and-graphics-pipeline".to_string()
+            )],
+        }));
+        assert!(state.apply_quest_rewards(&items).is_empty());
+    }
+
     #[test]
     fn test_app_state_multiple_updates() {
         let mut state = AppState::default();
@@ -79,4 +347,45 @@ mod tests {
             assert!((state.time - (i as f64) * 0.016).abs() < 1e-10);
         }
     }
+
+    #[test]
+    fn test_record_achievement_queues_toast() {
+        let database = AchievementDatabase::load("assets/data/achievements.toml").unwrap();
+        let mut state = AppState::default();
+        let event = AchievementEvent::LessonCompleted {
+            lesson_id: "01".to_string(),
+        };
+
+        assert_eq!(state.record_achievement(&database, &event, 5), 1);
+        assert!(state.achievements.is_unlocked("first_steps"));
+        assert_eq!(
+            state.achievement_toasts.current().unwrap().name,
+            "First Steps"
+        );
+
+        state.update(crate::achievement::TOAST_SECONDS);
+        assert!(state.achievement_toasts.current().is_none());
+        assert_eq!(state.record_achievement(&database, &event, 6), 0);
+    }
+
+    #[test]
+    fn test_triggers_and_quiz_results_reach_achievements() {
+        let database = AchievementDatabase::load("assets/data/achievements.toml").unwrap();
+        let mut state = AppState::default();
+
+        state.complete_lesson("01");
+        state.trigger(QuestTrigger::TalkedTo("ferris".to_string()));
+        state.record_quiz_result(&QuizResult {
+            quiz_id: "ownership".to_string(),
+            questions: Vec::new(),
+            score: 1.0,
+        });
+        assert_eq!(state.achievement_events.len(), 2);
+
+        assert_eq!(state.record_pending_achievements(&database, 5), 2);
+        assert!(state.achievements.is_unlocked("first_steps"));
+        assert!(state.achievements.is_unlocked("perfectionist"));
+        assert!(state.achievement_events.is_empty());
+        assert_eq!(state.record_pending_achievements(&database, 6), 0);
+    }
 }
@@ -0,0 +1,1097 @@
+//! User interface module for Learn Liberty
+//!
+//! This module is a small immediate-mode toolkit for lesson panels and
+//! menus. Each frame the caller describes its widgets (panels, labels,
+//! buttons, scroll views, text inputs, code editors, checkboxes and
+//! progress bars) and gets back whether they were clicked or changed.
+//! Widgets are stacked top to bottom inside their container, or placed at
+//! a point without overlapping the ones above, and the frame
+//! produces a layout tree that can be hit-tested, inspected in tests and
+//! drawn through the `GraphicsEngine`. Focus moves with Tab and the arrow
+//! keys, Enter or Space activates the focused widget, and the focused text
+//! input takes typed characters. A focused code editor takes every key
+//! until Escape releases it. Only focus and scroll offsets live between
+//! frames.
+
+use crate::education::{EducationalContent, ElementPayload};
+use crate::exercise::{output_diff, CodeEditor, RunOutcome, RunStatus};
+use crate::graphics::{DrawCommand, GraphicsEngine};
+use crate::window::InputEvent;
+use std::collections::{BTreeMap, HashMap};
+
+pub const PADDING: f32 = 8.0;
+pub const SPACING: f32 = 6.0;
+pub const FONT_SIZE: f32 = 16.0;
+pub const LINE_HEIGHT: f32 = 20.0;
+/// Advance of one character; text is laid out as if monospaced
+pub const CHAR_WIDTH: f32 = FONT_SIZE * 0.5;
+pub const TITLE_HEIGHT: f32 = 28.0;
+pub const BUTTON_HEIGHT: f32 = 32.0;
+pub const CHECKBOX_SIZE: f32 = 20.0;
+pub const PROGRESS_HEIGHT: f32 = 12.0;
+/// Distance PageUp and PageDown scroll
+pub const SCROLL_STEP: f32 = 120.0;
+
+const TEXT_COLOR: [f32; 4] = [0.1, 0.1, 0.12, 1.0];
+const PANEL_COLOR: [f32; 4] = [0.95, 0.94, 0.9, 1.0];
+const TITLE_COLOR: [f32; 4] = [0.3, 0.45, 0.7, 1.0];
+const WIDGET_COLOR: [f32; 4] = [0.82, 0.84, 0.88, 1.0];
+const FOCUS_COLOR: [f32; 4] = [0.98, 0.78, 0.3, 1.0];
+const FILL_COLOR: [f32; 4] = [0.3, 0.7, 0.4, 1.0];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// The overlap of two rectangles, empty when they do not meet
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Rect::new(
+            x,
+            y,
+            (self.right().min(other.right()) - x).max(0.0),
+            (self.bottom().min(other.bottom()) - y).max(0.0),
+        )
+    }
+
+    /// The rectangle shrunk by `amount` on every side
+    pub fn inset(&self, amount: f32) -> Rect {
+        Rect::new(
+            self.x + amount,
+            self.y + amount,
+            (self.width - 2.0 * amount).max(0.0),
+            (self.height - 2.0 * amount).max(0.0),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetKind {
+    Root,
+    Panel {
+        title: String,
+    },
+    Label {
+        lines: Vec<String>,
+    },
+    Button {
+        label: String,
+    },
+    ScrollView {
+        offset: f32,
+        content_height: f32,
+    },
+    TextInput {
+        text: String,
+    },
+    /// Lines of code and the cursor as line and column
+    CodeEditor {
+        lines: Vec<String>,
+        cursor: (usize, usize),
+    },
+    Checkbox {
+        label: String,
+        checked: bool,
+    },
+    ProgressBar {
+        value: f32,
+    },
+}
+
+/// A laid-out widget
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub kind: WidgetKind,
+    pub rect: Rect,
+    /// The part of the window the widget may draw in and be clicked in
+    pub clip: Rect,
+    pub focused: bool,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn new(id: &str, kind: WidgetKind, rect: Rect, clip: Rect) -> Self {
+        Self {
+            id: id.to_string(),
+            kind,
+            rect,
+            clip,
+            focused: false,
+            children: Vec::new(),
+        }
+    }
+
+    /// Find a widget by id, depth first
+    pub fn find(&self, id: &str) -> Option<&Node> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    /// The innermost widget under a point
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<&Node> {
+        if !self.rect.contains(x, y) || !self.clip.contains(x, y) {
+            return None;
+        }
+        self.children
+            .iter()
+            .rev()
+            .find_map(|child| child.hit_test(x, y))
+            .or(Some(self))
+    }
+
+    fn draw(&self, graphics: &mut GraphicsEngine) {
+        let r = self.rect;
+        let quad = |rect: Rect, color: [f32; 4]| DrawCommand::Quad {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+            color,
+        };
+        let text = |x: f32, y: f32, text: &str| DrawCommand::Text {
+            x,
+            y,
+            size: FONT_SIZE,
+            text: text.to_string(),
+            color: TEXT_COLOR,
+        };
+        let background = if self.focused {
+            FOCUS_COLOR
+        } else {
+            WIDGET_COLOR
+        };
+
+        graphics.draw(DrawCommand::Clip(Some([
+            self.clip.x,
+            self.clip.y,
+            self.clip.width,
+            self.clip.height,
+        ])));
+        match &self.kind {
+            WidgetKind::Root | WidgetKind::ScrollView { .. } => {}
+            WidgetKind::Panel { title } => {
+                graphics.draw(quad(r, PANEL_COLOR));
+                graphics.draw(quad(
+                    Rect::new(r.x, r.y, r.width, TITLE_HEIGHT),
+                    TITLE_COLOR,
+                ));
+                graphics.draw(text(r.x + PADDING, r.y + 4.0, title));
+            }
+            WidgetKind::Label { lines } => {
+                for (index, line) in lines.iter().enumerate() {
+                    graphics.draw(text(r.x, r.y + index as f32 * LINE_HEIGHT, line));
+                }
+            }
+            WidgetKind::Button { label } => {
+                graphics.draw(quad(r, background));
+                graphics.draw(text(r.x + PADDING, r.y + 6.0, label));
+            }
+            WidgetKind::TextInput { text: value } => {
+                graphics.draw(quad(r, background));
+                graphics.draw(text(r.x + PADDING, r.y + 6.0, value));
+                if self.focused {
+                    let cursor = r.x + PADDING + value.chars().count() as f32 * CHAR_WIDTH;
+                    graphics.draw(quad(
+                        Rect::new(cursor, r.y + 6.0, 2.0, LINE_HEIGHT),
+                        TEXT_COLOR,
+                    ));
+                }
+            }
+            WidgetKind::CodeEditor { lines, cursor } => {
+                graphics.draw(quad(r, background));
+                for (index, line) in lines.iter().enumerate() {
+                    let y = r.y + PADDING + index as f32 * LINE_HEIGHT;
+                    graphics.draw(text(r.x + PADDING, y, line));
+                }
+                if self.focused {
+                    let (line, column) = *cursor;
+                    graphics.draw(quad(
+                        Rect::new(
+                            r.x + PADDING + column as f32 * CHAR_WIDTH,
+                            r.y + PADDING + line as f32 * LINE_HEIGHT,
+                            2.0,
+                            LINE_HEIGHT,
+                        ),
+                        TEXT_COLOR,
+                    ));
+                }
+            }
+            WidgetKind::Checkbox { label, checked } => {
+                let tick = Rect::new(r.x, r.y, CHECKBOX_SIZE, CHECKBOX_SIZE);
+                graphics.draw(quad(tick, background));
+                if *checked {
+                    graphics.draw(quad(tick.inset(4.0), TEXT_COLOR));
+                }
+                graphics.draw(text(r.x + CHECKBOX_SIZE + PADDING, r.y, label));
+            }
+            WidgetKind::ProgressBar { value } => {
+                graphics.draw(quad(r, WIDGET_COLOR));
+                graphics.draw(quad(
+                    Rect::new(r.x, r.y, r.width * value.clamp(0.0, 1.0), r.height),
+                    FILL_COLOR,
+                ));
+            }
+        }
+        for child in &self.children {
+            child.draw(graphics);
+        }
+    }
+}
+
+/// The widgets of one frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutTree {
+    pub root: Node,
+}
+
+impl LayoutTree {
+    pub fn find(&self, id: &str) -> Option<&Node> {
+        self.root.find(id)
+    }
+
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<&Node> {
+        self.root.hit_test(x, y)
+    }
+
+    /// Queue the frame's draw commands
+    pub fn render(&self, graphics: &mut GraphicsEngine) {
+        self.root.draw(graphics);
+        graphics.draw(DrawCommand::Clip(None));
+    }
+}
+
+/// What a widget did this frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Response {
+    pub rect: Rect,
+    pub clicked: bool,
+    pub changed: bool,
+}
+
+/// Focus, scroll offsets and input waiting for the next frame
+#[derive(Debug, Clone, Default)]
+pub struct UiState {
+    focused: Option<String>,
+    /// The focused widget once scroll views have brought it into view
+    revealed: Option<String>,
+    scroll: HashMap<String, f32>,
+    /// Whether the focused widget takes every key, as a code editor does
+    captures_keys: bool,
+    clicks: Vec<(f32, f32)>,
+    keys: Vec<String>,
+}
+
+impl UiState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a click or key press for the next frame
+    pub fn handle_input(&mut self, input: &InputEvent) {
+        match input {
+            InputEvent::MouseClick { x, y } => self.clicks.push((*x, *y)),
+            InputEvent::KeyPress { key } => self.keys.push(key.clone()),
+            InputEvent::Resize { .. } | InputEvent::Focus { .. } | InputEvent::CloseRequested => {}
+        }
+    }
+
+    pub fn focused(&self) -> Option<&str> {
+        self.focused.as_deref()
+    }
+
+    /// Whether the focused widget took every key last frame, so the
+    /// caller should not treat key presses as shortcuts
+    pub fn captures_keys(&self) -> bool {
+        self.captures_keys
+    }
+
+    pub fn focus(&mut self, id: &str) {
+        self.focused = Some(id.to_string());
+    }
+
+    pub fn scroll_offset(&self, id: &str) -> f32 {
+        self.scroll.get(id).copied().unwrap_or(0.0)
+    }
+
+    /// Start describing a frame that fills a `width` by `height` window
+    pub fn frame(&mut self, width: f32, height: f32) -> Ui<'_> {
+        let screen = Rect::new(0.0, 0.0, width, height);
+        let clicks = std::mem::take(&mut self.clicks);
+        let keys = std::mem::take(&mut self.keys);
+        Ui {
+            state: self,
+            stack: vec![Container {
+                node: Node::new("", WidgetKind::Root, screen, screen),
+                content: screen.inset(PADDING),
+                cursor: PADDING,
+                holds_focus: false,
+            }],
+            clicks,
+            keys,
+            focus_order: Vec::new(),
+            focused_rect: None,
+            clicked_anything: false,
+            key_capture: None,
+        }
+    }
+}
+
+struct Container {
+    node: Node,
+    /// Where children go; its `y` is the top of the content and `cursor`
+    /// tracks the next row
+    content: Rect,
+    cursor: f32,
+    holds_focus: bool,
+}
+
+/// Builds one frame of widgets
+pub struct Ui<'a> {
+    state: &'a mut UiState,
+    stack: Vec<Container>,
+    clicks: Vec<(f32, f32)>,
+    keys: Vec<String>,
+    focus_order: Vec<String>,
+    focused_rect: Option<Rect>,
+    clicked_anything: bool,
+    /// The focused widget that takes every key this frame
+    key_capture: Option<String>,
+}
+
+impl Ui<'_> {
+    fn top(&mut self) -> &mut Container {
+        self.stack
+            .last_mut()
+            .expect("the root container is never popped")
+    }
+
+    /// Reserve the next row of the current container
+    fn allocate(&mut self, height: f32) -> (Rect, Rect) {
+        let top = self.top();
+        let rect = Rect::new(top.content.x, top.cursor, top.content.width, height);
+        top.cursor += height + SPACING;
+        (rect, top.node.clip)
+    }
+
+    fn is_focused(&self, id: &str) -> bool {
+        self.state.focused.as_deref() == Some(id)
+    }
+
+    /// Register a focusable widget and report whether it was clicked or
+    /// activated with the keyboard
+    fn interact(&mut self, id: &str, rect: Rect, clip: Rect) -> bool {
+        self.focus_order.push(id.to_string());
+        let visible = rect.intersect(&clip);
+        let clicked = match self
+            .clicks
+            .iter()
+            .position(|&(x, y)| visible.contains(x, y))
+        {
+            Some(index) => {
+                self.clicks.remove(index);
+                self.clicked_anything = true;
+                self.state.focused = Some(id.to_string());
+                true
+            }
+            None => false,
+        };
+
+        if !self.is_focused(id) {
+            return clicked;
+        }
+        self.focused_rect = Some(rect);
+        for container in &mut self.stack {
+            container.holds_focus = true;
+        }
+        clicked || self.keys.iter().any(|key| key == "Enter" || key == "Space")
+    }
+
+    fn push(&mut self, mut node: Node) {
+        node.focused = self.is_focused(&node.id);
+        self.top().node.children.push(node);
+    }
+
+    /// Run `body` inside a new container and return it with its content
+    /// height
+    fn nest(&mut self, node: Node, content: Rect, body: impl FnOnce(&mut Ui)) -> (Node, f32) {
+        self.stack.push(Container {
+            node,
+            content,
+            cursor: content.y,
+            holds_focus: false,
+        });
+        body(self);
+        let container = self.stack.pop().expect("pushed above");
+        let height = (container.cursor - SPACING - content.y).max(0.0);
+        if container.holds_focus {
+            if let Some(parent) = self.stack.last_mut() {
+                parent.holds_focus = true;
+            }
+        }
+        (container.node, height)
+    }
+
+    /// Lay out `body` from `(x, y)` relative to the top left of the current
+    /// container instead of straight below the previous widget. Widgets do
+    /// not overlap, so a point above the bottom of the previous widget is
+    /// moved down below it.
+    pub fn place(&mut self, x: f32, y: f32, body: impl FnOnce(&mut Ui)) {
+        let top = self.top();
+        let content = top.content;
+        let x = x.clamp(0.0, content.width);
+        top.content = Rect::new(content.x + x, content.y, content.width - x, 0.0);
+        top.cursor = top.cursor.max(content.y + y);
+        body(self);
+        self.top().content = content;
+    }
+
+    /// A titled box whose height fits its contents
+    pub fn panel(&mut self, id: &str, title: &str, body: impl FnOnce(&mut Ui)) -> Response {
+        let (start, clip) = self.allocate(0.0);
+        let content = Rect::new(
+            start.x + PADDING,
+            start.y + TITLE_HEIGHT + PADDING,
+            (start.width - 2.0 * PADDING).max(0.0),
+            0.0,
+        );
+        let panel = Node::new(
+            id,
+            WidgetKind::Panel {
+                title: title.to_string(),
+            },
+            start,
+            clip,
+        );
+        let (mut node, content_height) = self.nest(panel, content, body);
+        node.rect.height = TITLE_HEIGHT + PADDING + content_height + PADDING;
+        self.top().cursor += node.rect.height;
+        let rect = node.rect;
+        self.push(node);
+        Response {
+            rect,
+            ..Response::default()
+        }
+    }
+
+    /// Text wrapped to the container width
+    pub fn label(&mut self, text: &str) -> Response {
+        let width = self.top().content.width;
+        let lines = wrap(text, width);
+        let (rect, clip) = self.allocate(lines.len().max(1) as f32 * LINE_HEIGHT);
+        let id = format!("label{}", self.top().node.children.len());
+        self.push(Node::new(&id, WidgetKind::Label { lines }, rect, clip));
+        Response {
+            rect,
+            ..Response::default()
+        }
+    }
+
+    pub fn button(&mut self, id: &str, label: &str) -> Response {
+        let (rect, clip) = self.allocate(BUTTON_HEIGHT);
+        let clicked = self.interact(id, rect, clip);
+        self.push(Node::new(
+            id,
+            WidgetKind::Button {
+                label: label.to_string(),
+            },
+            rect,
+            clip,
+        ));
+        Response {
+            rect,
+            clicked,
+            changed: false,
+        }
+    }
+
+    pub fn checkbox(&mut self, id: &str, label: &str, checked: &mut bool) -> Response {
+        let (rect, clip) = self.allocate(CHECKBOX_SIZE);
+        let clicked = self.interact(id, rect, clip);
+        if clicked {
+            *checked = !*checked;
+        }
+        self.push(Node::new(
+            id,
+            WidgetKind::Checkbox {
+                label: label.to_string(),
+                checked: *checked,
+            },
+            rect,
+            clip,
+        ));
+        Response {
+            rect,
+            clicked,
+            changed: clicked,
+        }
+    }
+
+    /// A single-line field; typed characters go to it while it is focused
+    pub fn text_input(&mut self, id: &str, text: &mut String) -> Response {
+        let (rect, clip) = self.allocate(BUTTON_HEIGHT);
+        let clicked = self.interact(id, rect, clip);
+        let mut changed = false;
+        if self.is_focused(id) {
+            for key in &self.keys {
+                match key.as_str() {
+                    "Backspace" => changed |= text.pop().is_some(),
+                    "Space" => text.push(' '),
+                    key if key.chars().count() == 1 => text.push_str(key),
+                    _ => continue,
+                }
+                changed |= key != "Backspace";
+            }
+        }
+        self.push(Node::new(
+            id,
+            WidgetKind::TextInput { text: text.clone() },
+            rect,
+            clip,
+        ));
+        Response {
+            rect,
+            clicked,
+            changed,
+        }
+    }
+
+    /// A multi-line field for editing code. While it is focused it takes
+    /// every key but Escape, which hands the focus back.
+    pub fn code_editor(&mut self, id: &str, editor: &mut CodeEditor) -> Response {
+        let mut changed = false;
+        if self.is_focused(id) {
+            for key in self.keys.iter().filter(|key| *key != "Escape") {
+                changed |= editor.handle_key(key);
+            }
+        }
+        let lines: Vec<String> = editor.text().split('\n').map(str::to_string).collect();
+        let (rect, clip) = self.allocate(lines.len() as f32 * LINE_HEIGHT + 2.0 * PADDING);
+        let clicked = self.interact(id, rect, clip);
+        if self.is_focused(id) {
+            self.key_capture = Some(id.to_string());
+        }
+
+        let before = &editor.text()[..editor.cursor()];
+        let line = before.matches('\n').count();
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count();
+        self.push(Node::new(
+            id,
+            WidgetKind::CodeEditor {
+                lines,
+                cursor: (line, column),
+            },
+            rect,
+            clip,
+        ));
+        Response {
+            rect,
+            clicked,
+            changed,
+        }
+    }
+
+    pub fn progress_bar(&mut self, id: &str, value: f32) -> Response {
+        let (rect, clip) = self.allocate(PROGRESS_HEIGHT);
+        self.push(Node::new(
+            id,
+            WidgetKind::ProgressBar {
+                value: value.clamp(0.0, 1.0),
+            },
+            rect,
+            clip,
+        ));
+        Response {
+            rect,
+            ..Response::default()
+        }
+    }
+
+    /// A `height` tall window onto content that may be taller. PageUp and
+    /// PageDown scroll it while it holds the focus, and a newly focused
+    /// widget is scrolled into view.
+    pub fn scroll_view(&mut self, id: &str, height: f32, body: impl FnOnce(&mut Ui)) -> Response {
+        let (rect, clip) = self.allocate(height);
+        let offset = self.state.scroll_offset(id);
+        let view = Node::new(
+            id,
+            WidgetKind::ScrollView {
+                offset,
+                content_height: 0.0,
+            },
+            rect,
+            rect.intersect(&clip),
+        );
+        let content = Rect::new(rect.x, rect.y - offset, rect.width, 0.0);
+        let focused_before = self.focused_rect;
+        self.focused_rect = None;
+        let (mut node, content_height) = self.nest(view, content, body);
+
+        let mut next = offset;
+        if let Some(focused) = self.focused_rect {
+            for key in &self.keys {
+                match key.as_str() {
+                    "PageDown" => next += SCROLL_STEP,
+                    "PageUp" => next -= SCROLL_STEP,
+                    _ => {}
+                }
+            }
+            if self.state.revealed != self.state.focused {
+                if focused.y < rect.y {
+                    next -= rect.y - focused.y;
+                } else if focused.bottom() > rect.bottom() {
+                    next += focused.bottom() - rect.bottom();
+                }
+            }
+        }
+        let next = next.clamp(0.0, (content_height - height).max(0.0));
+        self.state.scroll.insert(id.to_string(), next);
+        self.focused_rect = self.focused_rect.or(focused_before);
+
+        node.kind = WidgetKind::ScrollView {
+            offset,
+            content_height,
+        };
+        self.push(node);
+        Response {
+            rect,
+            changed: next != offset,
+            ..Response::default()
+        }
+    }
+
+    /// Finish the frame: apply keyboard navigation and return the layout
+    pub fn finish(mut self) -> LayoutTree {
+        self.state.revealed = self.state.focused.clone();
+        let current = self
+            .state
+            .focused
+            .as_ref()
+            .and_then(|id| self.focus_order.iter().position(|other| other == id));
+        let capturing = self.key_capture.is_some() && self.key_capture == self.state.focused;
+        for key in &self.keys {
+            if capturing && key != "Escape" {
+                continue;
+            }
+            let count = self.focus_order.len();
+            if count == 0 {
+                break;
+            }
+            let next = match (key.as_str(), current) {
+                ("Tab" | "ArrowDown", Some(index)) => Some((index + 1) % count),
+                ("ArrowUp", Some(index)) => Some((index + count - 1) % count),
+                ("Tab" | "ArrowDown", None) => Some(0),
+                ("ArrowUp", None) => Some(count - 1),
+                ("Escape", _) => None,
+                _ => continue,
+            };
+            self.state.focused = next.map(|index| self.focus_order[index].clone());
+            break;
+        }
+        if !self.clicks.is_empty() && !self.clicked_anything {
+            self.state.focused = None;
+        }
+        self.state.captures_keys =
+            self.state.focused.is_some() && self.state.focused == self.key_capture;
+
+        let root = self.stack.pop().expect("the root container").node;
+        LayoutTree { root }
+    }
+}
+
+/// Break text into lines no wider than `width`, at spaces where possible
+pub fn wrap(text: &str, width: f32) -> Vec<String> {
+    let columns = ((width / CHAR_WIDTH).floor() as usize).max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let used = line.chars().count();
+            if used > 0 && used + 1 + word.len() > columns {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > columns {
+                let rest = word.split_off(columns);
+                lines.push(word.into_iter().collect());
+                word = rest;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// A runnable exercise as the lesson panel shows it: the learner's code
+/// and how its last run went
+pub struct ExerciseView<'a> {
+    pub editor: &'a mut CodeEditor,
+    pub running: bool,
+    pub outcome: Option<&'a RunOutcome>,
+    /// The output an output check expects, compared with what the last run
+    /// printed
+    pub expected: Option<&'a str>,
+}
+
+/// Show how the last run of an exercise went, with what it printed next to
+/// what was expected when it printed the wrong thing
+fn run_outcome(ui: &mut Ui, outcome: &RunOutcome, expected: Option<&str>) {
+    ui.label(match outcome.status {
+        RunStatus::Passed => "Passed",
+        RunStatus::CompileError => "Does not compile",
+        RunStatus::Failed => "Failed",
+        RunStatus::TimedOut => "Timed out",
+    });
+    match (outcome.status, expected) {
+        (RunStatus::Passed, _) => {}
+        (RunStatus::Failed, Some(expected)) if outcome.stderr.trim().is_empty() => {
+            ui.label(&output_diff(expected, &outcome.stdout).join("\n"));
+        }
+        _ => {
+            ui.label(&outcome.stderr);
+        }
+    }
+}
+
+/// Show a lesson in a panel with its progress. Each element is placed at
+/// its position in `positions`, measured from the top left of the
+/// scrolling content, and runnable exercises in `exercises`, by element
+/// index, get an editor for their code. Returns the index of the
+/// interactive element the learner clicked this frame, if any.
+pub fn lesson_panel(
+    ui: &mut Ui,
+    lesson: &EducationalContent,
+    progress: f32,
+    height: f32,
+    positions: &[(f32, f32)],
+    exercises: &mut BTreeMap<usize, ExerciseView>,
+) -> Option<usize> {
+    let mut clicked = None;
+    ui.panel(&lesson.id, &lesson.title, |ui| {
+        ui.progress_bar(&format!("{}/progress", lesson.id), progress);
+        ui.scroll_view(&format!("{}/content", lesson.id), height, |ui| {
+            if !lesson.description.is_empty() {
+                ui.label(&lesson.description);
+            }
+            for (index, element) in lesson.interactive_elements.iter().enumerate() {
+                let id = format!("{}/element/{}", lesson.id, index);
+                let (x, y) = positions.get(index).copied().unwrap_or_default();
+                ui.place(x, y, |ui| {
+                    let response = match &element.payload {
+                        ElementPayload::Text(text) => ui.label(&text.to_plain()),
+                        ElementPayload::Image { alt_text, asset } => {
+                            ui.label(if alt_text.is_empty() { asset } else { alt_text })
+                        }
+                        ElementPayload::Button { label, .. } => ui.button(&id, label),
+                        ElementPayload::Quiz(quiz) => ui.button(&id, &quiz.title),
+                        ElementPayload::Video { asset, .. } => ui.button(&id, asset),
+                        ElementPayload::Code { code, .. } => ui.label(code),
+                        ElementPayload::Exercise {
+                            title,
+                            instructions,
+                            ..
+                        } => {
+                            ui.label(&instructions.to_plain());
+                            match exercises.get_mut(&index) {
+                                Some(exercise) => {
+                                    let editor = format!("{}/editor/{}", lesson.id, index);
+                                    ui.code_editor(&editor, exercise.editor);
+                                    let label = if exercise.running {
+                                        "Running...".to_string()
+                                    } else {
+                                        format!("Run {}", title)
+                                    };
+                                    let response = ui.button(&id, &label);
+                                    if let Some(outcome) = exercise.outcome {
+                                        run_outcome(ui, outcome, exercise.expected);
+                                    }
+                                    response
+                                }
+                                None => ui.button(&id, title),
+                            }
+                        }
+                    };
+                    if response.clicked {
+                        clicked = Some(index);
+                    }
+                });
+            }
+        });
+    });
+    clicked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(state: &mut UiState, key: &str) {
+        state.handle_input(&InputEvent::KeyPress {
+            key: key.to_string(),
+        });
+    }
+
+    fn menu(state: &mut UiState, audio: &mut bool, name: &mut String) -> (LayoutTree, bool) {
+        let mut ui = state.frame(400.0, 300.0);
+        let mut start = false;
+        ui.panel("menu", "Main menu", |ui| {
+            ui.label("Welcome to Learn Liberty");
+            start = ui.button("start", "Start").clicked;
+            ui.checkbox("audio", "Audio", audio);
+            ui.text_input("name", name);
+        });
+        (ui.finish(), start)
+    }
+
+    #[test]
+    fn test_layout_stacks_widgets() {
+        let mut state = UiState::new();
+        let (tree, _) = menu(&mut state, &mut true, &mut String::new());
+
+        let panel = tree.find("menu").unwrap();
+        assert_eq!(panel.rect.x, PADDING);
+        assert_eq!(panel.rect.width, 400.0 - 2.0 * PADDING);
+        let start = tree.find("start").unwrap().rect;
+        assert_eq!(
+            start.y,
+            PADDING + TITLE_HEIGHT + PADDING + LINE_HEIGHT + SPACING
+        );
+        assert_eq!(start.width, panel.rect.width - 2.0 * PADDING);
+        let name = tree.find("name").unwrap().rect;
+        assert_eq!(panel.rect.bottom(), name.bottom() + PADDING);
+
+        assert_eq!(
+            tree.hit_test(start.x + 1.0, start.y + 1.0).unwrap().id,
+            "start"
+        );
+        assert_eq!(
+            tree.hit_test(panel.rect.x + 1.0, panel.rect.y + 1.0)
+                .unwrap()
+                .id,
+            "menu"
+        );
+        assert_eq!(tree.hit_test(399.0, 299.0).unwrap().kind, WidgetKind::Root);
+    }
+
+    #[test]
+    fn test_clicks_and_keyboard_navigation() {
+        let mut state = UiState::new();
+        let (mut audio, mut name) = (true, String::new());
+        let (tree, _) = menu(&mut state, &mut audio, &mut name);
+
+        let checkbox = tree.find("audio").unwrap().rect;
+        state.handle_input(&InputEvent::MouseClick {
+            x: checkbox.x + 2.0,
+            y: checkbox.y + 2.0,
+        });
+        menu(&mut state, &mut audio, &mut name);
+        assert!(!audio);
+        assert_eq!(state.focused(), Some("audio"));
+
+        key(&mut state, "Tab");
+        menu(&mut state, &mut audio, &mut name);
+        for typed in ["H", "i", "Space", "!", "Backspace"] {
+            key(&mut state, typed);
+        }
+        let (tree, _) = menu(&mut state, &mut audio, &mut name);
+        assert_eq!(name, "Hi ");
+        assert!(tree.find("name").unwrap().focused);
+
+        key(&mut state, "ArrowUp");
+        menu(&mut state, &mut audio, &mut name);
+        key(&mut state, "ArrowUp");
+        menu(&mut state, &mut audio, &mut name);
+        assert_eq!(state.focused(), Some("start"));
+        key(&mut state, "Enter");
+        assert!(menu(&mut state, &mut audio, &mut name).1);
+
+        state.handle_input(&InputEvent::MouseClick { x: 399.0, y: 299.0 });
+        menu(&mut state, &mut audio, &mut name);
+        assert_eq!(state.focused(), None);
+    }
+
+    #[test]
+    fn test_scroll_view_clips_and_follows_focus() {
+        let mut state = UiState::new();
+        let frame = |state: &mut UiState| {
+            let mut ui = state.frame(200.0, 400.0);
+            ui.scroll_view("list", 100.0, |ui| {
+                for index in 0..10 {
+                    ui.button(&format!("item{}", index), "Item");
+                }
+            });
+            ui.finish()
+        };
+
+        let tree = frame(&mut state);
+        let hidden = tree.find("item5").unwrap().rect;
+        assert!(tree
+            .hit_test(hidden.x + 1.0, hidden.y + 1.0)
+            .is_none_or(|n| n.id != "item5"));
+        let WidgetKind::ScrollView { content_height, .. } = tree.find("list").unwrap().kind else {
+            panic!("expected a scroll view");
+        };
+        assert_eq!(content_height, 10.0 * BUTTON_HEIGHT + 9.0 * SPACING);
+
+        state.focus("item5");
+        frame(&mut state);
+        let tree = frame(&mut state);
+        let shown = tree.find("item5").unwrap().rect;
+        let view = tree.find("list").unwrap().rect;
+        assert_eq!(shown.bottom(), view.bottom());
+        assert_eq!(
+            tree.hit_test(shown.x + 1.0, shown.y + 1.0).unwrap().id,
+            "item5"
+        );
+
+        let offset = state.scroll_offset("list");
+        key(&mut state, "PageDown");
+        frame(&mut state);
+        assert_eq!(state.scroll_offset("list"), offset + SCROLL_STEP);
+    }
+
+    #[test]
+    fn test_code_editor_takes_every_key_until_escape() {
+        let mut state = UiState::new();
+        let mut editor = CodeEditor::new("fn main() {\n}");
+        let frame = |state: &mut UiState, editor: &mut CodeEditor| {
+            let mut ui = state.frame(400.0, 300.0);
+            ui.code_editor("code", editor);
+            ui.button("run", "Run");
+            ui.finish()
+        };
+
+        let tree = frame(&mut state, &mut editor);
+        let code = tree.find("code").unwrap().rect;
+        assert_eq!(code.height, 2.0 * LINE_HEIGHT + 2.0 * PADDING);
+        state.handle_input(&InputEvent::MouseClick {
+            x: code.x + 2.0,
+            y: code.y + 2.0,
+        });
+        frame(&mut state, &mut editor);
+        assert!(state.captures_keys());
+
+        for typed in ["ArrowLeft", "Tab", "x", "ArrowDown"] {
+            key(&mut state, typed);
+        }
+        let tree = frame(&mut state, &mut editor);
+        assert_eq!(editor.text(), "fn main() {\n    x}");
+        assert_eq!(state.focused(), Some("code"));
+        assert_eq!(
+            tree.find("code").unwrap().kind,
+            WidgetKind::CodeEditor {
+                lines: vec!["fn main() {".to_string(), "    x}".to_string()],
+                cursor: (1, 5),
+            }
+        );
+
+        key(&mut state, "Escape");
+        frame(&mut state, &mut editor);
+        assert_eq!(editor.text(), "fn main() {\n    x}");
+        assert_eq!(state.focused(), None);
+        assert!(!state.captures_keys());
+    }
+
+    #[test]
+    fn test_placed_widgets_do_not_overlap() {
+        let mut state = UiState::new();
+        let mut ui = state.frame(400.0, 300.0);
+        ui.place(40.0, 100.0, |ui| {
+            ui.button("far", "Far");
+        });
+        ui.place(20.0, 0.0, |ui| {
+            ui.button("pushed", "Pushed");
+        });
+        ui.button("after", "After");
+        let tree = ui.finish();
+
+        let far = tree.find("far").unwrap().rect;
+        assert_eq!((far.x, far.y), (PADDING + 40.0, PADDING + 100.0));
+        assert_eq!(far.right(), 400.0 - PADDING);
+        let pushed = tree.find("pushed").unwrap().rect;
+        assert_eq!(
+            (pushed.x, pushed.y),
+            (PADDING + 20.0, far.bottom() + SPACING)
+        );
+        let after = tree.find("after").unwrap().rect;
+        assert_eq!((after.x, after.y), (PADDING, pushed.bottom() + SPACING));
+    }
+
+    #[test]
+    fn test_render_through_graphics_engine() {
+        let mut state = UiState::new();
+        let (tree, _) = menu(&mut state, &mut true, &mut "Sam".to_string());
+        let mut graphics = GraphicsEngine::headless(400, 300);
+
+        tree.render(&mut graphics);
+        graphics.render().unwrap();
+
+        let texts: Vec<&str> = graphics
+            .last_frame()
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Main menu",
+                "Welcome to Learn Liberty",
+                "Start",
+                "Audio",
+                "Sam"
+            ]
+        );
+        assert_eq!(graphics.last_frame().last(), Some(&DrawCommand::Clip(None)));
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("one two three", 9.0 * CHAR_WIDTH),
+            vec!["one two", "three"]
+        );
+        assert_eq!(wrap("abcdefgh", 3.0 * CHAR_WIDTH), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap("a\n\nb", 100.0), vec!["a", "", "b"]);
+    }
+}
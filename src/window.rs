@@ -4,11 +4,36 @@
 //! for the educational RPG application.
 
 use winit::{
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent},
     event_loop::EventLoop,
+    keyboard::Key,
     window::WindowBuilder,
 };
 
+/// Size in logical pixels that lesson and UI layouts are authored for
+pub const DESIGN_RESOLUTION: (u32, u32) = (800, 600);
+
+/// Input collected from the window between two updates
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    Focus {
+        focused: bool,
+    },
+    KeyPress {
+        key: String,
+    },
+    MouseClick {
+        x: f32,
+        y: f32,
+    },
+    /// The user asked to close the window
+    CloseRequested,
+}
+
 pub struct WindowManager {
     window: winit::window::Window,
     event_loop: EventLoop<()>,
@@ -19,7 +44,10 @@ impl WindowManager {
         let event_loop = EventLoop::new().unwrap();
         let window = WindowBuilder::new()
             .with_title(title)
-            .with_inner_size(winit::dpi::LogicalSize::new(800, 600))
+            .with_inner_size(winit::dpi::LogicalSize::new(
+                DESIGN_RESOLUTION.0,
+                DESIGN_RESOLUTION.1,
+            ))
             .with_resizable(true)
             .build(&event_loop)
             .unwrap();
@@ -27,19 +55,58 @@ impl WindowManager {
         Self { window, event_loop }
     }
 
+    /// Run the event loop. `update` receives the input gathered since the
+    /// previous call and returns `false` to close the window. Closing the
+    /// window is reported as `InputEvent::CloseRequested` so the game can
+    /// save before it returns `false`.
     pub fn run<F>(self, mut update: F)
     where
-        F: FnMut(&winit::window::Window) + 'static,
+        F: FnMut(&winit::window::Window, Vec<InputEvent>) -> bool + 'static,
     {
+        let mut pending = Vec::new();
+        let mut cursor = (0.0, 0.0);
+
         let _ = self.event_loop.run(move |event, elwt| match event {
-            Event::WindowEvent {
-                event: WindowEvent::CloseRequested,
-                ..
-            } => {
-                elwt.exit();
-            }
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => pending.push(InputEvent::CloseRequested),
+                WindowEvent::Resized(size) => pending.push(InputEvent::Resize {
+                    width: size.width,
+                    height: size.height,
+                }),
+                WindowEvent::Focused(focused) => pending.push(InputEvent::Focus { focused }),
+                WindowEvent::CursorMoved { position, .. } => {
+                    cursor = (position.x as f32, position.y as f32);
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                } => pending.push(InputEvent::MouseClick {
+                    x: cursor.0,
+                    y: cursor.1,
+                }),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key,
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => {
+                    let key = match logical_key {
+                        Key::Named(named) => format!("{:?}", named),
+                        Key::Character(text) => text.to_string(),
+                        _ => return,
+                    };
+                    pending.push(InputEvent::KeyPress { key });
+                }
+                _ => (),
+            },
             Event::AboutToWait => {
-                update(&self.window);
+                if !update(&self.window, std::mem::take(&mut pending)) {
+                    elwt.exit();
+                }
                 self.window.request_redraw();
             }
             _ => (),
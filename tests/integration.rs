@@ -221,3 +221,206 @@ fn test_memory_usage() {
     assert_eq!(lessons[0].media.len(), 2);
     assert_eq!(lessons[999].media.len(), 2);
 }
+
+/// Start a local HTTP server that answers each request with the next
+/// status in `statuses` and records the raw requests it received
+async fn mock_lrs(statuses: Vec<u16>) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}/xapi", listener.local_addr().unwrap());
+    let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let received = requests.clone();
+
+    tokio::spawn(async move {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length: usize = text
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .and_then(|length| length.trim().parse().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            received
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&request).to_string());
+            let response = format!("HTTP/1.1 {} Mock\r\nContent-Length: 0\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (endpoint, requests)
+}
+
+fn lrs_sync(endpoint: &str, name: &str) -> learn_liberty_app::LrsSync {
+    use learn_liberty_app::config::LrsSettings;
+    use learn_liberty_app::lrs::Backoff;
+    use learn_liberty_app::{LrsClient, StatementQueue};
+    use std::time::Duration;
+
+    let queue_path = std::env::temp_dir().join(format!(
+        "learn_liberty_lrs_{}_{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&queue_path);
+    let client = LrsClient::new(&LrsSettings {
+        endpoint: endpoint.to_string(),
+        username: "key".to_string(),
+        password: "secret".to_string(),
+        timeout_secs: 5,
+        base_iri: "https://learnliberty.example".to_string(),
+    })
+    .unwrap();
+    learn_liberty_app::LrsSync::new(client, StatementQueue::new(queue_path))
+        .with_backoff(Backoff::new(Duration::ZERO, Duration::ZERO))
+}
+
+/// Test that queued statements reach the LRS with basic auth
+#[tokio::test]
+async fn test_lrs_sync_posts_statements() {
+    use learn_liberty_app::analytics::{Actor, LearningEvent};
+
+    let (endpoint, requests) = mock_lrs(vec![200]).await;
+    let mut sync = lrs_sync(&endpoint, "posts");
+    let statement = LearningEvent::lesson_started(&Actor::guest(), "lesson_1", 0)
+        .to_xapi("https://learnliberty.example");
+
+    sync.enqueue(&[statement]).unwrap();
+    assert_eq!(sync.flush().await.unwrap(), 1);
+    assert!(sync.queue.is_empty().unwrap());
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("POST /xapi/statements HTTP/1.1"));
+    assert!(requests[0].contains("Authorization: Basic a2V5OnNlY3JldA=="));
+    assert!(requests[0].contains("X-Experience-API-Version: 1.0.3"));
+    assert!(requests[0].contains("lesson_1"));
+}
+
+/// Test that statements stay queued while the LRS fails and are sent on retry
+#[tokio::test]
+async fn test_lrs_sync_retries_after_failure() {
+    let (endpoint, requests) = mock_lrs(vec![503, 200]).await;
+    let mut sync = lrs_sync(&endpoint, "retry");
+    sync.batch_size = 2;
+
+    sync.enqueue(&[
+        serde_json::json!({"n": 1}),
+        serde_json::json!({"n": 2}),
+        serde_json::json!({"n": 3}),
+    ])
+    .unwrap();
+    assert!(sync.flush().await.is_err());
+    assert_eq!(sync.queue.len().unwrap(), 3);
+    assert_eq!(sync.backoff.failures(), 1);
+
+    // The mock only answers twice, so the second batch fails to connect
+    // and stays queued
+    assert!(sync.flush().await.is_err());
+    let pending = sync.queue.pending().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["n"], 3);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+/// Test that the background worker delivers statements before it shuts down
+#[tokio::test]
+async fn test_lrs_worker_sends_in_background() {
+    use learn_liberty_app::lrs::LrsWorker;
+    use std::time::Duration;
+
+    let (endpoint, requests) = mock_lrs(vec![200]).await;
+    let sync = lrs_sync(&endpoint, "worker");
+    let queue = learn_liberty_app::StatementQueue::new(sync.queue.path());
+    let worker = LrsWorker::spawn(sync, Duration::from_secs(60));
+
+    worker.send(vec![serde_json::json!({"n": 1})]);
+    worker.shutdown().await;
+
+    assert!(queue.is_empty().unwrap());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].contains("\"id\":"));
+}
+
+/// Test that an unreachable LRS leaves statements queued on disk
+#[tokio::test]
+async fn test_lrs_sync_offline_keeps_queue() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let mut sync = lrs_sync(&endpoint, "offline");
+
+    sync.enqueue(&[serde_json::json!({"n": 1})]).unwrap();
+    assert!(sync.flush().await.is_err());
+    assert_eq!(sync.queue.len().unwrap(), 1);
+}
+
+/// Test that mock window clicks drive the UI toolkit through hit testing
+#[test]
+fn test_ui_hit_testing_with_mock_events() {
+    use learn_liberty_app::tests::MockEvent;
+    use learn_liberty_app::ui::{self, UiState};
+    use learn_liberty_app::{ButtonAction, ElementPayload, InteractiveElement, RichText};
+
+    let (window, event_sender) = MockWindow::new("Learn Liberty", 800, 600);
+    let mut lesson = TestUtils::create_test_lesson();
+    lesson.add_interactive_element(InteractiveElement::new(
+        (0.0, 0.0),
+        ElementPayload::Text(RichText::plain("Welcome")),
+    ));
+    lesson.add_interactive_element(InteractiveElement::new(
+        (0.0, 40.0),
+        ElementPayload::Button {
+            label: "Continue".to_string(),
+            action: ButtonAction::NextSection,
+        },
+    ));
+
+    let mut state = UiState::new();
+    let (width, height) = window.get_size();
+    let frame = |state: &mut UiState| {
+        let mut ui = state.frame(width as f32, height as f32);
+        let clicked = ui::lesson_panel(&mut ui, &lesson, 0.5, 300.0, &[], &mut Default::default());
+        (ui.finish(), clicked)
+    };
+
+    let (tree, clicked) = frame(&mut state);
+    assert_eq!(clicked, None);
+    let button = tree.find("test_lesson_1/element/1").unwrap().rect;
+    let hit = tree.hit_test(button.x + 4.0, button.y + 4.0).unwrap();
+    assert_eq!(hit.id, "test_lesson_1/element/1");
+
+    event_sender
+        .send(MockEvent::MouseClick {
+            x: button.x + 4.0,
+            y: button.y + 4.0,
+        })
+        .unwrap();
+    event_sender.send(MockEvent::Close).unwrap();
+    while let Some(event) = window.try_receive_event() {
+        if let Some(input) = event.to_input() {
+            state.handle_input(&input);
+        }
+    }
+
+    let (tree, clicked) = frame(&mut state);
+    assert_eq!(clicked, Some(1));
+    assert_eq!(state.focused(), Some("test_lesson_1/element/1"));
+    assert!(tree.find("test_lesson_1/element/1").unwrap().focused);
+}